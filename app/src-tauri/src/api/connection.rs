@@ -1,6 +1,6 @@
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{fs, io, vec};
 
 use base64::prelude::BASE64_STANDARD;
@@ -12,14 +12,22 @@ use crate::error::LogicError;
 use crate::etcd;
 use crate::etcd::etcd_connector::EtcdConnector;
 use crate::etcd::etcd_connector_handler::EtcdConnectorHandler;
-use crate::transport::connection::{Connection, ConnectionInfo, KeyMonitorConfig, SessionData};
-use crate::utils::{aes_util, file_util, md5};
+use crate::etcd::offline_session::OfflineSession;
+use crate::etcd::snapshot_scheduler;
+use crate::ssh::{interactive, known_hosts};
+use crate::transport::connection::{
+    Connection, ConnectionInfo, ConnectionTls, KeyMonitorConfig, SessionData, TlsInspection,
+};
+use crate::transport::maintenance::OfflineSessionData;
+use crate::utils::{aes_util, file_util, md5, tls_util};
 
 use super::settings::get_settings;
 
 #[tauri::command]
-pub async fn connect_test(connection: Connection) -> Result<(), LogicError> {
-    let mut connector = EtcdConnector::new(connection, EtcdConnectorHandler::default()).await?;
+pub async fn connect_test(app_handle: AppHandle, connection: Connection) -> Result<(), LogicError> {
+    //  会话ID为0的断开事件会被界面忽略，这里只需要 app_handle 用于确认 ssh 公钥
+    let handler = EtcdConnectorHandler::new(app_handle, 0);
+    let mut connector = EtcdConnector::new(connection, handler).await?;
     connector.test_connection().await?;
     Ok(())
 }
@@ -42,6 +50,52 @@ pub async fn disconnect(session: i32) -> Result<(), LogicError> {
     Ok(())
 }
 
+/// 解析 tls 配置中的证书，用于在界面中展示证书信息
+#[tauri::command]
+pub fn inspect_tls_certificates(tls: ConnectionTls) -> Result<TlsInspection, LogicError> {
+    tls_util::inspect(&tls)
+}
+
+/// 回复 ssh 公钥确认事件
+#[tauri::command]
+pub fn ssh_host_key_answer(request_id: u32, trust: bool) -> Result<(), LogicError> {
+    known_hosts::answer(request_id, trust)
+}
+
+/// 回复 ssh keyboard-interactive 认证的输入请求，`answers` 为空表示取消认证
+#[tauri::command]
+pub fn ssh_auth_answer(request_id: u32, answers: Option<Vec<String>>) -> Result<(), LogicError> {
+    interactive::answer(request_id, answers)
+}
+
+/// 从 workbench 的 known_hosts 中删除主机的公钥，用于服务器更换公钥后重新信任
+#[tauri::command]
+pub fn ssh_known_hosts_remove(host: String, port: u16) -> Result<usize, LogicError> {
+    known_hosts::forget(&host, port)
+}
+
+/// 以只读方式打开本地快照文件，返回的会话ID可用于读取 key 的命令
+#[tauri::command]
+pub async fn offline_session_open(filepath: String) -> Result<OfflineSessionData, LogicError> {
+    let session =
+        tauri::async_runtime::spawn_blocking(move || OfflineSession::open(PathBuf::from(filepath)))
+            .await
+            .map_err(|e| LogicError::MsgError(e.to_string()))??;
+
+    let id = etcd::new_offline_session(session);
+    let data = etcd::get_offline_session(&id)
+        .map(|session| session.session_data(id))
+        .ok_or(LogicError::ConnectionLose)?;
+    info!("New offline session: {}", data.id);
+    Ok(data)
+}
+
+#[tauri::command]
+pub fn offline_session_close(session: i32) -> Result<(), LogicError> {
+    etcd::remove_offline_session(&session);
+    Ok(())
+}
+
 pub fn restore_connections(old_key: &[u8], new_key: &[u8]) -> io::Result<()> {
     let dir = file_util::get_conn_config_dir_path();
     if dir.exists() {
@@ -72,7 +126,15 @@ pub fn restore_connections(old_key: &[u8], new_key: &[u8]) -> io::Result<()> {
 
 /// 保存连接信息，继承其他配置项
 #[tauri::command]
-pub async fn save_connection(name: String, connection: Connection) -> Result<(), LogicError> {
+pub async fn save_connection(
+    app_handle: AppHandle,
+    name: String,
+    connection: Connection,
+) -> Result<(), LogicError> {
+    if let Some(tls) = &connection.tls {
+        tls_util::validate(tls)?;
+    }
+
     let mut dir = file_util::get_conn_config_dir_path();
     let key = get_settings().await?.connection_conf_encrypt_key;
 
@@ -81,6 +143,8 @@ pub async fn save_connection(name: String, connection: Connection) -> Result<(),
         connection,
         key_collection: vec![],
         key_monitor_list: vec![],
+        compaction_policy: None,
+        snapshot_schedule: None,
     };
     let file_name = md5(&connection_info.name);
     dir.push(file_name);
@@ -94,6 +158,8 @@ pub async fn save_connection(name: String, connection: Connection) -> Result<(),
             if let Ok(info) = serde_json::from_slice::<ConnectionInfo>(data.as_slice()) {
                 connection_info.key_collection = info.key_collection;
                 connection_info.key_monitor_list = info.key_monitor_list;
+                connection_info.compaction_policy = info.compaction_policy;
+                connection_info.snapshot_schedule = info.snapshot_schedule;
             }
         }
 
@@ -107,6 +173,11 @@ pub async fn save_connection(name: String, connection: Connection) -> Result<(),
 
     file.write_all(data.as_slice())?;
 
+    //  定时备份使用新的连接配置
+    if connection_info.snapshot_schedule.is_some() {
+        etcd::set_snapshot_scheduler(app_handle, &connection_info);
+    }
+
     Ok(())
 }
 
@@ -143,6 +214,9 @@ pub fn remove_connection(name: String) -> Result<(), LogicError> {
     if dir.exists() {
         fs::remove_file(dir)?;
     }
+
+    etcd::remove_snapshot_scheduler(&name);
+    snapshot_scheduler::remove_state(&name);
     Ok(())
 }
 
@@ -231,7 +305,7 @@ pub async fn export_connection(filepath: String) -> Result<(), LogicError> {
 }
 
 #[tauri::command]
-pub async fn import_connection(filepath: String) -> Result<(), LogicError> {
+pub async fn import_connection(app_handle: AppHandle, filepath: String) -> Result<(), LogicError> {
     let path = Path::new(&filepath);
     if !path.exists() {
         return Err(LogicError::ResourceNotExist("File not exists"));
@@ -248,7 +322,8 @@ pub async fn import_connection(filepath: String) -> Result<(), LogicError> {
     let list = serde_json::from_slice::<Vec<ConnectionInfo>>(s.as_slice())?;
 
     for info in list {
-        save_connection_info(info).await?;
+        save_connection_info(info.clone()).await?;
+        etcd::set_snapshot_scheduler(app_handle.clone(), &info);
     }
 
     Ok(())
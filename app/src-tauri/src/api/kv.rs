@@ -1,9 +1,10 @@
 use crate::api::settings::get_settings;
 use crate::error::LogicError;
 use crate::etcd;
+use crate::etcd::v2_client::{self, V2Client};
 use crate::transport::event::{KVBatchImportAndExportEvent, KVRenameDirEvent};
 use crate::transport::kv::{
-    KVPutResult, PutStrategy, RenameAction, SearchResult, SerializableKeyValue,
+    KVPutResult, PutStrategy, RenameAction, SearchResult, SerializableKeyValue, V2Node,
 };
 use crate::utils::{hex_to_vec, vec_to_hex};
 use etcd_client::{GetOptions, PutOptions};
@@ -30,10 +31,15 @@ const BATCH_IMPORT_START_EVENT: &str = "batchImportStartEvent";
 const BATCH_IMPORT_END_EVENT: &str = "batchImportEndEvent";
 const BATCH_IMPORT_ERR_EVENT: &str = "batchImportErrEvent";
 
+const V2_MIGRATE_EVENT: &str = "v2MigrateEvent";
+const V2_MIGRATE_START_EVENT: &str = "v2MigrateStartEvent";
+const V2_MIGRATE_END_EVENT: &str = "v2MigrateEndEvent";
+const V2_MIGRATE_ERR_EVENT: &str = "v2MigrateErrEvent";
+
 #[tauri::command]
 pub async fn kv_get_all_keys(session: i32) -> Result<Vec<SerializableKeyValue>, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
-    let keys = connector.kv_get_all_keys().await?;
+    let mut reader = etcd::get_kv_reader(&session)?;
+    let keys = reader.kv_get_all_keys().await?;
     Ok(keys)
 }
 
@@ -54,17 +60,22 @@ pub async fn kv_get(
     key: String,
     key_bytes: Option<Vec<u8>>,
 ) -> Result<SerializableKeyValue, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
+    let mut reader = etcd::get_kv_reader(&session)?;
     let k = if let Some(key_bytes) = key_bytes {
         key_bytes
     } else {
         key.into()
     };
-    let mut kv = connector.kv_get(k).await?;
+    let mut kv = reader.kv_get(k).await?;
+    drop(reader);
+
+    //  离线会话没有租约信息
     if kv.lease.ne("0") {
-        let lease_id = i64::from_str(kv.lease.as_str()).unwrap();
-        let info = connector.lease_get_simple_info(lease_id).await?;
-        kv.lease_info = Some(info)
+        if let Some(mut connector) = etcd::get_connector_optional(&session) {
+            let lease_id = i64::from_str(kv.lease.as_str()).unwrap();
+            let info = connector.lease_get_simple_info(lease_id).await?;
+            kv.lease_info = Some(info)
+        }
     }
     Ok(kv)
 }
@@ -76,14 +87,14 @@ pub async fn kv_get_by_version(
     key_bytes: Option<Vec<u8>>,
     version: i64,
 ) -> Result<SerializableKeyValue, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
+    let mut reader = etcd::get_kv_reader(&session)?;
     let k = if let Some(key_bytes) = key_bytes {
         key_bytes
     } else {
         key.into()
     };
 
-    let kv = connector.kv_get_by_version(k, version).await?;
+    let kv = reader.kv_get_by_version(k, version).await?;
     Ok(kv)
 }
 
@@ -95,13 +106,13 @@ pub async fn kv_get_history_versions(
     start: i64,
     end: i64,
 ) -> Result<Vec<i64>, LogicError> {
-    let mut connector = etcd::get_connector(&session)?;
+    let mut reader = etcd::get_kv_reader(&session)?;
     let k = if let Some(key_bytes) = key_bytes {
         key_bytes
     } else {
         key.into()
     };
-    let versions = connector.kv_get_history_versions(k, start, end).await?;
+    let versions = reader.kv_get_history_versions(k, start, end).await?;
     Ok(versions)
 }
 
@@ -548,3 +559,102 @@ async fn batch_import(
 
     Ok(())
 }
+
+async fn get_v2_client(session: i32) -> Result<V2Client, LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    connector.v2_client().await
+}
+
+/// 读取 v2 存储中的节点，目录只返回直接子节点
+#[tauri::command]
+pub async fn kv_v2_get(session: i32, key: String) -> Result<V2Node, LogicError> {
+    let client = get_v2_client(session).await?;
+    client.get(&key, false).await
+}
+
+/// 将 v2 存储中 `key` 下的所有值导出为批量导入所用的文件格式
+///
+/// 导出的 key 保留 v2 中的完整路径，返回导出的条目数
+#[tauri::command]
+pub async fn kv_v2_export(
+    session: i32,
+    key: String,
+    target_path: String,
+) -> Result<usize, LogicError> {
+    let path = Path::new(&target_path);
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent).await?;
+        }
+    }
+
+    let client = get_v2_client(session).await?;
+    let values = v2_client::flatten_values(client.get(&key, true).await?);
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(target_path)
+        .await?;
+    file.write_all(v2_client::export_content(&values).as_bytes())
+        .await?;
+    file.flush().await?;
+
+    Ok(values.len())
+}
+
+/// 将 v2 存储中 `key` 下的所有值写入 v3 的 `prefix` 前缀下，带有 TTL 的值会绑定新的租约
+#[tauri::command]
+pub async fn kv_v2_migrate(
+    app_handle: AppHandle,
+    session: i32,
+    key: String,
+    prefix: String,
+) -> Result<(), LogicError> {
+    let client = get_v2_client(session).await?;
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = v2_migrate(&app_handle, session, client, key, prefix).await {
+            log::error!("v2 migrate error: {:?}", e);
+            let _ = app_handle.emit_to("main", V2_MIGRATE_ERR_EVENT, format!("{:?}", e));
+        }
+    });
+
+    Ok(())
+}
+
+async fn v2_migrate(
+    app_handle: &AppHandle,
+    session: i32,
+    client: V2Client,
+    key: String,
+    prefix: String,
+) -> Result<(), LogicError> {
+    let values = v2_client::flatten_values(client.get(&key, true).await?);
+    let _ = app_handle.emit_to("main", V2_MIGRATE_START_EVENT, values.len());
+
+    let mut connector = etcd::get_connector(&session)?;
+    for node in values {
+        let target_key = v2_client::migrate_key(&prefix, &node.key).into_bytes();
+        let ttl = v2_client::migrate_ttl(&node);
+        let value = node.value.unwrap_or_default();
+        let event = match connector.kv_put(target_key.clone(), value, ttl).await {
+            Ok(_) => KVBatchImportAndExportEvent {
+                success: true,
+                key: Some(target_key),
+                failed_msg: None,
+            },
+            Err(e) => KVBatchImportAndExportEvent {
+                success: false,
+                key: Some(target_key),
+                failed_msg: Some(e.to_string()),
+            },
+        };
+        let _ = app_handle.emit_to("main", V2_MIGRATE_EVENT, event);
+    }
+
+    let _ = app_handle.emit_to("main", V2_MIGRATE_END_EVENT, ());
+
+    Ok(())
+}
@@ -1,17 +1,49 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
 
+use crate::api::connection::{get_connection, get_connection_list, save_connection_info};
+use crate::api::settings::get_settings;
 use crate::error::LogicError;
 use crate::etcd;
-use crate::etcd::etcd_connector::SnapshotTask;
+use crate::etcd::etcd_connector::{EtcdConnector, SnapshotTask};
+use crate::etcd::{health_probe, snapshot, snapshot_restore, snapshot_scheduler};
+use crate::transport::connection::{CompactionPolicy, SnapshotSchedule};
+use crate::transport::event::{MemberDefragmentEvent, NospaceRecoverEvent, NospaceRecoverStep};
 use crate::transport::maintenance::{
-    SerializableCluster, SnapshotInfo, SnapshotState, SnapshotStateEvent,
+    ClusterHealth, MaintenanceLog, SerializableAlarm, SerializableCluster,
+    SerializableClusterMember, SnapshotInfo, SnapshotRestoreOptions, SnapshotScheduleState,
+    SnapshotState, SnapshotStateEvent, SnapshotVerifyResult,
 };
+use crate::transport::metrics::{MemberMetrics, MetricFamily, MetricSeries, SshTunnelStats};
+use crate::utils::{file_util, md5};
 use dashmap::DashMap;
+use etcd_client::AlarmType;
 use lazy_static::lazy_static;
-use tauri::Manager;
+use log::warn;
+use tauri::{AppHandle, Manager};
 use tokio::sync::{mpsc, oneshot};
 
+/// 每个连接最多保留的维护日志条数
+const MAINTENANCE_LOG_LIMIT: usize = 500;
+
+const DEFRAGMENT_MEMBER_EVENT: &str = "defragmentMemberEvent";
+const DEFRAGMENT_MEMBER_START_EVENT: &str = "defragmentMemberStartEvent";
+const DEFRAGMENT_MEMBER_END_EVENT: &str = "defragmentMemberEndEvent";
+const DEFRAGMENT_MEMBER_ERR_EVENT: &str = "defragmentMemberErrEvent";
+
+const NOSPACE_RECOVER_EVENT: &str = "nospaceRecoverEvent";
+const NOSPACE_RECOVER_START_EVENT: &str = "nospaceRecoverStartEvent";
+const NOSPACE_RECOVER_END_EVENT: &str = "nospaceRecoverEndEvent";
+const NOSPACE_RECOVER_ERR_EVENT: &str = "nospaceRecoverErrEvent";
+
+const SNAPSHOT_RESTORE_EVENT: &str = "snapshotRestoreEvent";
+const SNAPSHOT_RESTORE_START_EVENT: &str = "snapshotRestoreStartEvent";
+const SNAPSHOT_RESTORE_END_EVENT: &str = "snapshotRestoreEndEvent";
+const SNAPSHOT_RESTORE_ERR_EVENT: &str = "snapshotRestoreErrEvent";
+
 #[allow(unused)]
 static SNAPSHOT_TASK_ID_COUNTER: AtomicI32 = AtomicI32::new(1);
 lazy_static! {
@@ -32,6 +64,97 @@ pub async fn maintenance_defragment(session: i32) -> Result<(), LogicError> {
     Ok(())
 }
 
+/// 对选中的成员节点逐个进行碎片整理，`member_ids` 为空时整理所有成员
+///
+/// 滚动模式下先整理 follower 最后整理 leader，每个节点整理完成后等待其恢复健康再继续下一个，
+/// 任一节点失败都将终止后续整理
+#[tauri::command]
+pub async fn maintenance_defragment_members(
+    app_handle: AppHandle,
+    session: i32,
+    member_ids: Vec<String>,
+    rolling: bool,
+) -> Result<(), LogicError> {
+    let c = etcd::get_connector(&session)?;
+    drop(c);
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = defragment_members(&app_handle, session, member_ids, rolling).await {
+            log::error!("defragment members error: {:?}", e);
+            let _ = app_handle.emit_to("main", DEFRAGMENT_MEMBER_ERR_EVENT, format!("{:?}", e));
+        }
+    });
+
+    Ok(())
+}
+
+async fn defragment_members(
+    app_handle: &AppHandle,
+    session: i32,
+    member_ids: Vec<String>,
+    rolling: bool,
+) -> Result<(), LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    let cluster = connector.cluster_get().await?;
+    let leader = cluster.status.leader.clone();
+
+    let mut members: Vec<SerializableClusterMember> = cluster
+        .members
+        .into_iter()
+        .filter(|m| member_ids.is_empty() || member_ids.contains(&m.id))
+        .collect();
+    if rolling {
+        //  false < true，leader 排在最后
+        members.sort_by_key(|m| m.id == leader);
+    }
+
+    let _ = app_handle.emit_to("main", DEFRAGMENT_MEMBER_START_EVENT, members.len());
+
+    let health_timeout = Duration::from_secs(get_settings().await?.request_timeout_seconds);
+    for member in members {
+        let is_leader = member.id == leader;
+        let mut event = match connector
+            .maintenance_defragment_member(member.client_uri.clone())
+            .await
+        {
+            Ok(result) => MemberDefragmentEvent {
+                member_id: member.id.clone(),
+                leader: is_leader,
+                result: Some(result),
+                success: true,
+                failed_msg: None,
+            },
+            Err(e) => MemberDefragmentEvent {
+                member_id: member.id.clone(),
+                leader: is_leader,
+                result: None,
+                success: false,
+                failed_msg: Some(format!("{:?}", e)),
+            },
+        };
+
+        if rolling && event.success {
+            if let Err(e) = connector
+                .wait_member_healthy(member.client_uri.clone(), health_timeout)
+                .await
+            {
+                event.success = false;
+                event.failed_msg = Some(format!("Member is unhealthy after defragment: {:?}", e));
+            }
+        }
+
+        let success = event.success;
+        let _ = app_handle.emit_to("main", DEFRAGMENT_MEMBER_EVENT, event);
+        if !success {
+            break;
+        }
+    }
+
+    let _ = app_handle.emit_to("main", DEFRAGMENT_MEMBER_END_EVENT, ());
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn maintenance_compact(
     session: i32,
@@ -43,6 +166,274 @@ pub async fn maintenance_compact(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn alarm_list(session: i32) -> Result<Vec<SerializableAlarm>, LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    let alarms = connector.alarm_list().await?;
+    Ok(alarms)
+}
+
+#[tauri::command]
+pub async fn alarm_disarm(
+    session: i32,
+    member_id: String,
+    alarm_type: i32,
+) -> Result<(), LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    connector.alarm_disarm(member_id, alarm_type).await?;
+    Ok(())
+}
+
+/// 从 NOSPACE 报警中恢复：压缩到当前版本 -> 逐个成员碎片整理 -> 解除 NOSPACE 报警
+#[tauri::command]
+pub async fn maintenance_recover_nospace(
+    app_handle: AppHandle,
+    session: i32,
+) -> Result<(), LogicError> {
+    let c = etcd::get_connector(&session)?;
+    drop(c);
+
+    tauri::async_runtime::spawn(async move {
+        //  步骤失败时提前结束也需要发送结束事件，避免界面一直等待
+        match recover_nospace(&app_handle, session).await {
+            Ok(_) => {
+                let _ = app_handle.emit_to("main", NOSPACE_RECOVER_END_EVENT, ());
+            }
+            Err(e) => {
+                log::error!("recover nospace error: {:?}", e);
+                let _ = app_handle.emit_to("main", NOSPACE_RECOVER_ERR_EVENT, format!("{:?}", e));
+            }
+        }
+    });
+
+    Ok(())
+}
+
+async fn recover_nospace(app_handle: &AppHandle, session: i32) -> Result<(), LogicError> {
+    let mut connector = etcd::get_connector(&session)?;
+    let cluster = connector.cluster_get().await?;
+
+    //  总步骤数：压缩 + 每个成员的碎片整理 + 解除报警
+    let _ = app_handle.emit_to(
+        "main",
+        NOSPACE_RECOVER_START_EVENT,
+        cluster.members.len() + 2,
+    );
+
+    let compact_result = connector.maintenance_compact(cluster.revision, true).await;
+    let event = match compact_result {
+        Ok(_) => NospaceRecoverEvent {
+            step: NospaceRecoverStep::Compact,
+            member_id: None,
+            success: true,
+            failed_msg: None,
+        },
+        //  已经压缩到该版本，无需再次压缩
+        Err(e) if etcd::is_compacted_error(&e) => {
+            warn!("revision {} has been compacted, skip it", cluster.revision);
+            NospaceRecoverEvent {
+                step: NospaceRecoverStep::Compact,
+                member_id: None,
+                success: true,
+                failed_msg: None,
+            }
+        }
+        Err(e) => NospaceRecoverEvent {
+            step: NospaceRecoverStep::Compact,
+            member_id: None,
+            success: false,
+            failed_msg: Some(e.to_string()),
+        },
+    };
+    let success = event.success;
+    let _ = app_handle.emit_to("main", NOSPACE_RECOVER_EVENT, event);
+    if !success {
+        return Ok(());
+    }
+
+    let leader = cluster.status.leader.clone();
+    let mut members = cluster.members;
+    members.sort_by_key(|m| m.id == leader);
+
+    for member in members {
+        let event = match connector
+            .maintenance_defragment_member(member.client_uri.clone())
+            .await
+        {
+            Ok(_) => NospaceRecoverEvent {
+                step: NospaceRecoverStep::Defragment,
+                member_id: Some(member.id),
+                success: true,
+                failed_msg: None,
+            },
+            Err(e) => NospaceRecoverEvent {
+                step: NospaceRecoverStep::Defragment,
+                member_id: Some(member.id),
+                success: false,
+                failed_msg: Some(format!("{:?}", e)),
+            },
+        };
+        let success = event.success;
+        let _ = app_handle.emit_to("main", NOSPACE_RECOVER_EVENT, event);
+        if !success {
+            return Ok(());
+        }
+    }
+
+    let alarms = connector.alarm_list().await?;
+    for alarm in alarms {
+        if alarm.alarm_type != AlarmType::Nospace as i32 {
+            continue;
+        }
+        let member_id = alarm.member_id.clone();
+        let event = match connector
+            .alarm_disarm(alarm.member_id, alarm.alarm_type)
+            .await
+        {
+            Ok(_) => NospaceRecoverEvent {
+                step: NospaceRecoverStep::Disarm,
+                member_id: Some(member_id),
+                success: true,
+                failed_msg: None,
+            },
+            Err(e) => NospaceRecoverEvent {
+                step: NospaceRecoverStep::Disarm,
+                member_id: Some(member_id),
+                success: false,
+                failed_msg: Some(e.to_string()),
+            },
+        };
+        let _ = app_handle.emit_to("main", NOSPACE_RECOVER_EVENT, event);
+    }
+
+    Ok(())
+}
+
+/// 设置会话的自动压缩策略，策略保存在连接配置中，仅支持已保存的连接
+#[tauri::command]
+pub async fn set_compaction_policy(
+    session: i32,
+    policy: Option<CompactionPolicy>,
+) -> Result<(), LogicError> {
+    let result = etcd::get_connection_info_optional(&session).map(|mut info| {
+        info.compaction_policy = policy.clone();
+        info.value().clone()
+    });
+    if let Some(info) = result {
+        save_connection_info(info.clone()).await?;
+
+        etcd::set_auto_compactor(session, info.name, policy);
+        Ok(())
+    } else {
+        Err(LogicError::ResourceNotExist(
+            "The connection has not been saved",
+        ))
+    }
+}
+
+/// 设置已保存连接的定时快照备份，不依赖会话，应用重启后会自动恢复
+#[tauri::command]
+pub async fn set_snapshot_schedule(
+    app: AppHandle,
+    name: String,
+    schedule: Option<SnapshotSchedule>,
+) -> Result<(), LogicError> {
+    let mut info = get_connection(name.clone())
+        .await?
+        .ok_or(LogicError::ResourceNotExist(
+            "The connection has not been saved",
+        ))?;
+    let was_enabled = info
+        .snapshot_schedule
+        .as_ref()
+        .is_some_and(|schedule| schedule.enabled);
+    info.snapshot_schedule = schedule.clone();
+    save_connection_info(info.clone()).await?;
+
+    if let Some(schedule) = &schedule {
+        if schedule.enabled && !was_enabled {
+            snapshot_scheduler::reset_next_run(&name, schedule);
+        }
+    }
+
+    etcd::update_cached_connection_info(&name, |cached| {
+        cached.snapshot_schedule = schedule.clone();
+    });
+    etcd::set_snapshot_scheduler(app, &info);
+    Ok(())
+}
+
+/// 读取定时快照备份的状态和历史记录
+#[tauri::command]
+pub fn snapshot_schedule_state(name: String) -> Result<SnapshotScheduleState, LogicError> {
+    snapshot_scheduler::load_state(&name)
+}
+
+/// 应用启动时恢复所有已保存连接的定时快照备份
+pub async fn restore_snapshot_schedules(app: AppHandle) -> Result<(), LogicError> {
+    for info in get_connection_list().await? {
+        etcd::set_snapshot_scheduler(app.clone(), &info);
+    }
+    Ok(())
+}
+
+/// 读取连接的维护日志，按时间先后排列
+#[tauri::command]
+pub fn maintenance_log_list(name: String) -> Result<Vec<MaintenanceLog>, LogicError> {
+    read_maintenance_logs(&name)
+}
+
+fn get_maintenance_log_path(connection_name: &String) -> PathBuf {
+    let mut path = file_util::get_maintenance_log_dir_path();
+    path.push(md5(connection_name));
+    path
+}
+
+fn read_maintenance_logs(connection_name: &String) -> Result<Vec<MaintenanceLog>, LogicError> {
+    let path = get_maintenance_log_path(connection_name);
+    let mut logs = vec![];
+    if !path.exists() {
+        return Ok(logs);
+    }
+
+    let reader = BufReader::new(fs::File::open(path)?);
+    for line in reader.lines() {
+        let line = line?;
+        match serde_json::from_str::<MaintenanceLog>(&line) {
+            Ok(log) => logs.push(log),
+            Err(e) => warn!("Ignored broken maintenance log: {}", e),
+        }
+    }
+    Ok(logs)
+}
+
+/// 追加一条维护日志，超过 [`MAINTENANCE_LOG_LIMIT`] 时移除最早的日志
+pub fn append_maintenance_log(
+    connection_name: &String,
+    log: MaintenanceLog,
+) -> Result<(), LogicError> {
+    let mut logs = read_maintenance_logs(connection_name)?;
+    logs.push(log);
+
+    let path = get_maintenance_log_path(connection_name);
+    if logs.len() > MAINTENANCE_LOG_LIMIT {
+        logs.drain(0..logs.len() - MAINTENANCE_LOG_LIMIT);
+
+        let mut content = String::new();
+        for log in logs {
+            content.push_str(serde_json::to_string(&log)?.as_str());
+            content.push('\n');
+        }
+        fs::write(path, content)?;
+    } else {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let line = serde_json::to_string(logs.last().unwrap())?;
+        file.write_all(line.as_bytes())?;
+        file.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn maintenance_create_snapshot_task(
     app: tauri::AppHandle,
@@ -50,11 +441,24 @@ pub async fn maintenance_create_snapshot_task(
     filepath: String,
 ) -> Result<SnapshotInfo, LogicError> {
     let mut connector = etcd::get_connector(&session)?;
-    let id = SNAPSHOT_TASK_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
+    let (info, _) = start_snapshot_task(app, &mut connector, PathBuf::from(filepath)).await?;
+    Ok(info)
+}
 
-    let task_id = id.clone();
+/// 创建快照任务并开始接收快照数据，任务状态通过 `snapshot_state` 事件通知前端
+///
+/// 返回的 Receiver 会在任务结束后收到任务的错误信息，成功时为 None
+pub async fn start_snapshot_task(
+    app: AppHandle,
+    connector: &mut EtcdConnector,
+    file_path: PathBuf,
+) -> Result<(SnapshotInfo, oneshot::Receiver<Option<String>>), LogicError> {
+    let (info, stop_receiver) = new_snapshot_task(&file_path);
+    let task_id = info.id;
     let (watch_sender, mut receiver) = mpsc::channel::<(u64, u64, Option<String>)>(128);
+    let (done_sender, done_receiver) = oneshot::channel();
 
+    let app_clone = app.clone();
     tokio::spawn(async move {
         while let Some(state) = receiver.recv().await {
             let task = SNAPSHOT_TASK_POOL.get_mut(&task_id);
@@ -68,19 +472,61 @@ pub async fn maintenance_create_snapshot_task(
 
                 let state = t.state.clone();
 
-                app.emit_all(
-                    "snapshot_state",
-                    SnapshotStateEvent {
-                        id: task_id,
-                        state: state,
-                    },
-                )
-                .unwrap();
+                app_clone
+                    .emit_all(
+                        "snapshot_state",
+                        SnapshotStateEvent {
+                            id: task_id,
+                            state: state,
+                        },
+                    )
+                    .unwrap();
             }
         }
+
+        let error_msg = match SNAPSHOT_TASK_POOL.get(&task_id) {
+            Some(t) => t.state.error_msg.clone(),
+            None => Some(String::from("Snapshot task was removed")),
+        };
+        let _ = done_sender.send(error_msg);
     });
 
-    let file_path = PathBuf::from(filepath);
+    if let Err(e) = connector
+        .maintenance_snapshot(file_path, watch_sender, stop_receiver)
+        .await
+    {
+        //  任务未能启动，先通过 `snapshot_state` 事件通知失败原因，再移除任务并返回错误
+        set_snapshot_task_error(&app, task_id, format!("{:?}", e));
+        remove_snapshot_task(task_id);
+        return Err(e);
+    }
+
+    Ok((info, done_receiver))
+}
+
+/// 报告一个未能创建的快照任务，使失败原因也能通过 `snapshot_state` 事件展示，通知后不保留任务
+pub fn report_snapshot_failure(app: &AppHandle, file_path: &PathBuf, error_msg: String) {
+    let (info, _) = new_snapshot_task(file_path);
+    set_snapshot_task_error(app, info.id, error_msg);
+    remove_snapshot_task(info.id);
+}
+
+fn set_snapshot_task_error(app: &AppHandle, task_id: i32, error_msg: String) {
+    if let Some(mut t) = SNAPSHOT_TASK_POOL.get_mut(&task_id) {
+        t.state.error_msg = Some(error_msg);
+        let _ = app.emit_all(
+            "snapshot_state",
+            SnapshotStateEvent {
+                id: task_id,
+                state: t.state.clone(),
+            },
+        );
+    }
+}
+
+fn new_snapshot_task(file_path: &PathBuf) -> (SnapshotInfo, oneshot::Receiver<()>) {
+    let id = SNAPSHOT_TASK_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
+
     let folder = if let Some(path) = file_path.parent() {
         path.to_string_lossy().to_string()
     } else {
@@ -104,7 +550,7 @@ pub async fn maintenance_create_snapshot_task(
     let (stop_sender, stop_receiver) = oneshot::channel();
 
     let info = SnapshotInfo {
-        id: task_id,
+        id,
         name: file_name.clone(),
         folder: folder.clone(),
         state: SnapshotState::default(),
@@ -118,11 +564,7 @@ pub async fn maintenance_create_snapshot_task(
     };
     SNAPSHOT_TASK_POOL.insert(id, task);
 
-    connector
-        .maintenance_snapshot(file_path, watch_sender, stop_receiver)
-        .await?;
-
-    Ok(info)
+    (info, stop_receiver)
 }
 
 #[tauri::command]
@@ -140,10 +582,14 @@ pub fn maintenance_stop_snapshot_task(task_id: i32) -> Result<(), LogicError> {
 
 #[tauri::command]
 pub fn maintenance_remove_snapshot_task(task_id: i32) -> Result<(), LogicError> {
-    SNAPSHOT_TASK_POOL.remove(&task_id);
+    remove_snapshot_task(task_id);
     Ok(())
 }
 
+pub fn remove_snapshot_task(task_id: i32) {
+    SNAPSHOT_TASK_POOL.remove(&task_id);
+}
+
 #[tauri::command]
 pub fn maintenance_list_snapshot_task() -> Result<Vec<SnapshotInfo>, LogicError> {
     let mut list = Vec::new();
@@ -158,9 +604,106 @@ pub fn maintenance_list_snapshot_task() -> Result<Vec<SnapshotInfo>, LogicError>
     Ok(list)
 }
 
+/// 重新校验本地快照文件的 sha256 以及元数据
 #[tauri::command]
-pub async fn metrics(session: i32) -> Result<Vec<(String, String)>, LogicError> {
+pub async fn snapshot_verify(filepath: String) -> Result<SnapshotVerifyResult, LogicError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        snapshot::verify_snapshot_file(&PathBuf::from(filepath))
+    })
+    .await
+    .map_err(|e| LogicError::MsgError(e.to_string()))?
+}
+
+/// 将快照恢复为新的 etcd 数据目录，进度通过事件通知
+#[tauri::command]
+pub async fn maintenance_restore_snapshot(
+    app_handle: AppHandle,
+    options: SnapshotRestoreOptions,
+) -> Result<(), LogicError> {
+    if options.data_dir.is_empty() || options.member_name.is_empty() {
+        return Err(LogicError::ArgumentError);
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let _ = app_handle.emit_to("main", SNAPSHOT_RESTORE_START_EVENT, ());
+        let result = snapshot_restore::restore(&options, |event| {
+            let _ = app_handle.emit_to("main", SNAPSHOT_RESTORE_EVENT, event);
+        });
+        match result {
+            Ok(result) => {
+                let _ = app_handle.emit_to("main", SNAPSHOT_RESTORE_END_EVENT, result);
+            }
+            Err(e) => {
+                log::error!("restore snapshot error: {:?}", e);
+                let _ = app_handle.emit_to("main", SNAPSHOT_RESTORE_ERR_EVENT, format!("{:?}", e));
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// 对每个成员进行多次探测，返回健康状态与延迟统计，`probes` 默认为 5
+#[tauri::command]
+pub async fn cluster_health(
+    session: i32,
+    probes: Option<u32>,
+) -> Result<ClusterHealth, LogicError> {
+    health_probe::cluster_health(session, probes.unwrap_or(5)).await
+}
+
+#[tauri::command]
+pub async fn metrics(session: i32) -> Result<Vec<MetricFamily>, LogicError> {
+    etcd::start_metrics_sampler(&session)?;
     let connector = etcd::get_connector(&session)?;
     let metrics = connector.metrics().await?;
     Ok(metrics)
+}
+
+/// 读取 ssh 隧道的流量与诊断统计，未使用 ssh 时返回空
+#[tauri::command]
+pub async fn tunnel_stats(session: i32) -> Result<Option<SshTunnelStats>, LogicError> {
+    let connector = etcd::get_connector(&session)?;
+    Ok(connector.ssh_tunnel_stats())
+}
+
+/// 并发读取集群中每个成员的检测数据，单个成员失败不影响其他成员
+#[tauri::command]
+pub async fn metrics_members(session: i32) -> Result<Vec<MemberMetrics>, LogicError> {
+    let endpoints = {
+        let mut connector = etcd::get_connector(&session)?;
+        connector.member_metrics_endpoints().await?
+    };
+
+    let tasks = endpoints
+        .into_iter()
+        .map(|(member_id, name, endpoint)| {
+            tokio::spawn(async move {
+                let result = endpoint.scrape().await;
+                MemberMetrics {
+                    member_id,
+                    name,
+                    url: endpoint.url(),
+                    success: result.is_ok(),
+                    failed_msg: result.as_ref().err().map(|e| format!("{:?}", e)),
+                    families: result.unwrap_or_default(),
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        result.push(
+            task.await
+                .map_err(|e| LogicError::MsgError(e.to_string()))?,
+        );
+    }
+    Ok(result)
+}
+
+/// 查询后台采样的指标历史，`names` 为指标族名称，为空时返回所有指标
+#[tauri::command]
+pub fn metrics_history(session: i32, names: Vec<String>) -> Result<Vec<MetricSeries>, LogicError> {
+    etcd::get_metrics_history(&session, &names)
 }
\ No newline at end of file
@@ -7,6 +7,9 @@ use log::info;
 use tauri::{updater::UpdateResponse, AppHandle, Manager, UpdaterEvent, Window, Wry};
 use tokio::sync::{Mutex, RwLock};
 
+#[cfg(not(target_os = "linux"))]
+use crate::transport::connection::ConnectionProxy;
+
 lazy_static! {
     static ref UPDATE_RESULT: Mutex<Option<UpdateResponse<Wry>>> = Mutex::new(None);
     static ref UPDATE_CHECK_SOURCE: RwLock<String> = RwLock::new(String::from("main"));
@@ -46,6 +49,7 @@ pub async fn check_update_with_source(
     let mut update_builder = tauri::updater::builder(app_handle.clone());
 
     let setting = get_settings().await?;
+    apply_network_proxy(setting.network_proxy.clone()).await?;
     update_builder = match setting.update_source {
         UpdateSource::Github => update_builder.endpoints(&[String::from(UPDATE_SOURCE_GITHUB)]),
         UpdateSource::Gitee => {
@@ -84,6 +88,40 @@ pub async fn check_update_with_source(
     Ok(available)
 }
 
+/// tauri 的更新器无法指定代理，只能读取环境变量中的代理，
+/// 因此启动本地的 CONNECT 代理并通过 `HTTPS_PROXY` 指向它，由本地代理经过配置的代理转发。
+///
+/// 注意 `HTTPS_PROXY` 是进程级的环境变量，设置后对整个进程生效，
+/// 之后所有读取系统代理的 HTTPS 请求都会经过该代理，直到取消网络代理配置。
+/// 访问 etcd、网关与 metrics 的客户端由 [`crate::utils::http_util::build_client`] 创建并禁用了系统代理，不受影响
+#[cfg(not(target_os = "linux"))]
+async fn apply_network_proxy(proxy: Option<ConnectionProxy>) -> Result<(), LogicError> {
+    use crate::proxy::connect_server::LocalConnectProxy;
+
+    lazy_static! {
+        static ref NETWORK_PROXY: Mutex<Option<(ConnectionProxy, LocalConnectProxy)>> =
+            Mutex::new(None);
+    }
+
+    let mut lock = NETWORK_PROXY.lock().await;
+    match proxy {
+        Some(proxy) => {
+            let running = matches!(&*lock, Some((current, _)) if current == &proxy);
+            if !running {
+                let server = LocalConnectProxy::start(proxy.clone()).await?;
+                std::env::set_var("HTTPS_PROXY", format!("http://127.0.0.1:{}", server.port()));
+                *lock = Some((proxy, server));
+            }
+        }
+        None => {
+            if lock.take().is_some() {
+                std::env::remove_var("HTTPS_PROXY");
+            }
+        }
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn install_update(app_handle: AppHandle) -> Result<(), LogicError> {
     let mut lock = UPDATE_RESULT.lock().await;
@@ -6,6 +6,7 @@ use log::error;
 use serde::{Deserialize, Serialize, Serializer};
 use tokio::sync::oneshot;
 
+use crate::transport::connection::{SshHostKeyInfo, TlsCertificateInfo};
 use crate::utils::aes_util::AesError;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +19,8 @@ enum ErrorType {
     SshClientError,
     /// ssh隧道异常
     SshKeysError,
+    /// ssh服务器公钥与已信任的公钥不一致
+    SshHostKeyChanged,
     /// 应用异常，一般是代码级的错误
     AppError,
     /// 参数错误
@@ -32,6 +35,8 @@ enum ErrorType {
     HttpRequestError,
     /// 超限制
     LimitedError,
+    /// tls 握手时服务器证书校验失败
+    TlsHandshakeError,
 }
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -58,6 +63,9 @@ pub enum LogicError {
     UpdateError(tauri::updater::Error),
     ReqwestError(reqwest::Error),
     LimitedError(i64),
+    SshHostKeyChanged(SshHostKeyInfo),
+    /// 服务器证书校验失败的原因与服务器提供的证书链
+    TlsHandshakeError(String, Vec<TlsCertificateInfo>),
 }
 
 impl Serialize for LogicError {
@@ -251,6 +259,20 @@ impl Serialize for LogicError {
                 })),
             }
             .serialize(serializer),
+            LogicError::SshHostKeyChanged(info) => ErrorPayload {
+                err_type: ErrorType::SshHostKeyChanged,
+                err_msg: "The ssh host key has changed, it may be a man-in-the-middle attack",
+                data: serde_json::to_value(info).ok(),
+            }
+            .serialize(serializer),
+            LogicError::TlsHandshakeError(msg, chain) => ErrorPayload {
+                err_type: ErrorType::TlsHandshakeError,
+                err_msg: msg.as_str(),
+                data: Some(serde_json::json!({
+                    "chain": chain
+                })),
+            }
+            .serialize(serializer),
         }
     }
 }
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use log::{debug, info, warn};
+use tokio::sync::oneshot;
+use tokio::time::interval;
+
+use crate::api::maintenance::append_maintenance_log;
+use crate::transport::connection::{CompactionPolicy, CompactionRetention};
+use crate::transport::maintenance::{MaintenanceAction, MaintenanceLog};
+
+use super::{get_connector_optional, is_compacted_error, now_timestamp};
+
+/// 版本采样间隔，按时间保留时根据采样结果换算目标版本
+const SAMPLE_INTERVAL_SECONDS: u64 = 60;
+
+/// 会话级别的自动压缩任务，按照 [`CompactionPolicy`] 周期性地压缩历史版本
+pub struct AutoCompactor {
+    shutdown_sender: Option<oneshot::Sender<()>>,
+}
+
+impl AutoCompactor {
+    pub fn start(session_id: i32, connection_name: String, policy: CompactionPolicy) -> Self {
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            let compact_task = async {
+                let compact_interval = Duration::from_secs(policy.interval_minutes.max(1) * 60);
+                let mut ticker = interval(Duration::from_secs(SAMPLE_INTERVAL_SECONDS));
+                let mut samples: VecDeque<(u64, i64)> = VecDeque::new();
+                let mut last_run = Instant::now();
+                let mut last_compacted = 0i64;
+
+                info!("Started auto compactor: {}", session_id);
+                loop {
+                    ticker.tick().await;
+
+                    let connector = get_connector_optional(&session_id);
+                    if connector.is_none() {
+                        debug!(
+                            "Connector is not exist, auto compactor exit: {}",
+                            session_id
+                        );
+                        break;
+                    }
+                    let mut connector = connector.unwrap();
+
+                    let revision = match connector.current_revision().await {
+                        Ok(revision) => revision,
+                        Err(e) => {
+                            warn!("Auto compactor failed to read revision: {}", e);
+                            continue;
+                        }
+                    };
+
+                    let now = now_timestamp() as u64;
+                    let target = match policy.retention {
+                        CompactionRetention::Revision(retention) => Some(revision - retention),
+                        CompactionRetention::Period(minutes) => {
+                            samples.push_back((now, revision));
+                            let boundary = now.saturating_sub(minutes * 60 * 1000);
+                            resolve_period_revision(&mut samples, boundary)
+                        }
+                    };
+
+                    if last_run.elapsed() < compact_interval {
+                        continue;
+                    }
+                    last_run = Instant::now();
+
+                    let target = match target {
+                        Some(target) if target > last_compacted && target > 0 => target,
+                        _ => {
+                            debug!("Auto compactor has nothing to compact: {}", session_id);
+                            continue;
+                        }
+                    };
+
+                    let start = Instant::now();
+                    let result = connector.maintenance_compact(target, policy.physical).await;
+                    drop(connector);
+
+                    let (success, failed_msg) = match result {
+                        Ok(_) => (true, None),
+                        //  已经被其他客户端压缩过
+                        Err(e) if is_compacted_error(&e) => (true, None),
+                        Err(e) => (false, Some(e.to_string())),
+                    };
+                    if success {
+                        last_compacted = target;
+                    }
+
+                    let log = MaintenanceLog {
+                        action: MaintenanceAction::AutoCompact,
+                        revision: target,
+                        duration: start.elapsed().as_millis() as u64,
+                        time: now,
+                        success,
+                        failed_msg,
+                    };
+                    if let Err(e) = append_maintenance_log(&connection_name, log) {
+                        warn!("Failed to append maintenance log: {:?}", e);
+                    }
+                }
+            };
+
+            tokio::select! {
+                _ = shutdown_receiver => {
+                    info!("Auto compactor shutdown: {}", session_id);
+                },
+                _ = compact_task => {}
+            }
+        });
+
+        AutoCompactor {
+            shutdown_sender: Some(shutdown_sender),
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Drop for AutoCompactor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// 根据采样记录换算出 `boundary` 时刻的版本号，同时移除不再需要的旧采样
+///
+/// 采样按时间升序排列，返回时间不晚于 `boundary` 的最新一次采样的版本号
+pub fn resolve_period_revision(samples: &mut VecDeque<(u64, i64)>, boundary: u64) -> Option<i64> {
+    while samples.len() >= 2 && samples[1].0 <= boundary {
+        samples.pop_front();
+    }
+
+    match samples.front() {
+        Some((time, revision)) if *time <= boundary => Some(*revision),
+        _ => None,
+    }
+}
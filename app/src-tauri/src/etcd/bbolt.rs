@@ -0,0 +1,606 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::LogicError;
+
+const PAGE_HEADER_SIZE: usize = 16;
+const ELEMENT_SIZE: usize = 16;
+const BUCKET_HEADER_SIZE: usize = 16;
+
+const BRANCH_PAGE_FLAG: u16 = 0x01;
+const LEAF_PAGE_FLAG: u16 = 0x02;
+const META_PAGE_FLAG: u16 = 0x04;
+const FREELIST_PAGE_FLAG: u16 = 0x10;
+
+const BUCKET_LEAF_FLAG: u32 = 0x01;
+
+const MAGIC: u32 = 0xED0CDAED;
+const VERSION: u32 = 2;
+const META_CHECKSUM_OFFSET: usize = 56;
+
+/// B+ 树的最大深度，bbolt 的树通常只有几层，超过时认为文件已损坏
+const MAX_TREE_DEPTH: usize = 64;
+
+/// bucket 的根节点，内联 bucket 的页面直接保存在父 bucket 的 value 中
+#[derive(Clone)]
+pub struct Bucket {
+    root: u64,
+    inline_page: Option<Vec<u8>>,
+}
+
+/// bbolt 数据文件的只读解析，用于离线读取 etcd 快照
+///
+/// 只实现读取所需的页面结构：meta、branch、leaf 以及内联 bucket，页面按需从文件中读取
+pub struct BoltDb {
+    file: File,
+    file_len: u64,
+    page_size: usize,
+    root: Bucket,
+    txid: u64,
+}
+
+impl BoltDb {
+    pub fn open(path: &Path) -> Result<Self, LogicError> {
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+
+        let mut head = vec![0u8; 4096];
+        let n = read_full(&mut file, &mut head)?;
+        head.truncate(n);
+        let page_size = parse_meta(&head)
+            .map(|m| m.page_size)
+            .ok_or_else(|| invalid("The first meta page is broken"))?;
+        if page_size < PAGE_HEADER_SIZE {
+            return Err(invalid("Invalid page size"));
+        }
+
+        let mut db = BoltDb {
+            file,
+            file_len,
+            page_size,
+            root: Bucket {
+                root: 0,
+                inline_page: None,
+            },
+            txid: 0,
+        };
+
+        //  两个 meta 页面中选择校验通过且事务号最大的一个
+        let mut selected: Option<Meta> = None;
+        for pgid in 0..2 {
+            let page = db.read_page(pgid)?;
+            if let Some(meta) = parse_meta(&page) {
+                if selected.as_ref().is_none_or(|s| meta.txid > s.txid) {
+                    selected = Some(meta);
+                }
+            }
+        }
+        let meta = selected.ok_or_else(|| invalid("No valid meta page"))?;
+        db.root = Bucket {
+            root: meta.root,
+            inline_page: None,
+        };
+        db.txid = meta.txid;
+        Ok(db)
+    }
+
+    pub fn txid(&self) -> u64 {
+        self.txid
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// 列出所有顶层 bucket 的名字
+    pub fn bucket_names(&mut self) -> Result<Vec<Vec<u8>>, LogicError> {
+        let root = self.root.clone();
+        let page = self.bucket_root_page(&root)?;
+        let mut names = vec![];
+        self.walk(&page, true, &mut vec![root.root], &mut |name, _| {
+            names.push(name.to_vec());
+            Ok(true)
+        })?;
+        Ok(names)
+    }
+
+    /// 打开顶层 bucket
+    pub fn bucket(&mut self, name: &[u8]) -> Result<Option<Bucket>, LogicError> {
+        let root = self.root.clone();
+        self.child_bucket(&root, name)
+    }
+
+    /// 打开嵌套的 bucket
+    pub fn child_bucket(
+        &mut self,
+        parent: &Bucket,
+        name: &[u8],
+    ) -> Result<Option<Bucket>, LogicError> {
+        match self.find(parent, name)? {
+            Some((flags, value)) if flags & BUCKET_LEAF_FLAG != 0 => {
+                if value.len() < BUCKET_HEADER_SIZE {
+                    return Err(invalid("Invalid bucket header"));
+                }
+                let root = read_u64(&value, 0);
+                let inline_page = if root == 0 {
+                    Some(value[BUCKET_HEADER_SIZE..].to_vec())
+                } else {
+                    None
+                };
+                Ok(Some(Bucket { root, inline_page }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// 读取 bucket 中 key 对应的值，子 bucket 不会被返回
+    pub fn get(&mut self, bucket: &Bucket, key: &[u8]) -> Result<Option<Vec<u8>>, LogicError> {
+        match self.find(bucket, key)? {
+            Some((flags, value)) if flags & BUCKET_LEAF_FLAG == 0 => Ok(Some(value)),
+            _ => Ok(None),
+        }
+    }
+
+    /// 按 key 升序遍历 bucket 中的所有键值对，回调返回 false 时停止遍历
+    pub fn for_each<F>(&mut self, bucket: &Bucket, mut f: F) -> Result<(), LogicError>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool, LogicError>,
+    {
+        let page = self.bucket_root_page(bucket)?;
+        self.walk(&page, false, &mut vec![bucket.root], &mut f)?;
+        Ok(())
+    }
+
+    /// 遍历页面中的元素，`buckets` 为 true 时只遍历子 bucket，否则只遍历普通键值对
+    ///
+    /// `path` 为从 bucket 根节点到当前页面经过的页面
+    fn walk<F>(
+        &mut self,
+        page: &[u8],
+        buckets: bool,
+        path: &mut Vec<u64>,
+        f: &mut F,
+    ) -> Result<bool, LogicError>
+    where
+        F: FnMut(&[u8], &[u8]) -> Result<bool, LogicError>,
+    {
+        let (flags, count) = page_header(page)?;
+        if flags & LEAF_PAGE_FLAG != 0 {
+            for i in 0..count {
+                let (elem_flags, key, value) = leaf_element(page, i)?;
+                if (elem_flags & BUCKET_LEAF_FLAG != 0) != buckets {
+                    continue;
+                }
+                if !f(key, value)? {
+                    return Ok(false);
+                }
+            }
+        } else if flags & BRANCH_PAGE_FLAG != 0 {
+            for i in 0..count {
+                let (_, pgid) = branch_element(page, i)?;
+                descend(path, pgid)?;
+                let child = self.read_page(pgid)?;
+                if !self.walk(&child, buckets, path, f)? {
+                    return Ok(false);
+                }
+                path.pop();
+            }
+        } else {
+            return Err(invalid("Unexpected page type"));
+        }
+        Ok(true)
+    }
+
+    fn find(&mut self, bucket: &Bucket, key: &[u8]) -> Result<Option<(u32, Vec<u8>)>, LogicError> {
+        let mut page = self.bucket_root_page(bucket)?;
+        let mut path = vec![bucket.root];
+        loop {
+            let (flags, count) = page_header(&page)?;
+            if flags & LEAF_PAGE_FLAG != 0 {
+                for i in 0..count {
+                    let (elem_flags, k, v) = leaf_element(&page, i)?;
+                    if k == key {
+                        return Ok(Some((elem_flags, v.to_vec())));
+                    }
+                }
+                return Ok(None);
+            } else if flags & BRANCH_PAGE_FLAG != 0 {
+                if count == 0 {
+                    return Ok(None);
+                }
+                //  找到最后一个不大于 key 的分支
+                let mut index = 0;
+                for i in 0..count {
+                    let (k, _) = branch_element(&page, i)?;
+                    if k > key {
+                        break;
+                    }
+                    index = i;
+                }
+                let (_, pgid) = branch_element(&page, index)?;
+                descend(&mut path, pgid)?;
+                page = self.read_page(pgid)?;
+            } else {
+                return Err(invalid("Unexpected page type"));
+            }
+        }
+    }
+
+    fn bucket_root_page(&mut self, bucket: &Bucket) -> Result<Vec<u8>, LogicError> {
+        match &bucket.inline_page {
+            Some(page) => Ok(page.clone()),
+            None => self.read_page(bucket.root),
+        }
+    }
+
+    /// 读取页面以及它的溢出页，页面ID与溢出页数量都来自文件内容，需要先检查是否超出文件范围
+    fn read_page(&mut self, pgid: u64) -> Result<Vec<u8>, LogicError> {
+        let page_size = self.page_size as u64;
+        let offset = pgid
+            .checked_mul(page_size)
+            .filter(|offset| *offset < self.file_len)
+            .ok_or_else(|| invalid("Page out of range"))?;
+        self.file.seek(SeekFrom::Start(offset))?;
+
+        let mut page = vec![0u8; self.page_size];
+        if read_full(&mut self.file, &mut page)? < PAGE_HEADER_SIZE {
+            return Err(invalid("Page out of range"));
+        }
+
+        let overflow = read_u32(&page, 12) as u64;
+        if overflow > 0 {
+            let end = (pgid + 1 + overflow).checked_mul(page_size);
+            if end.is_none_or(|end| end > self.file_len) {
+                return Err(invalid("Overflow page out of range"));
+            }
+            let mut rest = vec![0u8; overflow as usize * self.page_size];
+            if read_full(&mut self.file, &mut rest)? < rest.len() {
+                return Err(invalid("Unexpected end of file in overflow page"));
+            }
+            page.extend_from_slice(&rest);
+        }
+        Ok(page)
+    }
+}
+
+/// 按 key 升序批量写入，生成一个新的 bbolt 数据文件，只支持顶层 bucket
+///
+/// 页面顺序写入：0、1 为 meta 页，2 为空的 freelist 页，之后依次是各 bucket 的 B+ 树，最后是根 bucket
+pub struct BoltWriter {
+    file: File,
+    page_size: usize,
+    next_pgid: u64,
+    buckets: Vec<(Vec<u8>, u64)>,
+    current: Option<BucketBuilder>,
+}
+
+struct BucketBuilder {
+    name: Vec<u8>,
+    /// 当前叶子页中的键值对
+    items: Vec<(Vec<u8>, Vec<u8>)>,
+    items_size: usize,
+    /// 已写入的叶子页：(第一个key, 页面ID)
+    leaves: Vec<(Vec<u8>, u64)>,
+}
+
+impl BoltWriter {
+    pub fn create(path: &Path, page_size: usize) -> Result<Self, LogicError> {
+        let file = File::create(path)?;
+        Ok(BoltWriter {
+            file,
+            page_size,
+            next_pgid: 3,
+            buckets: vec![],
+            current: None,
+        })
+    }
+
+    pub fn begin_bucket(&mut self, name: &[u8]) -> Result<(), LogicError> {
+        self.end_bucket()?;
+        self.current = Some(BucketBuilder {
+            name: name.to_vec(),
+            items: vec![],
+            items_size: 0,
+            leaves: vec![],
+        });
+        Ok(())
+    }
+
+    /// 写入当前 bucket，key 必须严格递增
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), LogicError> {
+        let item_size = ELEMENT_SIZE + key.len() + value.len();
+        let full = match &self.current {
+            Some(b) => {
+                if let Some((last, _)) = b.items.last() {
+                    if last.as_slice() >= key {
+                        return Err(invalid("Keys must be written in ascending order"));
+                    }
+                }
+                !b.items.is_empty() && PAGE_HEADER_SIZE + b.items_size + item_size > self.page_size
+            }
+            None => return Err(invalid("No bucket is opened")),
+        };
+        if full {
+            self.flush_leaf()?;
+        }
+
+        let builder = self.current.as_mut().unwrap();
+        builder.items.push((key.to_vec(), value.to_vec()));
+        builder.items_size += item_size;
+        Ok(())
+    }
+
+    pub fn end_bucket(&mut self) -> Result<(), LogicError> {
+        if self.current.is_none() {
+            return Ok(());
+        }
+        let has_items = self.current.as_ref().is_some_and(|b| !b.items.is_empty());
+        if has_items || self.current.as_ref().is_some_and(|b| b.leaves.is_empty()) {
+            self.flush_leaf()?;
+        }
+
+        let builder = self.current.take().unwrap();
+        let root = self.write_branches(builder.leaves)?;
+        self.buckets.push((builder.name, root));
+        Ok(())
+    }
+
+    /// 写入根 bucket、freelist 以及 meta 页
+    pub fn finish(mut self, txid: u64) -> Result<(), LogicError> {
+        self.end_bucket()?;
+
+        let mut buckets = std::mem::take(&mut self.buckets);
+        buckets.sort_by(|a, b| a.0.cmp(&b.0));
+        let items = buckets
+            .into_iter()
+            .map(|(name, root)| {
+                let mut value = vec![0u8; BUCKET_HEADER_SIZE];
+                value[0..8].copy_from_slice(&root.to_le_bytes());
+                (name, value)
+            })
+            .collect::<Vec<_>>();
+        let root = self.write_leaf(&items, BUCKET_LEAF_FLAG)?;
+
+        let mut freelist = vec![0u8; self.page_size];
+        write_page_header(&mut freelist, 2, FREELIST_PAGE_FLAG, 0, 0);
+        self.write_at(2, &freelist)?;
+
+        for pgid in 0..2u64 {
+            let mut page = vec![0u8; self.page_size];
+            write_page_header(&mut page, pgid, META_PAGE_FLAG, 0, 0);
+            let meta = &mut page[PAGE_HEADER_SIZE..];
+            meta[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+            meta[4..8].copy_from_slice(&VERSION.to_le_bytes());
+            meta[8..12].copy_from_slice(&(self.page_size as u32).to_le_bytes());
+            meta[16..24].copy_from_slice(&root.to_le_bytes());
+            meta[32..40].copy_from_slice(&2u64.to_le_bytes());
+            meta[40..48].copy_from_slice(&self.next_pgid.to_le_bytes());
+            meta[48..56].copy_from_slice(&(txid + pgid).to_le_bytes());
+            let checksum = fnv64a(&meta[..META_CHECKSUM_OFFSET]);
+            meta[56..64].copy_from_slice(&checksum.to_le_bytes());
+            self.write_at(pgid, &page)?;
+        }
+        self.file.sync_all()?;
+        Ok(())
+    }
+
+    fn flush_leaf(&mut self) -> Result<(), LogicError> {
+        let items = std::mem::take(&mut self.current.as_mut().unwrap().items);
+        let first_key = items.first().map(|(k, _)| k.clone()).unwrap_or_default();
+        let pgid = self.write_leaf(&items, 0)?;
+
+        let builder = self.current.as_mut().unwrap();
+        builder.items_size = 0;
+        builder.leaves.push((first_key, pgid));
+        Ok(())
+    }
+
+    /// 逐层写入分支页，返回根页面ID
+    fn write_branches(&mut self, mut level: Vec<(Vec<u8>, u64)>) -> Result<u64, LogicError> {
+        while level.len() > 1 {
+            let mut next_level = vec![];
+            let mut chunk: Vec<(Vec<u8>, u64)> = vec![];
+            let mut chunk_size = 0;
+            for (key, pgid) in level {
+                let item_size = ELEMENT_SIZE + key.len();
+                if !chunk.is_empty() && PAGE_HEADER_SIZE + chunk_size + item_size > self.page_size {
+                    let first_key = chunk[0].0.clone();
+                    next_level.push((first_key, self.write_branch(&chunk)?));
+                    chunk.clear();
+                    chunk_size = 0;
+                }
+                chunk.push((key, pgid));
+                chunk_size += item_size;
+            }
+            if !chunk.is_empty() {
+                let first_key = chunk[0].0.clone();
+                next_level.push((first_key, self.write_branch(&chunk)?));
+            }
+            level = next_level;
+        }
+        Ok(level[0].1)
+    }
+
+    fn write_leaf(&mut self, items: &[(Vec<u8>, Vec<u8>)], flags: u32) -> Result<u64, LogicError> {
+        let mut page = vec![0u8; PAGE_HEADER_SIZE + items.len() * ELEMENT_SIZE];
+        for (i, (key, value)) in items.iter().enumerate() {
+            let offset = PAGE_HEADER_SIZE + i * ELEMENT_SIZE;
+            let pos = page.len() - offset;
+            page[offset..offset + 4].copy_from_slice(&flags.to_le_bytes());
+            page[offset + 4..offset + 8].copy_from_slice(&(pos as u32).to_le_bytes());
+            page[offset + 8..offset + 12].copy_from_slice(&(key.len() as u32).to_le_bytes());
+            page[offset + 12..offset + 16].copy_from_slice(&(value.len() as u32).to_le_bytes());
+            page.extend_from_slice(key);
+            page.extend_from_slice(value);
+        }
+        self.write_page(page, LEAF_PAGE_FLAG, items.len())
+    }
+
+    fn write_branch(&mut self, items: &[(Vec<u8>, u64)]) -> Result<u64, LogicError> {
+        let mut page = vec![0u8; PAGE_HEADER_SIZE + items.len() * ELEMENT_SIZE];
+        for (i, (key, pgid)) in items.iter().enumerate() {
+            let offset = PAGE_HEADER_SIZE + i * ELEMENT_SIZE;
+            let pos = page.len() - offset;
+            page[offset..offset + 4].copy_from_slice(&(pos as u32).to_le_bytes());
+            page[offset + 4..offset + 8].copy_from_slice(&(key.len() as u32).to_le_bytes());
+            page[offset + 8..offset + 16].copy_from_slice(&pgid.to_le_bytes());
+            page.extend_from_slice(key);
+        }
+        self.write_page(page, BRANCH_PAGE_FLAG, items.len())
+    }
+
+    /// 分配页面并写入，超过一页的内容使用溢出页
+    fn write_page(
+        &mut self,
+        mut page: Vec<u8>,
+        flags: u16,
+        count: usize,
+    ) -> Result<u64, LogicError> {
+        let pages = (page.len() + self.page_size - 1) / self.page_size;
+        page.resize(pages * self.page_size, 0);
+
+        let pgid = self.next_pgid;
+        write_page_header(&mut page, pgid, flags, count as u16, (pages - 1) as u32);
+        self.write_at(pgid, &page)?;
+        self.next_pgid += pages as u64;
+        Ok(pgid)
+    }
+
+    fn write_at(&mut self, pgid: u64, data: &[u8]) -> Result<(), LogicError> {
+        self.file
+            .seek(SeekFrom::Start(pgid * self.page_size as u64))?;
+        self.file.write_all(data)?;
+        Ok(())
+    }
+}
+
+fn write_page_header(page: &mut [u8], pgid: u64, flags: u16, count: u16, overflow: u32) {
+    page[0..8].copy_from_slice(&pgid.to_le_bytes());
+    page[8..10].copy_from_slice(&flags.to_le_bytes());
+    page[10..12].copy_from_slice(&count.to_le_bytes());
+    page[12..16].copy_from_slice(&overflow.to_le_bytes());
+}
+
+struct Meta {
+    page_size: usize,
+    root: u64,
+    txid: u64,
+}
+
+fn parse_meta(page: &[u8]) -> Option<Meta> {
+    if page.len() < PAGE_HEADER_SIZE + META_CHECKSUM_OFFSET + 8 {
+        return None;
+    }
+    let flags = read_u16(page, 8);
+    if flags & META_PAGE_FLAG == 0 {
+        return None;
+    }
+
+    let meta = &page[PAGE_HEADER_SIZE..];
+    if read_u32(meta, 0) != MAGIC {
+        return None;
+    }
+    if fnv64a(&meta[..META_CHECKSUM_OFFSET]) != read_u64(meta, META_CHECKSUM_OFFSET) {
+        return None;
+    }
+
+    Some(Meta {
+        page_size: read_u32(meta, 8) as usize,
+        root: read_u64(meta, 16),
+        txid: read_u64(meta, 48),
+    })
+}
+
+fn page_header(page: &[u8]) -> Result<(u16, usize), LogicError> {
+    if page.len() < PAGE_HEADER_SIZE {
+        return Err(invalid("Page is too small"));
+    }
+    Ok((read_u16(page, 8), read_u16(page, 10) as usize))
+}
+
+fn leaf_element(page: &[u8], index: usize) -> Result<(u32, &[u8], &[u8]), LogicError> {
+    let offset = PAGE_HEADER_SIZE + index * ELEMENT_SIZE;
+    check_range(page, offset, ELEMENT_SIZE)?;
+
+    let flags = read_u32(page, offset);
+    let pos = read_u32(page, offset + 4) as usize;
+    let ksize = read_u32(page, offset + 8) as usize;
+    let vsize = read_u32(page, offset + 12) as usize;
+
+    let key_start = offset + pos;
+    check_range(page, key_start, ksize + vsize)?;
+    let key = &page[key_start..key_start + ksize];
+    let value = &page[key_start + ksize..key_start + ksize + vsize];
+    Ok((flags, key, value))
+}
+
+fn branch_element(page: &[u8], index: usize) -> Result<(&[u8], u64), LogicError> {
+    let offset = PAGE_HEADER_SIZE + index * ELEMENT_SIZE;
+    check_range(page, offset, ELEMENT_SIZE)?;
+
+    let pos = read_u32(page, offset) as usize;
+    let ksize = read_u32(page, offset + 4) as usize;
+    let pgid = read_u64(page, offset + 8);
+
+    let key_start = offset + pos;
+    check_range(page, key_start, ksize)?;
+    Ok((&page[key_start..key_start + ksize], pgid))
+}
+
+fn check_range(page: &[u8], offset: usize, len: usize) -> Result<(), LogicError> {
+    if offset + len > page.len() {
+        Err(invalid("Page element out of range"))
+    } else {
+        Ok(())
+    }
+}
+
+fn read_full(file: &mut File, buf: &mut [u8]) -> Result<usize, LogicError> {
+    let mut read = 0;
+    while read < buf.len() {
+        let n = file.read(&mut buf[read..])?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    Ok(read)
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+fn fnv64a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in data {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// 进入分支页的子页面，损坏的文件中分支页可能指向自身或祖先页面，需要避免无限循环
+fn descend(path: &mut Vec<u64>, pgid: u64) -> Result<(), LogicError> {
+    if path.len() >= MAX_TREE_DEPTH {
+        return Err(invalid("The page tree is too deep"));
+    }
+    if path.contains(&pgid) {
+        return Err(invalid("Cyclic page reference"));
+    }
+    path.push(pgid);
+    Ok(())
+}
+
+fn invalid(msg: &str) -> LogicError {
+    LogicError::MsgError(format!("Invalid snapshot file: {}", msg))
+}
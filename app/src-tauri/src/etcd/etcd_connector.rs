@@ -3,23 +3,30 @@ use crate::api::role::role_get_permissions;
 use crate::api::settings::get_settings;
 use crate::error::LogicError;
 use crate::etcd::wrapped_etcd_client::WrappedEtcdClient;
+use crate::k8s::port_forward::K8sPortForward;
+use crate::proxy::proxy_tunnel::ProxyTunnel;
+use crate::proxy::TcpForwarder;
 use crate::ssh::ssh_client::SshClientHandler;
 use crate::ssh::ssh_tunnel::SshTunnel;
-use crate::transport::connection::{Connection, ConnectionUser};
+use crate::transport::connection::{
+    Connection, ConnectionTls, ConnectionTransport, ConnectionUser,
+};
 use crate::transport::kv::{
     get_prefix_one, SearchResult, SerializableKeyValue, SerializableLeaseInfo,
     SerializableLeaseSimpleInfo,
 };
 use crate::transport::maintenance::{
-    SerializableCluster, SerializableClusterMember, SerializableClusterStatus, SnapshotInfo,
-    SnapshotState,
+    SerializableAlarm, SerializableCluster, SerializableClusterMember, SerializableClusterStatus,
+    SerializableMemberDefragment, SnapshotInfo, SnapshotMetadata, SnapshotState,
 };
+use crate::transport::metrics::{MetricFamily, SshTunnelStats};
 use crate::transport::user::{ReadableKeys, SerializablePermission, SerializableUser};
-use crate::utils::k8s_formatter;
+use crate::utils::{k8s_formatter, tls_util};
 use etcd_client::{
-    AlarmAction, AlarmType, Client, CompactionOptions, ConnectOptions, Error, GetOptions,
-    GetResponse, Identity, LeaseGrantOptions, LeaseTimeToLiveOptions, PermissionType, PutOptions,
-    RoleRevokePermissionOptions, SortOrder, SortTarget, WatchOptions, WatchStream, Watcher,
+    AlarmAction, AlarmOptions, AlarmType, Client, CompactionOptions, ConnectOptions, Error,
+    GetOptions, GetResponse, Identity, LeaseGrantOptions, LeaseTimeToLiveOptions, PermissionType,
+    PutOptions, RoleRevokePermissionOptions, SortOrder, SortTarget, WatchOptions, WatchStream,
+    Watcher,
 };
 use log::{debug, error, info, warn};
 use russh::client;
@@ -28,11 +35,9 @@ use std::any::Any;
 use std::collections::{HashMap, HashSet};
 use std::ffi::{OsStr, OsString};
 use std::future::Future;
-use std::net::IpAddr;
 use std::ops::DerefMut;
 use std::path::PathBuf;
 use std::pin::Pin;
-use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use std::{fs, u8};
@@ -43,21 +48,92 @@ use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 
 use super::etcd_connector_handler::EtcdConnectorHandler;
+use super::gateway_proxy::{GatewayEndpoint, GatewayProxy, DEFAULT_GATEWAY_PREFIX};
+use super::metrics_endpoint::{self, MetricsEndpoint};
+use super::now_timestamp;
+use super::snapshot::{self, SnapshotHasher};
+use super::srv_discovery::{self, SystemSrvResolver};
+use super::v2_client::V2Client;
+
+/// 直连的成员节点
+pub struct MemberClient {
+    pub id: String,
+    pub name: String,
+    pub client_urls: Vec<String>,
+    pub client: Result<WrappedEtcdClient, LogicError>,
+}
 
 pub struct EtcdConnector {
     namespace: Option<String>,
     client: WrappedEtcdClient,
     ssh: Option<SshTunnel>,
+    /// 未使用 ssh 时经过代理的本地转发
+    proxy: Option<ProxyTunnel>,
+    /// 经过 Kubernetes API server 到 pod 的端口转发
+    k8s: Option<K8sPortForward>,
     connection_config: Connection,
+    connect_options: ConnectOptions,
+    metrics_endpoint: MetricsEndpoint,
+    /// 连接的节点地址（ssh 转发前）
+    endpoints: Vec<String>,
+    /// 使用 grpc-gateway 传输时的本地代理，客户端实际连接该代理
+    gateway: Option<GatewayProxy>,
 }
 
 impl EtcdConnector {
     pub async fn new(
-        connection: Connection,
+        mut connection: Connection,
         handler: EtcdConnectorHandler,
     ) -> Result<Self, LogicError> {
         let settings = get_settings().await?;
+
+        //  私钥与客户端证书不匹配时直接返回明确的错误，而不是握手失败
+        let inspection = match &connection.tls {
+            Some(tls) => tls_util::validate(tls)?,
+            None => None,
+        };
+        let client_cert = inspection.and_then(|inspection| inspection.identity.into_iter().next());
+        if let Some(cert) = client_cert {
+            let days_left = tls_util::days_until_expiry(&cert);
+            let warning_days = settings.tls_cert_expiry_warning_days as i64;
+            if warning_days > 0 && days_left < warning_days {
+                warn!(
+                    "Client certificate '{}' expires in {} days",
+                    cert.subject, days_left
+                );
+                handler.tls_certificate_expiring(cert, days_left);
+            }
+        }
+
+        //  通过 SRV 发现节点时以第一个节点作为主要地址，ssh 隧道与默认指标地址都基于该地址
+        let discovered = match &connection.discovery_srv {
+            Some(domain) if !domain.trim().is_empty() => {
+                let resolver = SystemSrvResolver::new()?;
+                let endpoints = srv_discovery::discover(
+                    &resolver,
+                    domain,
+                    connection.discovery_srv_name.as_deref(),
+                )
+                .await?;
+                //  `_etcd-client-ssl` 记录的节点要求 tls，未配置时使用系统根证书校验
+                if connection.tls.is_none() && endpoints.iter().any(|e| e.tls) {
+                    info!("TLS is enabled with system root certificates for SRV endpoints");
+                    connection.tls = Some(ConnectionTls {
+                        domain: None,
+                        cert: vec![],
+                        identity: None,
+                    });
+                }
+                connection.host = endpoints[0].host.clone();
+                connection.port = endpoints[0].port;
+                endpoints.iter().map(|e| e.url()).collect()
+            }
+            _ => vec![],
+        };
+
         let mut connection_config = connection.clone();
+        let mut endpoints = connection_endpoints(&connection, discovered);
+        let metrics_url = MetricsEndpoint::connection_url(&connection, &endpoints[0])?;
 
         let mut option = ConnectOptions::new()
             .with_keep_alive(Duration::from_secs(10), Duration::from_secs(5))
@@ -70,7 +146,12 @@ impl EtcdConnector {
             option = option.with_user(user.username, user.password)
         };
 
-        if let Some(tls) = connection.tls {
+        //  使用 grpc-gateway 时由本地代理负责 tls，客户端与代理之间使用明文
+        let grpc_tls = match connection.transport {
+            ConnectionTransport::Grpc => connection.tls,
+            ConnectionTransport::Gateway => None,
+        };
+        if let Some(tls) = grpc_tls {
             #[cfg(feature = "etcd-client-tls")]
             {
                 use etcd_client::{Certificate, Identity, TlsOptions};
@@ -132,10 +213,15 @@ impl EtcdConnector {
         let mut port = connection.port;
         let namespace = connection.namespace.clone();
 
-        let ssh = if let Some(ssh) = connection.ssh {
+        if connection.ssh.is_some() && connection.k8s.is_some() {
+            return Err(LogicError::MsgError(String::from(
+                "Kubernetes port-forward can not be used together with ssh",
+            )));
+        }
+
+        let mut ssh = if let Some(ssh) = connection.ssh {
             let ssh_context =
-                SshTunnel::new(ssh, Box::leak(host.clone().into_boxed_str()), port, handler)
-                    .await?;
+                SshTunnel::new(ssh, host.clone(), port, connection.proxy.clone(), handler).await?;
             port = ssh_context.get_proxy_port();
             host.clear();
             host.push_str("127.0.0.1");
@@ -148,15 +234,110 @@ impl EtcdConnector {
             None
         };
 
-        let address = format!("{}:{}", host, port);
-        info!("Connect to etcd server: {}", address);
-        let client = Client::connect([address], Some(option)).await?;
+        //  端口转发时代理只用于 API server 的连接
+        let mut k8s = if let Some(k8s) = connection.k8s {
+            let k8s_context = K8sPortForward::new(k8s, port, connection.proxy.clone()).await?;
+            port = k8s_context.get_proxy_port();
+            host.clear();
+            host.push_str("127.0.0.1");
+
+            connection_config.host = host.clone();
+            connection_config.port = port;
+
+            Some(k8s_context)
+        } else {
+            None
+        };
+
+        //  同时使用 ssh 时代理只用于 ssh 连接，否则所有流量都经过代理的本地转发
+        let mut proxy = match (&ssh, &k8s, connection.proxy) {
+            (None, None, Some(proxy)) => {
+                let proxy_context = ProxyTunnel::new(proxy, host.clone(), port).await?;
+                port = proxy_context.get_proxy_port();
+                host.clear();
+                host.push_str("127.0.0.1");
+
+                connection_config.host = host.clone();
+                connection_config.port = port;
+
+                Some(proxy_context)
+            }
+            _ => None,
+        };
+
+        let metrics_endpoint = MetricsEndpoint::new(
+            metrics_url,
+            tunnel_forwarder(&mut ssh, &mut k8s, &mut proxy),
+            connection_config.tls.clone(),
+        )
+        .await?;
+
+        //  使用 ssh 或代理时每个节点都通过同一个隧道转发到不同的本地端口
+        let addresses = match tunnel_forwarder(&mut ssh, &mut k8s, &mut proxy) {
+            Some(forwarder) => {
+                let mut addresses = Vec::with_capacity(endpoints.len());
+                for endpoint in &endpoints {
+                    addresses.push(forward_member_url(forwarder, endpoint).await?);
+                }
+                addresses
+            }
+            None => endpoints.clone(),
+        };
+
+        let gateway = match connection.transport {
+            ConnectionTransport::Grpc => None,
+            ConnectionTransport::Gateway => {
+                let prefix = connection
+                    .gateway_prefix
+                    .as_ref()
+                    .map(|p| p.trim())
+                    .filter(|p| !p.is_empty())
+                    .unwrap_or(DEFAULT_GATEWAY_PREFIX);
+                let mut gateway_endpoints = Vec::with_capacity(endpoints.len());
+                for (endpoint, address) in endpoints.iter().zip(addresses.iter()) {
+                    gateway_endpoints.push(
+                        GatewayEndpoint::new(endpoint, address, prefix, &connection_config.tls)
+                            .await?,
+                    );
+                }
+                Some(GatewayProxy::start(gateway_endpoints).await?)
+            }
+        };
+        let addresses = match &gateway {
+            Some(gateway) => vec![format!("http://127.0.0.1:{}", gateway.port())],
+            None => addresses,
+        };
+
+        info!("Connect to etcd server: {:?}", endpoints);
+        let client = match Client::connect(addresses, Some(option.clone())).await {
+            Ok(client) => client,
+            Err(e) => {
+                return Err(explain_tls_error(e.into(), &connection_config, &endpoints[0]).await)
+            }
+        };
+        let mut client = WrappedEtcdClient::new(client, (&connection.user).clone());
+
+        //  gateway 不支持成员列表接口，端口转发只能到达同一个 pod，其他成员的地址无法转发
+        if connection.discover_endpoints && gateway.is_none() && k8s.is_none() {
+            discover_endpoints(
+                &mut client,
+                tunnel_forwarder(&mut ssh, &mut k8s, &mut proxy),
+                &mut endpoints,
+            )
+            .await;
+        }
 
         Ok(EtcdConnector {
             namespace,
-            client: WrappedEtcdClient::new(client, (&connection.user).clone()),
+            client,
             ssh,
+            proxy,
+            k8s,
             connection_config,
+            connect_options: option,
+            metrics_endpoint,
+            endpoints,
+            gateway,
         })
     }
 
@@ -172,7 +353,23 @@ impl EtcdConnector {
         &mut self.client
     }
 
+    pub fn endpoints(&self) -> &Vec<String> {
+        &self.endpoints
+    }
+
+    /// ssh 隧道的流量统计，未使用 ssh 时为空
+    pub fn ssh_tunnel_stats(&self) -> Option<SshTunnelStats> {
+        self.ssh.as_ref().map(|ssh| ssh.stats())
+    }
+
     pub async fn test_connection(&mut self) -> Result<(), LogicError> {
+        match self.get_any_key().await {
+            Err(e) => Err(explain_tls_error(e, &self.connection_config, &self.endpoints[0]).await),
+            ok => ok,
+        }
+    }
+
+    async fn get_any_key(&mut self) -> Result<(), LogicError> {
         let readable_keys = self.get_readable_keys().await?;
 
         let key = if readable_keys.read_all_keys {
@@ -1045,6 +1242,46 @@ impl EtcdConnector {
         }
     }
 
+    /// 获取集群中所有已激活的报警
+    pub async fn alarm_list(&mut self) -> Result<Vec<SerializableAlarm>, Error> {
+        let response = self
+            .client
+            .alarm(AlarmAction::Get, AlarmType::None, None)
+            .await?;
+
+        let mut alarms = Vec::with_capacity(response.alarms().len());
+        for alarm in response.alarms() {
+            alarms.push(SerializableAlarm {
+                member_id: alarm.member_id().to_string(),
+                alarm_type: alarm.alarm() as i32,
+            })
+        }
+        Ok(alarms)
+    }
+
+    /// 解除成员节点上的指定报警，对应 `etcdctl alarm disarm`
+    pub async fn alarm_disarm(&mut self, member_id: String, alarm_type: i32) -> Result<(), Error> {
+        let member_id = member_id
+            .parse::<u64>()
+            .map_err(|e| Error::InvalidArgs(e.to_string()))?;
+        let alarm_type =
+            AlarmType::try_from(alarm_type).map_err(|e| Error::InvalidArgs(e.to_string()))?;
+
+        let mut options = AlarmOptions::new();
+        options.with_member(member_id);
+
+        self.client
+            .alarm(AlarmAction::Deactivate, alarm_type, Some(options))
+            .await?;
+        Ok(())
+    }
+
+    /// 获取当前连接节点的最新版本号
+    pub async fn current_revision(&mut self) -> Result<i64, Error> {
+        let status = self.client.status().await?;
+        Ok(status.header().map(|h| h.revision()).unwrap_or_default())
+    }
+
     /// 对server进行版本压缩，指定版本之前的旧版本将被移除
     pub async fn maintenance_compact(
         &mut self,
@@ -1066,6 +1303,142 @@ impl EtcdConnector {
         Ok(())
     }
 
+    /// 对指定成员节点进行碎片整理，`client_urls` 为该成员的客户端地址
+    ///
+    /// 整理前后各查询一次成员状态，返回数据库大小的变化
+    pub async fn maintenance_defragment_member(
+        &mut self,
+        client_urls: Vec<String>,
+    ) -> Result<SerializableMemberDefragment, LogicError> {
+        let mut client = self.connect_member(client_urls).await?;
+
+        let before = client.status().await?;
+        client.defragment().await?;
+        let after = client.status().await?;
+
+        let member_id = after
+            .header()
+            .map(|h| h.member_id().to_string())
+            .unwrap_or_default();
+
+        Ok(SerializableMemberDefragment {
+            member_id,
+            db_size_before: before.db_size(),
+            db_size_used_before: before.raft_used_db_size(),
+            db_size_after: after.db_size(),
+            db_size_used_after: after.raft_used_db_size(),
+        })
+    }
+
+    /// 等待成员节点恢复健康，健康的标准参考 `etcdctl endpoint health`：状态无错误且可以进行线性一致读
+    pub async fn wait_member_healthy(
+        &mut self,
+        client_urls: Vec<String>,
+        timeout: Duration,
+    ) -> Result<(), LogicError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let result = self.check_member_health(client_urls.clone()).await;
+            match result {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    if tokio::time::Instant::now() >= deadline {
+                        return Err(e);
+                    }
+                    debug!("member is not healthy yet: {:?}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    async fn check_member_health(&self, client_urls: Vec<String>) -> Result<(), LogicError> {
+        let mut client = self.connect_member(client_urls).await?;
+        let status = client.status().await?;
+        if !status.errors().is_empty() {
+            return Err(LogicError::MsgError(status.errors().join(", ")));
+        }
+        client
+            .kv_get_request(
+                Vec::from("health"),
+                Some(GetOptions::new().with_count_only()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// 直连某一个成员节点，用于碎片整理、状态查询等只对单个节点生效的操作
+    ///
+    /// 使用 ssh 时每个成员地址都会通过同一个 ssh 会话转发
+    async fn connect_member(
+        &mut self,
+        client_urls: Vec<String>,
+    ) -> Result<WrappedEtcdClient, LogicError> {
+        if client_urls.is_empty() {
+            return Err(LogicError::ArgumentError);
+        }
+        if self.gateway.is_some() {
+            return Err(LogicError::MsgError(String::from(
+                "Connecting to a single member is not supported by grpc-gateway transport",
+            )));
+        }
+        //  端口转发固定到配置的 pod，无法到达其他成员
+        if self.k8s.is_some() {
+            return Err(LogicError::MsgError(String::from(
+                "Connecting to a single member is not supported by kubernetes port-forward",
+            )));
+        }
+
+        let endpoints = match tunnel_forwarder(&mut self.ssh, &mut self.k8s, &mut self.proxy) {
+            Some(forwarder) => {
+                let mut endpoints = Vec::with_capacity(client_urls.len());
+                for client_url in &client_urls {
+                    endpoints.push(forward_member_url(forwarder, client_url).await?);
+                }
+                endpoints
+            }
+            None => client_urls,
+        };
+
+        let client = Client::connect(endpoints, Some(self.connect_options.clone())).await?;
+        Ok(WrappedEtcdClient::new(
+            client,
+            self.connection_config.user.clone(),
+        ))
+    }
+
+    /// 直连集群中的每个成员节点，单个成员连接失败不影响其他成员
+    pub async fn member_clients(&mut self) -> Result<Vec<MemberClient>, LogicError> {
+        let response = self.client.member_list().await?;
+        let members = response
+            .members()
+            .iter()
+            .map(|m| {
+                (
+                    m.id().to_string(),
+                    String::from(m.name()),
+                    m.client_urls().to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut clients = Vec::with_capacity(members.len());
+        for (id, name, client_urls) in members {
+            let client = self.connect_member(client_urls.clone()).await;
+            clients.push(MemberClient {
+                id,
+                name,
+                client_urls,
+                client,
+            });
+        }
+        Ok(clients)
+    }
+
+    pub fn is_ssh_tunnel(&self) -> bool {
+        self.ssh.is_some()
+    }
+
     /// 保存数据快照
     pub async fn maintenance_snapshot(
         &mut self,
@@ -1085,35 +1458,47 @@ impl EtcdConnector {
         let mut file = OpenOptions::new()
             .write(true)
             .append(true)
-            .open(file_path)
+            .open(&file_path)
             .await?;
 
         let watch_task = async move {
+            let mut hasher = SnapshotHasher::new();
+            let mut header = None;
+            //  最后一块数据的进度在校验完成后才发送，remain 为 0 表示任务完成
+            let mut last_received = 0;
             loop {
                 let slip_result = stream.message().await;
                 match slip_result {
                     Ok(slip) => {
-                        if let Some(response) = slip {
+                        if let Some(mut response) = slip {
+                            if header.is_none() {
+                                header = response.take_header();
+                            }
                             let blob = response.blob();
                             let remain = response.remaining_bytes();
 
                             let received = blob.len() as u64;
 
-                            file.write_all(blob).await;
+                            if let Err(e) = file.write_all(blob).await {
+                                watch_sender
+                                    .send((0, 0, Some(e.to_string())))
+                                    .await
+                                    .unwrap_or_else(|e| error!("watch send error[write]: {e}"));
+                                debug!("snapshot [write failed]: {e}");
+                                return;
+                            }
+                            hasher.update(blob);
 
                             debug!("snapshot [remain] {}-{}", received, remain);
-                            watch_sender
-                                .send((received, remain, None))
-                                .await
-                                .unwrap_or_else(|e| error!("watch send error[remain]: {e}"));
                             if remain == 0 {
+                                last_received = received;
                                 break;
                             }
-                        } else {
                             watch_sender
-                                .send((0, 0, None))
+                                .send((received, remain, None))
                                 .await
-                                .unwrap_or_else(|e| error!("watch send error[finish]: {e}"));
+                                .unwrap_or_else(|e| error!("watch send error[remain]: {e}"));
+                        } else {
                             break;
                         }
                     }
@@ -1123,10 +1508,54 @@ impl EtcdConnector {
                             .await
                             .unwrap_or_else(|e| error!("watch send error[failed]: {e}"));
                         debug!("snapshot [failed]: {e}");
-                        break;
+                        return;
                     }
                 }
             }
+
+            let result = match file.flush().await {
+                Ok(_) => {
+                    let size = hasher.size();
+                    hasher.verify().and_then(|sha256| {
+                        let metadata = SnapshotMetadata {
+                            cluster_id: header
+                                .as_ref()
+                                .map(|h| h.cluster_id().to_string())
+                                .unwrap_or_default(),
+                            member_id: header
+                                .as_ref()
+                                .map(|h| h.member_id().to_string())
+                                .unwrap_or_default(),
+                            revision: header.as_ref().map(|h| h.revision()).unwrap_or_default(),
+                            size,
+                            sha256,
+                            time: now_timestamp() as u64,
+                        };
+                        snapshot::write_metadata(&file_path, &metadata)
+                    })
+                }
+                Err(e) => Err(LogicError::from(e)),
+            };
+
+            match result {
+                Ok(_) => {
+                    watch_sender
+                        .send((last_received, 0, None))
+                        .await
+                        .unwrap_or_else(|e| error!("watch send error[finish]: {e}"));
+                }
+                Err(e) => {
+                    let msg = match e {
+                        LogicError::MsgError(msg) => msg,
+                        e => format!("{:?}", e),
+                    };
+                    debug!("snapshot [verify failed]: {msg}");
+                    watch_sender
+                        .send((0, 0, Some(msg)))
+                        .await
+                        .unwrap_or_else(|e| error!("watch send error[verify]: {e}"));
+                }
+            }
         };
 
         let task = tokio::spawn(async {
@@ -1143,58 +1572,85 @@ impl EtcdConnector {
         Ok(())
     }
 
-    /// 读取server的检测数据
-    pub async fn metrics(&self) -> Result<Vec<(String, String)>, LogicError> {
-        let response = if let Some(tls) = &self.connection_config.tls {
-            let mut client_builder = reqwest::Client::builder().use_rustls_tls().tls_sni(true);
-            for cert in &tls.cert {
-                let certificate = reqwest::Certificate::from_pem(cert.as_slice())?;
-                client_builder = client_builder.add_root_certificate(certificate);
-            }
-
-            if let Some(identity) = &tls.identity {
-                let buf = [&identity.key[..], &identity.cert[..]].concat();
-                let id = reqwest::Identity::from_pem(buf.as_slice())?;
-                client_builder = client_builder.identity(id);
-            }
-
-            if let Some(domain) = &tls.domain {
-                if let Ok(ip_addr) = IpAddr::from_str(domain.as_str()) {
-                    client_builder = client_builder.local_address(Some(ip_addr));
-                }
-            }
-
-            let url = format!(
-                "https://{}:{}/metrics",
-                self.connection_config.host, self.connection_config.port
-            );
+    /// 当前连接的指标地址，不持有连接，便于后台任务在释放会话后再发送请求
+    pub fn metrics_endpoint(&self) -> MetricsEndpoint {
+        self.metrics_endpoint.clone()
+    }
 
-            client_builder.build()?.get(url).send().await?
-        } else {
-            let url = format!(
-                "http://{}:{}/metrics",
-                self.connection_config.host, self.connection_config.port
-            );
+    /// 读取server的检测数据
+    pub async fn metrics(&self) -> Result<Vec<MetricFamily>, LogicError> {
+        self.metrics_endpoint.scrape().await
+    }
 
-            reqwest::Client::new().get(url).send().await?
+    /// 集群中每个成员的指标地址：(成员ID, 成员名, 指标地址)
+    ///
+    /// 配置了指标地址时沿用其协议、端口与路径，只将 host 替换为成员的 host；
+    /// 否则使用成员的第一个客户端地址
+    pub async fn member_metrics_endpoints(
+        &mut self,
+    ) -> Result<Vec<(String, String, MetricsEndpoint)>, LogicError> {
+        if self.k8s.is_some() {
+            return Err(LogicError::MsgError(String::from(
+                "Member metrics are not supported by kubernetes port-forward",
+            )));
+        }
+        let response = self.client.member_list().await?;
+        let configured = match &self.connection_config.metrics_url {
+            Some(url) if !url.trim().is_empty() => Some(metrics_endpoint::parse_url(url.trim())?),
+            _ => None,
         };
 
-        let response = response.text().await?;
-
-        let mut metrics = vec![];
-
-        for line in response.lines() {
-            if line.starts_with("#") {
-                continue;
-            }
-            if let Some(idx) = line.find(" ") {
-                let k = &line[0..idx];
-                let v = &line[idx + 1..];
-                metrics.push((String::from(k), String::from(v)));
-            }
+        let mut endpoints = vec![];
+        for member in response.members() {
+            let client_url = match member.client_urls().first() {
+                Some(url) => metrics_endpoint::parse_url(url)?,
+                None => continue,
+            };
+            let url = match &configured {
+                Some(template) => {
+                    let mut url = template.clone();
+                    url.set_host(client_url.host_str())
+                        .map_err(|e| LogicError::MsgError(format!("Invalid metrics url: {}", e)))?;
+                    url
+                }
+                None => {
+                    let mut url = client_url;
+                    url.set_path("/metrics");
+                    url
+                }
+            };
+            let endpoint = MetricsEndpoint::new(
+                url,
+                tunnel_forwarder(&mut self.ssh, &mut self.k8s, &mut self.proxy),
+                self.connection_config.tls.clone(),
+            )
+            .await?;
+            endpoints.push((
+                member.id().to_string(),
+                String::from(member.name()),
+                endpoint,
+            ));
         }
+        Ok(endpoints)
+    }
 
-        Ok(metrics)
+    /// 访问 v2 存储的客户端，使用连接的第一个节点
+    pub async fn v2_client(&mut self) -> Result<V2Client, LogicError> {
+        let endpoint = self
+            .endpoints
+            .first()
+            .ok_or(LogicError::MsgError(String::from("No endpoint available")))?;
+        let url = reqwest::Url::parse(endpoint)
+            .map_err(|e| LogicError::MsgError(format!("Invalid endpoint '{}': {}", endpoint, e)))?;
+        let timeout = Duration::from_secs(get_settings().await?.request_timeout_seconds);
+        V2Client::new(
+            url,
+            tunnel_forwarder(&mut self.ssh, &mut self.k8s, &mut self.proxy),
+            &self.connection_config.tls,
+            self.connection_config.user.clone(),
+            timeout,
+        )
+        .await
     }
 
     /// Key监听
@@ -1217,6 +1673,187 @@ fn key_next(key: &mut Vec<u8>) {
         key[len - 1] += 1
     }
 }
+
+/// 连接失败时单独进行一次 tls 握手，服务器证书校验不通过时附带服务器提供的证书链，便于排查 CA 与域名配置
+///
+/// `connection` 中的地址为转发后的第一个节点地址，`endpoint` 为转发前的地址
+async fn explain_tls_error(e: LogicError, connection: &Connection, endpoint: &str) -> LogicError {
+    let unavailable = match &e {
+        LogicError::EtcdClientError(Error::TransportError(_)) => true,
+        //  Unavailable
+        LogicError::EtcdClientError(Error::GRpcStatus(status)) => status.code() as i32 == 14,
+        _ => false,
+    };
+    let tls = match &connection.tls {
+        Some(tls) if unavailable => tls,
+        _ => return e,
+    };
+
+    let server_name = tls
+        .domain
+        .clone()
+        .filter(|domain| !domain.trim().is_empty())
+        .or_else(|| {
+            reqwest::Url::parse(endpoint)
+                .ok()?
+                .host_str()
+                .map(|host| host.trim_start_matches('[').trim_end_matches(']').to_string())
+        });
+    let server_name = match server_name {
+        Some(server_name) => server_name,
+        None => return e,
+    };
+    let connect_timeout = match get_settings().await {
+        Ok(settings) => Duration::from_secs(settings.connect_timeout_seconds),
+        Err(_) => return e,
+    };
+
+    match tls_util::presented_chain(
+        &connection.host,
+        connection.port,
+        &server_name,
+        tls,
+        connect_timeout,
+    )
+    .await
+    {
+        Ok(presented) if !presented.chain.is_empty() => match presented.error {
+            Some(reason) => LogicError::TlsHandshakeError(
+                format!(
+                    "TLS handshake failed, the server certificate is not trusted: {}",
+                    reason
+                ),
+                presented.chain,
+            ),
+            None => e,
+        },
+        Ok(_) => e,
+        Err(probe_error) => {
+            debug!("TLS probe failed: {:?}", probe_error);
+            e
+        }
+    }
+}
+
+/// 连接配置中的所有节点地址，没有协议的地址根据是否启用 tls 补全为 http 或 https
+///
+/// `discovered` 为 SRV 发现的节点，不为空时代替 host、port
+fn connection_endpoints(connection: &Connection, discovered: Vec<String>) -> Vec<String> {
+    let scheme = if connection.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let mut endpoints = if discovered.is_empty() {
+        vec![format!(
+            "{}://{}:{}",
+            scheme, connection.host, connection.port
+        )]
+    } else {
+        discovered
+    };
+    for endpoint in &connection.endpoints {
+        let endpoint = endpoint.trim();
+        if endpoint.is_empty() {
+            continue;
+        }
+        let endpoint = if endpoint.contains("://") {
+            endpoint.to_string()
+        } else {
+            format!("{}://{}", scheme, endpoint)
+        };
+        if !endpoints.contains(&endpoint) {
+            endpoints.push(endpoint);
+        }
+    }
+    endpoints
+}
+
+/// 将成员列表中其他成员的客户端地址加入客户端的负载均衡，某个节点不可用时请求会切换到其他节点
+///
+/// 发现失败只记录日志，不影响已建立的连接
+async fn discover_endpoints(
+    client: &mut WrappedEtcdClient,
+    mut forwarder: Option<&mut dyn TcpForwarder>,
+    endpoints: &mut Vec<String>,
+) {
+    let response = match client.member_list().await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!("Failed to discover etcd endpoints: {}", e);
+            return;
+        }
+    };
+
+    for member in response.members() {
+        for client_url in member.client_urls() {
+            if endpoints.contains(client_url) {
+                continue;
+            }
+            let address = match forwarder.as_deref_mut() {
+                Some(forwarder) => match forward_member_url(forwarder, client_url).await {
+                    Ok(address) => address,
+                    Err(e) => {
+                        warn!("Failed to forward endpoint {}: {:?}", client_url, e);
+                        continue;
+                    }
+                },
+                None => client_url.clone(),
+            };
+            match client.get_inner().add_endpoint(address).await {
+                Ok(_) => {
+                    info!("Discovered etcd endpoint: {}", client_url);
+                    endpoints.push(client_url.clone());
+                }
+                Err(e) => warn!("Failed to add endpoint {}: {}", client_url, e),
+            }
+        }
+    }
+}
+
+/// ssh 隧道或代理隧道，两者不会同时存在
+fn tunnel_forwarder<'a>(
+    ssh: &'a mut Option<SshTunnel>,
+    k8s: &'a mut Option<K8sPortForward>,
+    proxy: &'a mut Option<ProxyTunnel>,
+) -> Option<&'a mut dyn TcpForwarder> {
+    match (ssh, k8s, proxy) {
+        (Some(ssh), _, _) => Some(ssh),
+        (None, Some(k8s), _) => Some(k8s),
+        (None, None, Some(proxy)) => Some(proxy),
+        _ => None,
+    }
+}
+
+/// 通过 ssh 或代理转发成员的客户端地址，返回本地转发地址
+async fn forward_member_url(
+    forwarder: &mut dyn TcpForwarder,
+    client_url: &String,
+) -> Result<String, LogicError> {
+    let url = reqwest::Url::parse(client_url)
+        .map_err(|e| LogicError::MsgError(format!("Invalid member url '{}': {}", client_url, e)))?;
+    let host = url
+        .host_str()
+        .map(|host| {
+            host.trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string()
+        })
+        .ok_or(LogicError::MsgError(format!(
+            "Invalid member url: {}",
+            client_url
+        )))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or(LogicError::MsgError(format!(
+            "Invalid member url: {}",
+            client_url
+        )))?;
+
+    let local_port = forwarder.forward(host, port).await?;
+    Ok(format!("{}://127.0.0.1:{}", url.scheme(), local_port))
+}
+
 pub struct SnapshotTask {
     pub name: String,
     pub folder: String,
@@ -1,10 +1,18 @@
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager};
 
-use crate::transport::event::{DisconnectCase, SessionDisconnectedEvent};
+use crate::transport::connection::TlsCertificateInfo;
+use crate::transport::event::{
+    DisconnectCase, SessionDisconnectedEvent, SshTunnelState, SshTunnelStateEvent,
+    TlsCertificateExpiringEvent,
+};
 
 use super::remove_connector;
 
+const SESSION_DISCONNECTED_EVENT: &str = "sessionDisconnected";
+const SSH_TUNNEL_STATE_EVENT: &str = "sshTunnelState";
+const TLS_CERTIFICATE_EXPIRING_EVENT: &str = "tlsCertificateExpiring";
+
 #[derive(Clone, Default)]
 pub struct EtcdConnectorHandler {
     app_handle: Option<AppHandle>,
@@ -19,10 +27,45 @@ impl EtcdConnectorHandler {
         }
     }
 
+    pub fn app_handle(&self) -> Option<&AppHandle> {
+        self.app_handle.as_ref()
+    }
+
+    pub fn session_id(&self) -> i32 {
+        self.session_id
+    }
+
+    /// 通知界面 ssh 隧道的重连状态，会话本身保持不变
+    pub fn ssh_tunnel_state(&self, state: SshTunnelState) {
+        if let Some(app) = self.app_handle.as_ref() {
+            let _ = app.emit_all(
+                SSH_TUNNEL_STATE_EVENT,
+                SshTunnelStateEvent {
+                    session_id: self.session_id,
+                    state,
+                },
+            );
+        }
+    }
+
+    /// 提醒客户端证书即将过期，连接本身不受影响
+    pub fn tls_certificate_expiring(&self, certificate: TlsCertificateInfo, days_left: i64) {
+        if let Some(app) = self.app_handle.as_ref() {
+            let _ = app.emit_all(
+                TLS_CERTIFICATE_EXPIRING_EVENT,
+                TlsCertificateExpiringEvent {
+                    session_id: self.session_id,
+                    certificate,
+                    days_left,
+                },
+            );
+        }
+    }
+
     pub fn disconnected(&self, case: DisconnectCase) {
         if let Some(app) = self.app_handle.as_ref() {
             let _ = app.emit_all(
-                "sessionDisconnected",
+                SESSION_DISCONNECTED_EVENT,
                 SessionDisconnectedEvent {
                     session_id: self.session_id,
                     case,
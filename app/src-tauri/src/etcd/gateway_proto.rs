@@ -0,0 +1,550 @@
+//! grpc-gateway 转发所需的 etcd v3 消息定义
+//!
+//! 同一个结构体既用于 protobuf 编解码（与 etcd 客户端通信），也用于 json 序列化（与 grpc-gateway 通信）。
+//! json 格式与 gateway 的 protojson 一致：字段使用 proto 原始名称，bytes 为 base64，int64 可能为字符串，
+//! 枚举可能为名称。
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct ResponseHeader {
+    #[prost(uint64, tag = "1")]
+    #[serde(default, with = "json_uint64")]
+    pub cluster_id: u64,
+    #[prost(uint64, tag = "2")]
+    #[serde(default, with = "json_uint64")]
+    pub member_id: u64,
+    #[prost(int64, tag = "3")]
+    #[serde(default, with = "json_int64")]
+    pub revision: i64,
+    #[prost(uint64, tag = "4")]
+    #[serde(default, with = "json_uint64")]
+    pub raft_term: u64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct KeyValue {
+    #[prost(bytes = "vec", tag = "1")]
+    #[serde(default, with = "json_bytes")]
+    pub key: Vec<u8>,
+    #[prost(int64, tag = "2")]
+    #[serde(default, with = "json_int64")]
+    pub create_revision: i64,
+    #[prost(int64, tag = "3")]
+    #[serde(default, with = "json_int64")]
+    pub mod_revision: i64,
+    #[prost(int64, tag = "4")]
+    #[serde(default, with = "json_int64")]
+    pub version: i64,
+    #[prost(bytes = "vec", tag = "5")]
+    #[serde(default, with = "json_bytes")]
+    pub value: Vec<u8>,
+    #[prost(int64, tag = "6")]
+    #[serde(default, with = "json_int64")]
+    pub lease: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct RangeRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    #[serde(default, with = "json_bytes")]
+    pub key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    #[serde(default, with = "json_bytes")]
+    pub range_end: Vec<u8>,
+    #[prost(int64, tag = "3")]
+    #[serde(default, with = "json_int64")]
+    pub limit: i64,
+    #[prost(int64, tag = "4")]
+    #[serde(default, with = "json_int64")]
+    pub revision: i64,
+    #[prost(int32, tag = "5")]
+    #[serde(default)]
+    pub sort_order: i32,
+    #[prost(int32, tag = "6")]
+    #[serde(default)]
+    pub sort_target: i32,
+    #[prost(bool, tag = "7")]
+    #[serde(default)]
+    pub serializable: bool,
+    #[prost(bool, tag = "8")]
+    #[serde(default)]
+    pub keys_only: bool,
+    #[prost(bool, tag = "9")]
+    #[serde(default)]
+    pub count_only: bool,
+    #[prost(int64, tag = "10")]
+    #[serde(default, with = "json_int64")]
+    pub min_mod_revision: i64,
+    #[prost(int64, tag = "11")]
+    #[serde(default, with = "json_int64")]
+    pub max_mod_revision: i64,
+    #[prost(int64, tag = "12")]
+    #[serde(default, with = "json_int64")]
+    pub min_create_revision: i64,
+    #[prost(int64, tag = "13")]
+    #[serde(default, with = "json_int64")]
+    pub max_create_revision: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct RangeResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(message, repeated, tag = "2")]
+    #[serde(default)]
+    pub kvs: Vec<KeyValue>,
+    #[prost(bool, tag = "3")]
+    #[serde(default)]
+    pub more: bool,
+    #[prost(int64, tag = "4")]
+    #[serde(default, with = "json_int64")]
+    pub count: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct PutRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    #[serde(default, with = "json_bytes")]
+    pub key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    #[serde(default, with = "json_bytes")]
+    pub value: Vec<u8>,
+    #[prost(int64, tag = "3")]
+    #[serde(default, with = "json_int64")]
+    pub lease: i64,
+    #[prost(bool, tag = "4")]
+    #[serde(default)]
+    pub prev_kv: bool,
+    #[prost(bool, tag = "5")]
+    #[serde(default)]
+    pub ignore_value: bool,
+    #[prost(bool, tag = "6")]
+    #[serde(default)]
+    pub ignore_lease: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct PutResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(message, optional, tag = "2")]
+    #[serde(default)]
+    pub prev_kv: Option<KeyValue>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct DeleteRangeRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    #[serde(default, with = "json_bytes")]
+    pub key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    #[serde(default, with = "json_bytes")]
+    pub range_end: Vec<u8>,
+    #[prost(bool, tag = "3")]
+    #[serde(default)]
+    pub prev_kv: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct DeleteRangeResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(int64, tag = "2")]
+    #[serde(default, with = "json_int64")]
+    pub deleted: i64,
+    #[prost(message, repeated, tag = "3")]
+    #[serde(default)]
+    pub prev_kvs: Vec<KeyValue>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseGrantRequest {
+    #[prost(int64, tag = "1")]
+    #[serde(default, rename = "TTL", with = "json_int64")]
+    pub ttl: i64,
+    #[prost(int64, tag = "2")]
+    #[serde(default, rename = "ID", with = "json_int64")]
+    pub id: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseGrantResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(int64, tag = "2")]
+    #[serde(default, rename = "ID", with = "json_int64")]
+    pub id: i64,
+    #[prost(int64, tag = "3")]
+    #[serde(default, rename = "TTL", with = "json_int64")]
+    pub ttl: i64,
+    #[prost(string, tag = "4")]
+    #[serde(default)]
+    pub error: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseRevokeRequest {
+    #[prost(int64, tag = "1")]
+    #[serde(default, rename = "ID", with = "json_int64")]
+    pub id: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseRevokeResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseTimeToLiveRequest {
+    #[prost(int64, tag = "1")]
+    #[serde(default, rename = "ID", with = "json_int64")]
+    pub id: i64,
+    #[prost(bool, tag = "2")]
+    #[serde(default)]
+    pub keys: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseTimeToLiveResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(int64, tag = "2")]
+    #[serde(default, rename = "ID", with = "json_int64")]
+    pub id: i64,
+    #[prost(int64, tag = "3")]
+    #[serde(default, rename = "TTL", with = "json_int64")]
+    pub ttl: i64,
+    #[prost(int64, tag = "4")]
+    #[serde(default, rename = "grantedTTL", with = "json_int64")]
+    pub granted_ttl: i64,
+    #[prost(bytes = "vec", repeated, tag = "5")]
+    #[serde(default, with = "json_bytes_list")]
+    pub keys: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseLeasesRequest {}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseStatus {
+    #[prost(int64, tag = "1")]
+    #[serde(default, rename = "ID", with = "json_int64")]
+    pub id: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct LeaseLeasesResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(message, repeated, tag = "2")]
+    #[serde(default)]
+    pub leases: Vec<LeaseStatus>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct AuthenticateRequest {
+    #[prost(string, tag = "1")]
+    #[serde(default)]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    #[serde(default)]
+    pub password: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct AuthenticateResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(string, tag = "2")]
+    #[serde(default)]
+    pub token: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct AuthUserGetRequest {
+    #[prost(string, tag = "1")]
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct AuthUserGetResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(string, repeated, tag = "2")]
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct AuthRoleGetRequest {
+    #[prost(string, tag = "1")]
+    #[serde(default)]
+    pub role: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct Permission {
+    /// READ = 0, WRITE = 1, READWRITE = 2
+    #[prost(int32, tag = "1")]
+    #[serde(default, rename = "permType", deserialize_with = "de_permission_type")]
+    pub perm_type: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    #[serde(default, with = "json_bytes")]
+    pub key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    #[serde(default, with = "json_bytes")]
+    pub range_end: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct AuthRoleGetResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(message, repeated, tag = "2")]
+    #[serde(default)]
+    pub perm: Vec<Permission>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchRequest {
+    #[prost(oneof = "WatchRequestUnion", tags = "1, 2, 3")]
+    pub request_union: Option<WatchRequestUnion>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Oneof)]
+pub enum WatchRequestUnion {
+    #[prost(message, tag = "1")]
+    CreateRequest(WatchCreateRequest),
+    #[prost(message, tag = "2")]
+    CancelRequest(WatchCancelRequest),
+    #[prost(message, tag = "3")]
+    ProgressRequest(WatchProgressRequest),
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct WatchCreateRequest {
+    #[prost(bytes = "vec", tag = "1")]
+    #[serde(default, with = "json_bytes")]
+    pub key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    #[serde(default, with = "json_bytes")]
+    pub range_end: Vec<u8>,
+    #[prost(int64, tag = "3")]
+    #[serde(default, with = "json_int64")]
+    pub start_revision: i64,
+    #[prost(bool, tag = "4")]
+    #[serde(default)]
+    pub progress_notify: bool,
+    /// NOPUT = 0, NODELETE = 1
+    #[prost(int32, repeated, tag = "5")]
+    #[serde(default)]
+    pub filters: Vec<i32>,
+    #[prost(bool, tag = "6")]
+    #[serde(default)]
+    pub prev_kv: bool,
+    #[prost(int64, tag = "7")]
+    #[serde(default, with = "json_int64")]
+    pub watch_id: i64,
+    #[prost(bool, tag = "8")]
+    #[serde(default)]
+    pub fragment: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchCancelRequest {
+    #[prost(int64, tag = "1")]
+    pub watch_id: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchProgressRequest {}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct Event {
+    /// PUT = 0, DELETE = 1
+    #[prost(int32, tag = "1")]
+    #[serde(default, rename = "type", deserialize_with = "de_event_type")]
+    pub event_type: i32,
+    #[prost(message, optional, tag = "2")]
+    #[serde(default)]
+    pub kv: Option<KeyValue>,
+    #[prost(message, optional, tag = "3")]
+    #[serde(default)]
+    pub prev_kv: Option<KeyValue>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message, Serialize, Deserialize)]
+pub struct WatchResponse {
+    #[prost(message, optional, tag = "1")]
+    #[serde(default)]
+    pub header: Option<ResponseHeader>,
+    #[prost(int64, tag = "2")]
+    #[serde(default, with = "json_int64")]
+    pub watch_id: i64,
+    #[prost(bool, tag = "3")]
+    #[serde(default)]
+    pub created: bool,
+    #[prost(bool, tag = "4")]
+    #[serde(default)]
+    pub canceled: bool,
+    #[prost(int64, tag = "5")]
+    #[serde(default, with = "json_int64")]
+    pub compact_revision: i64,
+    #[prost(string, tag = "6")]
+    #[serde(default)]
+    pub cancel_reason: String,
+    #[prost(bool, tag = "7")]
+    #[serde(default)]
+    pub fragment: bool,
+    #[prost(message, repeated, tag = "11")]
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+/// gateway 返回的错误
+#[derive(Debug, Deserialize)]
+pub struct GatewayError {
+    #[serde(default)]
+    pub code: i32,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// watch 流中的一条消息
+#[derive(Deserialize)]
+pub struct WatchStreamChunk {
+    #[serde(default)]
+    pub result: Option<WatchResponse>,
+    #[serde(default)]
+    pub error: Option<GatewayError>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonNumber {
+    Number(i64),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonUnsigned {
+    Number(u64),
+    Text(String),
+}
+
+mod json_int64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::JsonNumber;
+
+    pub fn serialize<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(*value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        match JsonNumber::deserialize(deserializer)? {
+            JsonNumber::Number(n) => Ok(n),
+            JsonNumber::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+mod json_uint64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::JsonUnsigned;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(*value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        match JsonUnsigned::deserialize(deserializer)? {
+            JsonUnsigned::Number(n) => Ok(n),
+            JsonUnsigned::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+mod json_bytes {
+    use base64::prelude::BASE64_STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        BASE64_STANDARD
+            .decode(s.as_bytes())
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+mod json_bytes_list {
+    use base64::prelude::BASE64_STANDARD;
+    use base64::Engine;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Vec<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(value.len()))?;
+        for item in value {
+            seq.serialize_element(&BASE64_STANDARD.encode(item))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| BASE64_STANDARD.decode(s.as_bytes()))
+            .collect::<Result<Vec<Vec<u8>>, _>>()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn de_enum<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+    names: &[&str],
+) -> Result<i32, D::Error> {
+    match JsonNumber::deserialize(deserializer)? {
+        JsonNumber::Number(n) => Ok(n as i32),
+        JsonNumber::Text(s) => names
+            .iter()
+            .position(|name| *name == s)
+            .map(|idx| idx as i32)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown enum value: {}", s))),
+    }
+}
+
+fn de_event_type<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    de_enum(deserializer, &["PUT", "DELETE"])
+}
+
+fn de_permission_type<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    de_enum(deserializer, &["READ", "WRITE", "READWRITE"])
+}
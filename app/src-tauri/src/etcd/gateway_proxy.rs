@@ -0,0 +1,659 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use log::{debug, info, warn};
+use prost::Message;
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::select;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::error::LogicError;
+use crate::transport::connection::ConnectionTls;
+use crate::utils::http_util;
+
+use super::gateway_proto::{
+    AuthRoleGetRequest, AuthRoleGetResponse, AuthUserGetRequest, AuthUserGetResponse,
+    AuthenticateRequest, AuthenticateResponse, DeleteRangeRequest, DeleteRangeResponse,
+    GatewayError, LeaseGrantRequest, LeaseGrantResponse, LeaseLeasesRequest, LeaseLeasesResponse,
+    LeaseRevokeRequest, LeaseRevokeResponse, LeaseTimeToLiveRequest, LeaseTimeToLiveResponse,
+    PutRequest, PutResponse, RangeRequest, RangeResponse, WatchCreateRequest, WatchRequest,
+    WatchRequestUnion, WatchResponse, WatchStreamChunk,
+};
+
+/// grpc-gateway 默认的路径前缀
+pub const DEFAULT_GATEWAY_PREFIX: &str = "/v3";
+
+const GRPC_OK: i32 = 0;
+const GRPC_UNKNOWN: i32 = 2;
+const GRPC_INVALID_ARGUMENT: i32 = 3;
+const GRPC_NOT_FOUND: i32 = 5;
+const GRPC_PERMISSION_DENIED: i32 = 7;
+const GRPC_UNIMPLEMENTED: i32 = 12;
+const GRPC_UNAVAILABLE: i32 = 14;
+const GRPC_UNAUTHENTICATED: i32 = 16;
+
+/// gRPC 消息帧头长度：1 字节压缩标记 + 4 字节消息长度
+const FRAME_HEADER_LEN: usize = 5;
+
+/// etcd 客户端认证后通过该 gRPC 元数据传递 token
+const TOKEN_HEADER: &str = "token";
+
+/// 一个 grpc-gateway 节点
+pub struct GatewayEndpoint {
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl GatewayEndpoint {
+    /// `endpoint` 为节点地址，`address` 为实际连接地址（经过 ssh 时为本地转发地址）
+    pub async fn new(
+        endpoint: &str,
+        address: &str,
+        prefix: &str,
+        tls: &Option<ConnectionTls>,
+    ) -> Result<Self, LogicError> {
+        let mut url = parse_url(endpoint)?;
+        let address = parse_url(address)?;
+        let connect_host = address
+            .host_str()
+            .map(|host| {
+                host.trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string()
+            })
+            .ok_or(LogicError::MsgError(format!(
+                "Invalid gateway address: {}",
+                address
+            )))?;
+        let connect_port = address
+            .port_or_known_default()
+            .ok_or(LogicError::MsgError(format!(
+                "Invalid gateway address: {}",
+                address
+            )))?;
+
+        url.set_path(prefix.trim_end_matches('/'));
+        //  watch 是长连接，不设置整体超时，普通请求的超时由 etcd 客户端控制
+        let (client, base_url) =
+            http_util::build_client(&url, &connect_host, connect_port, tls, None).await?;
+        Ok(GatewayEndpoint { base_url, client })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.as_str().trim_end_matches('/'), path)
+    }
+}
+
+/// 将 etcd 客户端的 gRPC 请求转换为 grpc-gateway 的 http json 请求的本地代理
+///
+/// etcd 客户端连接代理监听的本地端口，代理负责与 gateway 之间的 tls、base64 编码与认证 token 的传递。
+/// 只支持 kv、lease、watch 与认证相关的接口，其他接口返回 `UNIMPLEMENTED`。
+/// 配置了多个节点时，连接失败的请求会依次切换到下一个节点
+pub struct GatewayProxy {
+    port: u16,
+    shutdown_sender: Option<oneshot::Sender<()>>,
+}
+
+impl GatewayProxy {
+    pub async fn start(endpoints: Vec<GatewayEndpoint>) -> Result<Self, LogicError> {
+        if endpoints.is_empty() {
+            return Err(LogicError::ArgumentError);
+        }
+        let gateway = Arc::new(Gateway {
+            endpoints,
+            current: AtomicUsize::new(0),
+        });
+
+        let make_service = make_service_fn(move |_| {
+            let gateway = Arc::clone(&gateway);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle(Arc::clone(&gateway), request)
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .map_err(|e| LogicError::MsgError(format!("Failed to start gateway proxy: {}", e)))?
+            .http2_only(true)
+            .serve(make_service);
+        let port = server.local_addr().port();
+
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let server = server.with_graceful_shutdown(async {
+                let _ = shutdown_receiver.await;
+            });
+            if let Err(e) = server.await {
+                warn!("Gateway proxy {} exited with error: {}", port, e);
+            }
+            info!("Gateway proxy shutdown: {}", port);
+        });
+        info!("Started gateway proxy on port {}", port);
+
+        Ok(GatewayProxy {
+            port,
+            shutdown_sender: Some(shutdown_sender),
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn shutdown(&mut self) {
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Drop for GatewayProxy {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+struct Gateway {
+    endpoints: Vec<GatewayEndpoint>,
+    /// 当前使用的节点下标
+    current: AtomicUsize,
+}
+
+impl Gateway {
+    /// 发送请求，连接失败时依次切换到其他节点
+    async fn post(
+        &self,
+        path: &str,
+        authorization: &Option<HeaderValue>,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let start = self.current.load(Ordering::Relaxed);
+        let mut last_error = None;
+        for i in 0..self.endpoints.len() {
+            let idx = (start + i) % self.endpoints.len();
+            let endpoint = &self.endpoints[idx];
+
+            let mut request = endpoint
+                .client
+                .post(endpoint.url(path))
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization.clone());
+            }
+
+            match request.send().await {
+                Ok(response) => {
+                    if idx != start {
+                        info!("Gateway switched to endpoint: {}", endpoint.base_url);
+                        self.current.store(idx, Ordering::Relaxed);
+                    }
+                    return Ok(response);
+                }
+                Err(e) if e.is_connect() => {
+                    warn!(
+                        "Gateway endpoint {} is unavailable: {}",
+                        endpoint.base_url, e
+                    );
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap())
+    }
+}
+
+struct Status {
+    code: i32,
+    message: String,
+}
+
+impl Status {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Status {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn trailers(&self) -> HeaderMap {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from(self.code));
+        if !self.message.is_empty() {
+            if let Ok(message) = HeaderValue::from_str(&percent_encode(&self.message)) {
+                trailers.insert("grpc-message", message);
+            }
+        }
+        trailers
+    }
+}
+
+async fn handle(
+    gateway: Arc<Gateway>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let path = request.uri().path().to_string();
+    let authorization = authorization(request.headers());
+    debug!("Gateway proxy request: {}", path);
+
+    if path == "/etcdserverpb.Watch/Watch" {
+        return Ok(watch(gateway, authorization, request.into_body()));
+    }
+
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            return Ok(grpc_response(Err(Status::new(
+                GRPC_INVALID_ARGUMENT,
+                e.to_string(),
+            ))))
+        }
+    };
+
+    let gateway = gateway.as_ref();
+    let auth = &authorization;
+    let result = match path.as_str() {
+        "/etcdserverpb.KV/Range" => {
+            unary::<RangeRequest, RangeResponse>(gateway, "/kv/range", auth, body).await
+        }
+        "/etcdserverpb.KV/Put" => {
+            unary::<PutRequest, PutResponse>(gateway, "/kv/put", auth, body).await
+        }
+        "/etcdserverpb.KV/DeleteRange" => {
+            unary::<DeleteRangeRequest, DeleteRangeResponse>(gateway, "/kv/deleterange", auth, body)
+                .await
+        }
+        "/etcdserverpb.Lease/LeaseGrant" => {
+            unary::<LeaseGrantRequest, LeaseGrantResponse>(gateway, "/lease/grant", auth, body)
+                .await
+        }
+        "/etcdserverpb.Lease/LeaseRevoke" => {
+            unary::<LeaseRevokeRequest, LeaseRevokeResponse>(gateway, "/lease/revoke", auth, body)
+                .await
+        }
+        "/etcdserverpb.Lease/LeaseTimeToLive" => {
+            unary::<LeaseTimeToLiveRequest, LeaseTimeToLiveResponse>(
+                gateway,
+                "/lease/timetolive",
+                auth,
+                body,
+            )
+            .await
+        }
+        "/etcdserverpb.Lease/LeaseLeases" => {
+            unary::<LeaseLeasesRequest, LeaseLeasesResponse>(gateway, "/lease/leases", auth, body)
+                .await
+        }
+        "/etcdserverpb.Auth/Authenticate" => {
+            unary::<AuthenticateRequest, AuthenticateResponse>(
+                gateway,
+                "/auth/authenticate",
+                auth,
+                body,
+            )
+            .await
+        }
+        "/etcdserverpb.Auth/UserGet" => {
+            unary::<AuthUserGetRequest, AuthUserGetResponse>(gateway, "/auth/user/get", auth, body)
+                .await
+        }
+        "/etcdserverpb.Auth/RoleGet" => {
+            unary::<AuthRoleGetRequest, AuthRoleGetResponse>(gateway, "/auth/role/get", auth, body)
+                .await
+        }
+        _ => Err(Status::new(
+            GRPC_UNIMPLEMENTED,
+            format!("{} is not supported by grpc-gateway transport", path),
+        )),
+    };
+    Ok(grpc_response(result))
+}
+
+/// grpc-gateway 从 `Authorization` 头读取 token，etcd 客户端则放在 `token` 元数据中，watch 与普通请求共用
+fn authorization(headers: &HeaderMap) -> Option<HeaderValue> {
+    headers
+        .get(AUTHORIZATION)
+        .or_else(|| headers.get(TOKEN_HEADER))
+        .cloned()
+}
+
+async fn unary<Req, Resp>(
+    gateway: &Gateway,
+    path: &str,
+    authorization: &Option<HeaderValue>,
+    body: Bytes,
+) -> Result<Vec<u8>, Status>
+where
+    Req: Message + Default + Serialize,
+    Resp: Message + DeserializeOwned,
+{
+    let mut buffer = body.to_vec();
+    let message = take_frame(&mut buffer)?.ok_or(Status::new(
+        GRPC_INVALID_ARGUMENT,
+        "incomplete grpc message",
+    ))?;
+    let request = Req::decode(message.as_slice())
+        .map_err(|e| Status::new(GRPC_INVALID_ARGUMENT, e.to_string()))?;
+    let json =
+        serde_json::to_vec(&request).map_err(|e| Status::new(GRPC_UNKNOWN, e.to_string()))?;
+
+    let response = gateway
+        .post(path, authorization, json)
+        .await
+        .map_err(|e| Status::new(GRPC_UNAVAILABLE, e.to_string()))?;
+    let status = response.status();
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| Status::new(GRPC_UNAVAILABLE, e.to_string()))?;
+    if !status.is_success() {
+        return Err(gateway_error(status, &bytes));
+    }
+
+    let response: Resp = serde_json::from_slice(&bytes).map_err(|e| {
+        Status::new(
+            GRPC_UNKNOWN,
+            format!("Invalid grpc-gateway response: {}", e),
+        )
+    })?;
+    Ok(encode_frame(&response))
+}
+
+/// watch 是双向流：客户端可以在同一个流上创建与取消多个 watch，
+/// 而 gateway 的每个 http 流只对应一个 watch，因此每个创建请求都单独发起一个 http 流，并由代理分配 watch id
+fn watch(
+    gateway: Arc<Gateway>,
+    authorization: Option<HeaderValue>,
+    mut body: Body,
+) -> Response<Body> {
+    let (mut sender, response_body) = Body::channel();
+    tokio::spawn(async move {
+        let (event_sender, mut event_receiver) = mpsc::channel::<Result<WatchResponse, Status>>(64);
+        let mut watchers: HashMap<i64, JoinHandle<()>> = HashMap::new();
+        let mut next_watch_id = 0i64;
+        let mut buffer: Vec<u8> = vec![];
+
+        loop {
+            select! {
+                chunk = body.data() => {
+                    let chunk = match chunk {
+                        Some(Ok(chunk)) => chunk,
+                        _ => break,
+                    };
+                    buffer.extend_from_slice(&chunk);
+                    loop {
+                        let message = match take_frame(&mut buffer) {
+                            Ok(Some(message)) => message,
+                            Ok(None) => break,
+                            Err(status) => {
+                                let _ = sender.send_trailers(status.trailers()).await;
+                                return;
+                            }
+                        };
+                        let request = match WatchRequest::decode(message.as_slice()) {
+                            Ok(request) => request,
+                            Err(e) => {
+                                warn!("Gateway proxy received invalid watch request: {}", e);
+                                continue;
+                            }
+                        };
+                        match request.request_union {
+                            Some(WatchRequestUnion::CreateRequest(create)) => {
+                                let watch_id = if create.watch_id > 0 {
+                                    create.watch_id
+                                } else {
+                                    while watchers.contains_key(&next_watch_id) {
+                                        next_watch_id += 1;
+                                    }
+                                    next_watch_id
+                                };
+                                let task = tokio::spawn(watch_stream(
+                                    Arc::clone(&gateway),
+                                    authorization.clone(),
+                                    create,
+                                    watch_id,
+                                    event_sender.clone(),
+                                ));
+                                if let Some(old) = watchers.insert(watch_id, task) {
+                                    old.abort();
+                                }
+                            }
+                            Some(WatchRequestUnion::CancelRequest(cancel)) => {
+                                if let Some(task) = watchers.remove(&cancel.watch_id) {
+                                    task.abort();
+                                }
+                                let _ = event_sender
+                                    .send(Ok(WatchResponse {
+                                        watch_id: cancel.watch_id,
+                                        canceled: true,
+                                        ..Default::default()
+                                    }))
+                                    .await;
+                            }
+                            //  gateway 的 watch 流不支持主动请求进度通知
+                            _ => {}
+                        }
+                    }
+                },
+                event = event_receiver.recv() => {
+                    match event {
+                        Some(Ok(response)) => {
+                            let frame = encode_frame(&response);
+                            if sender.send_data(Bytes::from(frame)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(Err(status)) => {
+                            let _ = sender.send_trailers(status.trailers()).await;
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        for task in watchers.values() {
+            task.abort();
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/grpc")
+        .body(response_body)
+        .unwrap()
+}
+
+async fn watch_stream(
+    gateway: Arc<Gateway>,
+    authorization: Option<HeaderValue>,
+    create: WatchCreateRequest,
+    watch_id: i64,
+    sender: mpsc::Sender<Result<WatchResponse, Status>>,
+) {
+    let body = match serde_json::to_vec(&serde_json::json!({ "create_request": create })) {
+        Ok(body) => body,
+        Err(e) => {
+            let _ = sender
+                .send(Err(Status::new(GRPC_UNKNOWN, e.to_string())))
+                .await;
+            return;
+        }
+    };
+
+    let mut response = match gateway.post("/watch", &authorization, body).await {
+        Ok(response) => response,
+        Err(e) => {
+            let _ = sender
+                .send(Err(Status::new(GRPC_UNAVAILABLE, e.to_string())))
+                .await;
+            return;
+        }
+    };
+    let status = response.status();
+    if !status.is_success() {
+        let bytes = response.bytes().await.unwrap_or_default();
+        let _ = sender.send(Err(gateway_error(status, &bytes))).await;
+        return;
+    }
+
+    //  gateway 的流式响应中每条消息以换行分隔
+    let mut buffer: Vec<u8> = vec![];
+    let cancel_reason = loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                buffer.extend_from_slice(&chunk);
+                while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                    let line = buffer.drain(..=pos).collect::<Vec<u8>>();
+                    if line.iter().all(|b| b.is_ascii_whitespace()) {
+                        continue;
+                    }
+                    match serde_json::from_slice::<WatchStreamChunk>(&line) {
+                        Ok(WatchStreamChunk {
+                            result: Some(mut result),
+                            ..
+                        }) => {
+                            result.watch_id = watch_id;
+                            if sender.send(Ok(result)).await.is_err() {
+                                return;
+                            }
+                        }
+                        Ok(WatchStreamChunk {
+                            error: Some(error), ..
+                        }) => {
+                            let message = error.error.unwrap_or(error.message);
+                            let _ = sender.send(Err(Status::new(error.code, message))).await;
+                            return;
+                        }
+                        Ok(_) => {}
+                        Err(e) => warn!("Invalid grpc-gateway watch response: {}", e),
+                    }
+                }
+            }
+            Ok(None) => break String::from("grpc-gateway watch stream closed"),
+            Err(e) => break e.to_string(),
+        }
+    };
+
+    let _ = sender
+        .send(Ok(WatchResponse {
+            watch_id,
+            canceled: true,
+            cancel_reason,
+            ..Default::default()
+        }))
+        .await;
+}
+
+fn grpc_response(result: Result<Vec<u8>, Status>) -> Response<Body> {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        match result {
+            Ok(frame) => {
+                if sender.send_data(Bytes::from(frame)).await.is_ok() {
+                    let _ = sender
+                        .send_trailers(Status::new(GRPC_OK, "").trailers())
+                        .await;
+                }
+            }
+            Err(status) => {
+                let _ = sender.send_trailers(status.trailers()).await;
+            }
+        }
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/grpc")
+        .body(body)
+        .unwrap()
+}
+
+/// gateway 的错误响应转换为 gRPC 状态，响应体中没有状态码时按 http 状态码推断
+fn gateway_error(status: reqwest::StatusCode, body: &[u8]) -> Status {
+    match serde_json::from_slice::<GatewayError>(body) {
+        Ok(error) if error.code > 0 => {
+            let message = if error.message.is_empty() {
+                error.error.unwrap_or_default()
+            } else {
+                error.message
+            };
+            Status::new(error.code, message)
+        }
+        _ => {
+            let code = match status.as_u16() {
+                400 => GRPC_INVALID_ARGUMENT,
+                401 => GRPC_UNAUTHENTICATED,
+                403 => GRPC_PERMISSION_DENIED,
+                404 => GRPC_NOT_FOUND,
+                501 => GRPC_UNIMPLEMENTED,
+                502..=504 => GRPC_UNAVAILABLE,
+                _ => GRPC_UNKNOWN,
+            };
+            Status::new(
+                code,
+                format!("grpc-gateway {}: {}", status, String::from_utf8_lossy(body)),
+            )
+        }
+    }
+}
+
+/// 从缓冲区中取出一个完整的 gRPC 消息，数据不完整时返回 None
+fn take_frame(buffer: &mut Vec<u8>) -> Result<Option<Vec<u8>>, Status> {
+    if buffer.len() < FRAME_HEADER_LEN {
+        return Ok(None);
+    }
+    if buffer[0] != 0 {
+        return Err(Status::new(
+            GRPC_UNIMPLEMENTED,
+            "compressed grpc message is not supported",
+        ));
+    }
+    let len = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+    if buffer.len() < FRAME_HEADER_LEN + len {
+        return Ok(None);
+    }
+    let message = buffer[FRAME_HEADER_LEN..FRAME_HEADER_LEN + len].to_vec();
+    buffer.drain(..FRAME_HEADER_LEN + len);
+    Ok(Some(message))
+}
+
+fn encode_frame<M: Message>(message: &M) -> Vec<u8> {
+    let data = message.encode_to_vec();
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + data.len());
+    frame.push(0u8);
+    frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&data);
+    frame
+}
+
+/// grpc-message 需要对非可见字符与 `%` 进行百分号编码
+fn percent_encode(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for b in message.bytes() {
+        if (0x20..0x7f).contains(&b) && b != b'%' {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+fn parse_url(url: &str) -> Result<Url, LogicError> {
+    Url::parse(url)
+        .map_err(|e| LogicError::MsgError(format!("Invalid gateway url '{}': {}", url, e)))
+}
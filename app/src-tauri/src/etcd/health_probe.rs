@@ -0,0 +1,152 @@
+use std::time::Instant;
+
+use etcd_client::GetOptions;
+
+use crate::error::LogicError;
+use crate::transport::maintenance::{ClusterHealth, LatencyStats, MemberHealth};
+
+use super::etcd_connector::MemberClient;
+use super::get_connector;
+
+/// 单次健康检查最多的探测次数
+pub const MAX_PROBES: u32 = 100;
+
+/// 参考 `etcdctl endpoint health` 对每个成员进行多次线性一致读与 status 请求，统计往返延迟
+///
+/// 只在建立成员连接时持有会话，各成员的探测并发执行
+pub async fn cluster_health(session: i32, probes: u32) -> Result<ClusterHealth, LogicError> {
+    let probes = probes.clamp(1, MAX_PROBES);
+    let (ssh_tunnel, members) = {
+        let mut connector = get_connector(&session)?;
+        (connector.is_ssh_tunnel(), connector.member_clients().await?)
+    };
+
+    let tasks = members
+        .into_iter()
+        .map(|member| tokio::spawn(probe_member(member, probes)))
+        .collect::<Vec<_>>();
+
+    let mut members = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        members.push(
+            task.await
+                .map_err(|e| LogicError::MsgError(e.to_string()))?,
+        );
+    }
+
+    Ok(ClusterHealth {
+        ssh_tunnel,
+        probes,
+        members,
+    })
+}
+
+async fn probe_member(member: MemberClient, probes: u32) -> MemberHealth {
+    let mut health = MemberHealth {
+        member_id: member.id,
+        name: member.name,
+        client_urls: member.client_urls,
+        healthy: false,
+        read_latency: None,
+        status_latency: None,
+        failed_probes: 0,
+        leader: None,
+        raft_index: None,
+        db_size: None,
+        errors: vec![],
+    };
+
+    let mut client = match member.client {
+        Ok(client) => client,
+        Err(e) => {
+            health.failed_probes = probes as usize;
+            health.errors.push(format!("{:?}", e));
+            return health;
+        }
+    };
+
+    let mut read_samples = Vec::with_capacity(probes as usize);
+    let mut status_samples = Vec::with_capacity(probes as usize);
+    for _ in 0..probes {
+        let mut failed = false;
+
+        let start = Instant::now();
+        let read_result = client
+            .kv_get_request(
+                Vec::from("health"),
+                Some(GetOptions::new().with_count_only()),
+            )
+            .await;
+        match read_result {
+            Ok(_) => read_samples.push(elapsed_millis(start)),
+            //  与 etcdctl 一致，没有读权限也说明成员可以正常处理请求
+            Err(e) if e.to_string().contains("permission denied") => {
+                read_samples.push(elapsed_millis(start))
+            }
+            Err(e) => {
+                failed = true;
+                add_error(&mut health.errors, e.to_string());
+            }
+        }
+
+        let start = Instant::now();
+        match client.status().await {
+            Ok(status) => {
+                status_samples.push(elapsed_millis(start));
+                if !status.errors().is_empty() {
+                    failed = true;
+                    add_error(&mut health.errors, status.errors().join(", "));
+                }
+                health.leader = Some(status.leader().to_string());
+                health.raft_index = Some(status.raft_index());
+                health.db_size = Some(status.db_size());
+            }
+            Err(e) => {
+                failed = true;
+                add_error(&mut health.errors, e.to_string());
+            }
+        }
+
+        if failed {
+            health.failed_probes += 1;
+        }
+    }
+
+    health.healthy = health.failed_probes == 0;
+    health.read_latency = latency_stats(read_samples);
+    health.status_latency = latency_stats(status_samples);
+    health
+}
+
+fn elapsed_millis(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+fn add_error(errors: &mut Vec<String>, error: String) {
+    if !errors.contains(&error) {
+        errors.push(error);
+    }
+}
+
+pub fn latency_stats(mut samples: Vec<f64>) -> Option<LatencyStats> {
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let sum: f64 = samples.iter().sum();
+    Some(LatencyStats {
+        samples: samples.len(),
+        min: samples[0],
+        max: samples[samples.len() - 1],
+        avg: sum / samples.len() as f64,
+        p50: percentile(&samples, 50.0),
+        p90: percentile(&samples, 90.0),
+        p99: percentile(&samples, 99.0),
+    })
+}
+
+/// 最近秩法计算百分位数，`sorted` 必须已升序排列且不为空
+fn percentile(sorted: &Vec<f64>, p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
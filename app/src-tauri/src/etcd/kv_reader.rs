@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+
+use crate::error::LogicError;
+use crate::transport::kv::SerializableKeyValue;
+
+use super::etcd_connector::EtcdConnector;
+
+/// 只读的 key-value 视图，在线会话与离线快照会话共用
+#[async_trait]
+pub trait KvReader: Send + Sync {
+    /// 获取所有key，不包含value
+    async fn kv_get_all_keys(&mut self) -> Result<Vec<SerializableKeyValue>, LogicError>;
+
+    /// 获取键值对详情
+    async fn kv_get(&mut self, key: Vec<u8>) -> Result<SerializableKeyValue, LogicError>;
+
+    /// 根据历史版本获取键值对详情
+    async fn kv_get_by_version(
+        &mut self,
+        key: Vec<u8>,
+        version: i64,
+    ) -> Result<SerializableKeyValue, LogicError>;
+
+    /// 获取某一个key在 `start` 与 `end` 之间的历史版本，按版本倒序排列
+    async fn kv_get_history_versions(
+        &mut self,
+        key: Vec<u8>,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<i64>, LogicError>;
+}
+
+#[async_trait]
+impl KvReader for EtcdConnector {
+    async fn kv_get_all_keys(&mut self) -> Result<Vec<SerializableKeyValue>, LogicError> {
+        Ok(EtcdConnector::kv_get_all_keys(self).await?)
+    }
+
+    async fn kv_get(&mut self, key: Vec<u8>) -> Result<SerializableKeyValue, LogicError> {
+        EtcdConnector::kv_get(self, key).await
+    }
+
+    async fn kv_get_by_version(
+        &mut self,
+        key: Vec<u8>,
+        version: i64,
+    ) -> Result<SerializableKeyValue, LogicError> {
+        EtcdConnector::kv_get_by_version(self, key, version).await
+    }
+
+    async fn kv_get_history_versions(
+        &mut self,
+        key: Vec<u8>,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<i64>, LogicError> {
+        Ok(EtcdConnector::kv_get_history_versions(self, key, start, end).await?)
+    }
+}
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use reqwest::Url;
+
+use crate::error::LogicError;
+use crate::proxy::TcpForwarder;
+use crate::transport::connection::{Connection, ConnectionTls};
+use crate::transport::metrics::MetricFamily;
+use crate::utils::{http_util, prometheus};
+
+const SCRAPE_TIMEOUT_SECONDS: u64 = 10;
+
+/// 指标采集地址
+///
+/// `url` 保留配置中的逻辑地址，实际连接 `connect_host:connect_port`（经过 ssh 或代理时为本地转发端口）。
+/// 请求时 url 中的域名作为 SNI 与证书校验的主机名，并将该域名解析到实际连接的地址。
+#[derive(Clone)]
+pub struct MetricsEndpoint {
+    url: Url,
+    connect_host: String,
+    connect_port: u16,
+    tls: Option<ConnectionTls>,
+}
+
+impl MetricsEndpoint {
+    /// 连接配置的指标地址，未配置时使用客户端地址 `endpoint`，协议与该地址保持一致
+    pub fn connection_url(connection: &Connection, endpoint: &str) -> Result<Url, LogicError> {
+        match &connection.metrics_url {
+            Some(url) if !url.trim().is_empty() => parse_url(url.trim()),
+            _ => parse_url(&format!("{}/metrics", endpoint)),
+        }
+    }
+
+    pub async fn new(
+        url: Url,
+        forwarder: Option<&mut dyn TcpForwarder>,
+        tls: Option<ConnectionTls>,
+    ) -> Result<Self, LogicError> {
+        let host = url_host(&url)?;
+        let port = url
+            .port_or_known_default()
+            .ok_or(LogicError::MsgError(format!(
+                "Invalid metrics url: {}",
+                url
+            )))?;
+
+        let (connect_host, connect_port) = match forwarder {
+            Some(forwarder) => (
+                String::from("127.0.0.1"),
+                forwarder.forward(host, port).await?,
+            ),
+            None => (host, port),
+        };
+
+        Ok(MetricsEndpoint {
+            url,
+            connect_host,
+            connect_port,
+            tls,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        self.url.to_string()
+    }
+
+    pub async fn scrape(&self) -> Result<Vec<MetricFamily>, LogicError> {
+        let (client, url) = http_util::build_client(
+            &self.url,
+            &self.connect_host,
+            self.connect_port,
+            &self.tls,
+            Some(Duration::from_secs(SCRAPE_TIMEOUT_SECONDS)),
+        )
+        .await?;
+
+        let response = client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(prometheus::parse(&response))
+    }
+}
+
+pub fn parse_url(url: &str) -> Result<Url, LogicError> {
+    Url::parse(url)
+        .map_err(|e| LogicError::MsgError(format!("Invalid metrics url '{}': {}", url, e)))
+}
+
+/// url 中的 host，ipv6 地址去掉方括号
+pub fn url_host(url: &Url) -> Result<String, LogicError> {
+    url.host_str()
+        .map(|host| {
+            host.trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string()
+        })
+        .ok_or(LogicError::MsgError(format!(
+            "Invalid metrics url: {}",
+            url
+        )))
+}
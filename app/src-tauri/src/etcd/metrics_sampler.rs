@@ -0,0 +1,134 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use log::{debug, info};
+use tokio::sync::oneshot;
+use tokio::time::interval;
+
+use crate::transport::metrics::{MetricFamily, MetricPoint, MetricSeries, MetricType};
+
+use super::{get_connector_optional, now_timestamp};
+
+/// 采样间隔
+const SAMPLE_INTERVAL_SECONDS: u64 = 15;
+
+/// 每个指标保留的采样点数量，默认保留最近一小时
+const SERIES_CAPACITY: usize = 240;
+
+/// 会话级别的指标采样任务，周期性地读取 `/metrics` 并在内存中保留最近一段时间的数值
+///
+/// 只记录 counter、gauge 与 untyped 类型的指标，histogram 与 summary 仍可通过实时查询获取
+pub struct MetricsSampler {
+    shutdown_sender: Option<oneshot::Sender<()>>,
+    series: Arc<RwLock<HashMap<String, MetricSeries>>>,
+}
+
+impl MetricsSampler {
+    pub fn start(session_id: i32) -> Self {
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        let series = Arc::new(RwLock::new(HashMap::new()));
+        let series_clone = Arc::clone(&series);
+
+        tokio::spawn(async move {
+            let sample_task = async {
+                let mut ticker = interval(Duration::from_secs(SAMPLE_INTERVAL_SECONDS));
+
+                info!("Started metrics sampler: {}", session_id);
+                loop {
+                    ticker.tick().await;
+
+                    //  只在获取地址时持有连接，避免慢请求阻塞会话的其他操作
+                    let endpoint = match get_connector_optional(&session_id) {
+                        Some(connector) => connector.metrics_endpoint(),
+                        None => {
+                            debug!(
+                                "Connector is not exist, metrics sampler exit: {}",
+                                session_id
+                            );
+                            break;
+                        }
+                    };
+
+                    match endpoint.scrape().await {
+                        Ok(families) => {
+                            let time = now_timestamp() as u64;
+                            record(&series_clone, families, time);
+                        }
+                        Err(e) => {
+                            debug!("Metrics sampler failed to scrape {}: {:?}", session_id, e);
+                        }
+                    }
+                }
+            };
+
+            tokio::select! {
+                _ = shutdown_receiver => {
+                    info!("Metrics sampler shutdown: {}", session_id);
+                },
+                _ = sample_task => {}
+            }
+        });
+
+        MetricsSampler {
+            shutdown_sender: Some(shutdown_sender),
+            series,
+        }
+    }
+
+    /// 查询指定指标的历史数据，`names` 为空时返回所有指标
+    pub fn history(&self, names: &Vec<String>) -> Vec<MetricSeries> {
+        let series = self.series.read().unwrap();
+        let mut result = series
+            .values()
+            .filter(|s| names.is_empty() || names.contains(&s.name))
+            .cloned()
+            .collect::<Vec<MetricSeries>>();
+        result.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.labels.cmp(&b.labels)));
+        result
+    }
+
+    pub fn shutdown(&mut self) {
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Drop for MetricsSampler {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn record(
+    series: &Arc<RwLock<HashMap<String, MetricSeries>>>,
+    families: Vec<MetricFamily>,
+    time: u64,
+) {
+    let mut series = series.write().unwrap();
+    for family in families {
+        if matches!(
+            family.metric_type,
+            MetricType::Histogram | MetricType::Summary
+        ) {
+            continue;
+        }
+        for metric in family.metrics {
+            let value = match metric.value {
+                Some(value) if value.is_finite() => value,
+                _ => continue,
+            };
+            let key = format!("{}{:?}", family.name, metric.labels);
+            let entry = series.entry(key).or_insert_with(|| MetricSeries {
+                name: family.name.clone(),
+                labels: metric.labels,
+                points: VecDeque::with_capacity(SERIES_CAPACITY),
+            });
+            if entry.points.len() >= SERIES_CAPACITY {
+                entry.points.pop_front();
+            }
+            entry.points.push_back(MetricPoint { time, value });
+        }
+    }
+}
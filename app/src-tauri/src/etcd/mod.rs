@@ -1,4 +1,5 @@
 #![allow(unused)]
+use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -6,21 +7,43 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use crate::api::connection;
 use crate::error::LogicError;
 use crate::etcd::etcd_connector::EtcdConnector;
-use crate::transport::connection::{Connection, ConnectionInfo, KeyMonitorConfig, SessionData};
+use crate::transport::connection::{
+    CompactionPolicy, Connection, ConnectionInfo, KeyMonitorConfig, SessionData,
+};
 use crate::transport::event::KeyMonitorModifiedByServerEvent;
+use crate::transport::metrics::MetricSeries;
 use dashmap::mapref::one::{Ref, RefMut};
+use auto_compactor::AutoCompactor;
 use dashmap::DashMap;
 use etcd_client::Error;
 use etcd_connector_handler::EtcdConnectorHandler;
 use key_watcher::KeyWatcher;
+use kv_reader::KvReader;
 use lazy_static::lazy_static;
 use log::{error, info};
+use metrics_sampler::MetricsSampler;
+use offline_session::OfflineSession;
+use snapshot_scheduler::SnapshotScheduler;
 use tauri::{AppHandle, Window};
 use tokio::sync::Mutex;
 
+pub mod auto_compactor;
+mod bbolt;
 pub mod etcd_connector;
 pub mod etcd_connector_handler;
+pub mod gateway_proto;
+pub mod gateway_proxy;
+pub mod health_probe;
+pub mod kv_reader;
+pub mod metrics_endpoint;
+pub mod metrics_sampler;
+pub mod offline_session;
+pub mod snapshot;
+pub mod snapshot_restore;
+pub mod snapshot_scheduler;
+pub mod srv_discovery;
 mod test;
+pub mod v2_client;
 mod wrapped_etcd_client;
 pub mod key_watcher;
 
@@ -31,6 +54,10 @@ lazy_static! {
     static ref CONNECTION_CONFIG: DashMap<i32, Connection> = DashMap::with_capacity(2);
     static ref CONNECTION_INFO_POOL: DashMap<i32, ConnectionInfo> = DashMap::new();
     static ref CONNECTION_KEY_WATCHERS: DashMap<i32, KeyWatcher> = DashMap::new();
+    static ref CONNECTION_AUTO_COMPACTORS: DashMap<i32, AutoCompactor> = DashMap::new();
+    static ref CONNECTION_METRICS_SAMPLERS: DashMap<i32, MetricsSampler> = DashMap::new();
+    static ref SNAPSHOT_SCHEDULERS: DashMap<String, SnapshotScheduler> = DashMap::new();
+    static ref OFFLINE_SESSION_POOL: DashMap<i32, OfflineSession> = DashMap::new();
 }
 
 fn gen_connection_id() -> i32 {
@@ -44,6 +71,14 @@ pub fn now_timestamp() -> u128 {
         .as_millis()
 }
 
+/// etcd 对已压缩的版本返回 OutOfRange 状态码
+pub fn is_compacted_error(e: &Error) -> bool {
+    match e {
+        Error::GRpcStatus(s) => s.code() as i32 == 11,
+        _ => false,
+    }
+}
+
 pub async fn new_connector(
     name: String,
     connection: Connection,
@@ -67,19 +102,22 @@ pub async fn new_connector(
     let read_all_keys = connector.get_readable_keys().await?.read_all_keys;
     let query_pagination = connection.query_pagination;
     let query_pagination_size = connection.query_pagination_size;
+    let endpoints = connector.endpoints().clone();
 
     CONNECTION_POOL.insert(connector_id, connector);
 
     CONNECTION_CONFIG.insert(connector_id, connection);
 
-    let info_result = connection::get_connection(name).await?;
+    let info_result = connection::get_connection(name.clone()).await?;
 
     let mut connection_saved = false;
     let mut key_collection = None;
     let mut key_monitor_list = None;
+    let mut compaction_policy = None;
     if let Some(info) = info_result {
         key_collection = Some((&info.key_collection).clone());
         key_monitor_list = Some((&info.key_monitor_list).clone());
+        compaction_policy = info.compaction_policy.clone();
         connection_saved = true;
 
         CONNECTION_INFO_POOL.insert(connector_id, info);
@@ -95,6 +133,10 @@ pub async fn new_connector(
     }
     CONNECTION_KEY_WATCHERS.insert(connector_id, key_watcher);
 
+    if let Some(policy) = &compaction_policy {
+        set_auto_compactor(connector_id, name, Some(policy.clone()));
+    }
+
     Ok(SessionData {
         id: connector_id,
         user,
@@ -106,6 +148,8 @@ pub async fn new_connector(
         read_all_keys,
         query_pagination,
         query_pagination_size,
+        compaction_policy,
+        endpoints,
     })
 }
 
@@ -117,6 +161,56 @@ pub fn get_connector_optional(id: &i32) -> Option<RefMut<'_, i32, EtcdConnector>
     CONNECTION_POOL.get_mut(id)
 }
 
+/// 在线会话或离线快照会话的只读视图
+pub enum KvReaderRef<'a> {
+    Online(RefMut<'a, i32, EtcdConnector>),
+    Offline(RefMut<'a, i32, OfflineSession>),
+}
+
+impl<'a> Deref for KvReaderRef<'a> {
+    type Target = dyn KvReader;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            KvReaderRef::Online(connector) => connector.value(),
+            KvReaderRef::Offline(session) => session.value(),
+        }
+    }
+}
+
+impl<'a> DerefMut for KvReaderRef<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            KvReaderRef::Online(connector) => connector.value_mut(),
+            KvReaderRef::Offline(session) => session.value_mut(),
+        }
+    }
+}
+
+pub fn get_kv_reader(id: &i32) -> Result<KvReaderRef<'_>, LogicError> {
+    if let Some(session) = OFFLINE_SESSION_POOL.get_mut(id) {
+        return Ok(KvReaderRef::Offline(session));
+    }
+    get_connector(id).map(KvReaderRef::Online)
+}
+
+/// 打开离线快照会话，返回会话ID
+pub fn new_offline_session(session: OfflineSession) -> i32 {
+    let id = gen_connection_id();
+    OFFLINE_SESSION_POOL.insert(id, session);
+    id
+}
+
+pub fn get_offline_session(id: &i32) -> Option<Ref<'_, i32, OfflineSession>> {
+    OFFLINE_SESSION_POOL.get(id)
+}
+
+pub fn remove_offline_session(id: &i32) {
+    if OFFLINE_SESSION_POOL.remove(id).is_some() {
+        info!("Removed offline session: {}", id);
+    }
+}
+
 pub fn get_connection_config(id: &i32) -> Option<Ref<'_, i32, Connection>> {
     CONNECTION_CONFIG.get(id)
 }
@@ -148,6 +242,75 @@ pub fn retry_key_watcher(id: i32, window: Window, config: KeyMonitorConfig) {
     });
 }
 
+/// 设置会话的自动压缩策略，会先停止已有的自动压缩任务
+pub fn set_auto_compactor(id: i32, connection_name: String, policy: Option<CompactionPolicy>) {
+    if let Some((_, mut compactor)) = CONNECTION_AUTO_COMPACTORS.remove(&id) {
+        compactor.shutdown();
+    }
+
+    if let Some(policy) = policy {
+        if policy.enabled {
+            CONNECTION_AUTO_COMPACTORS
+                .insert(id, AutoCompactor::start(id, connection_name, policy));
+        }
+    }
+}
+
+/// 首次请求指标时才开始采样，未查看指标的会话不会周期性地请求 `/metrics`
+pub fn start_metrics_sampler(id: &i32) -> Result<(), LogicError> {
+    if !CONNECTION_POOL.contains_key(id) {
+        return Err(LogicError::ConnectionLose);
+    }
+    CONNECTION_METRICS_SAMPLERS
+        .entry(*id)
+        .or_insert_with(|| MetricsSampler::start(*id));
+    Ok(())
+}
+
+/// 查询会话采样的指标历史数据，尚未开始采样时会先开始采样
+pub fn get_metrics_history(id: &i32, names: &Vec<String>) -> Result<Vec<MetricSeries>, LogicError> {
+    start_metrics_sampler(id)?;
+    CONNECTION_METRICS_SAMPLERS
+        .get(id)
+        .map(|sampler| sampler.history(names))
+        .ok_or(LogicError::ConnectionLose)
+}
+
+/// 根据已保存的连接配置设置定时快照备份，会先停止已有的备份任务
+pub fn set_snapshot_scheduler(app: AppHandle, info: &ConnectionInfo) {
+    remove_snapshot_scheduler(&info.name);
+
+    if let Some(schedule) = &info.snapshot_schedule {
+        if schedule.enabled {
+            let scheduler = SnapshotScheduler::start(
+                app,
+                info.name.clone(),
+                info.connection.clone(),
+                schedule.clone(),
+            );
+            SNAPSHOT_SCHEDULERS.insert(info.name.clone(), scheduler);
+        }
+    }
+}
+
+pub fn remove_snapshot_scheduler(connection_name: &String) {
+    if let Some((_, mut scheduler)) = SNAPSHOT_SCHEDULERS.remove(connection_name) {
+        scheduler.shutdown();
+    }
+}
+
+/// 更新已打开会话中缓存的连接配置，避免会话保存配置时覆盖新的修改
+pub fn update_cached_connection_info<F>(connection_name: &String, f: F)
+where
+    F: Fn(&mut ConnectionInfo),
+{
+    for mut entry in CONNECTION_INFO_POOL.iter_mut() {
+        if entry.name.eq(connection_name) {
+            f(entry.value_mut());
+        }
+    }
+}
+
 pub async fn remove_connector(id: &i32) {
     if let Some((_, connector)) = CONNECTION_POOL.remove(id) {
         drop(connector);
@@ -163,4 +326,12 @@ pub async fn remove_connector(id: &i32) {
     if let Some((_, mut key_watcher)) = CONNECTION_KEY_WATCHERS.remove(id) {
         key_watcher.remove_config_all().await;
     }
+
+    if let Some((_, mut compactor)) = CONNECTION_AUTO_COMPACTORS.remove(id) {
+        compactor.shutdown();
+    }
+
+    if let Some((_, mut sampler)) = CONNECTION_METRICS_SAMPLERS.remove(id) {
+        sampler.shutdown();
+    }
 }
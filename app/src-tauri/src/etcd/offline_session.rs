@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use prost::Message;
+
+use crate::error::LogicError;
+use crate::transport::kv::SerializableKeyValue;
+use crate::transport::maintenance::OfflineSessionData;
+use crate::utils::k8s_formatter;
+
+use super::bbolt::{BoltDb, Bucket};
+use super::kv_reader::KvReader;
+
+const KEY_BUCKET: &[u8] = b"key";
+const META_BUCKET: &[u8] = b"meta";
+const FINISHED_COMPACT_REV_KEY: &[u8] = b"finishedCompactRev";
+
+/// etcd 存储的版本 key：8字节主版本号 + '_' + 8字节子版本号，删除标记会再追加一个 't'
+const REVISION_BYTES_LEN: usize = 17;
+const TOMBSTONE_MARK: u8 = b't';
+
+/// etcd mvcc 存储的键值对，与 mvccpb.KeyValue 保持一致
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct MvccKeyValue {
+    #[prost(bytes = "vec", tag = "1")]
+    key: Vec<u8>,
+    #[prost(int64, tag = "2")]
+    create_revision: i64,
+    #[prost(int64, tag = "3")]
+    mod_revision: i64,
+    #[prost(int64, tag = "4")]
+    version: i64,
+    #[prost(bytes = "vec", tag = "5")]
+    value: Vec<u8>,
+    #[prost(int64, tag = "6")]
+    lease: i64,
+}
+
+/// key 的一次修改记录，不包含 value
+struct KeyRevision {
+    revision_bytes: Vec<u8>,
+    main: i64,
+    create_revision: i64,
+    version: i64,
+    lease: i64,
+    tombstone: bool,
+}
+
+/// 离线会话，直接解析 `maintenance_snapshot` 保存的快照文件，只提供只读视图
+pub struct OfflineSession {
+    filepath: PathBuf,
+    db: BoltDb,
+    key_bucket: Bucket,
+    /// 每个 key 的修改记录，按版本升序排列
+    index: BTreeMap<Vec<u8>, Vec<KeyRevision>>,
+    revision: i64,
+    compact_revision: i64,
+}
+
+impl OfflineSession {
+    pub fn open(filepath: PathBuf) -> Result<Self, LogicError> {
+        let mut db = BoltDb::open(&filepath)?;
+        let key_bucket = db
+            .bucket(KEY_BUCKET)?
+            .ok_or(LogicError::MsgError(String::from(
+                "Invalid snapshot file: key bucket not found",
+            )))?;
+
+        let mut index: BTreeMap<Vec<u8>, Vec<KeyRevision>> = BTreeMap::new();
+        let mut revision = 0;
+        db.for_each(&key_bucket, |revision_bytes, value| {
+            let main = parse_main_revision(revision_bytes)?;
+            let kv = decode_kv(value)?;
+            let tombstone = revision_bytes.len() > REVISION_BYTES_LEN
+                && revision_bytes[REVISION_BYTES_LEN] == TOMBSTONE_MARK;
+
+            index.entry(kv.key).or_default().push(KeyRevision {
+                revision_bytes: revision_bytes.to_vec(),
+                main,
+                create_revision: kv.create_revision,
+                version: kv.version,
+                lease: kv.lease,
+                tombstone,
+            });
+            revision = revision.max(main);
+            Ok(true)
+        })?;
+
+        let mut compact_revision = 0;
+        if let Some(meta_bucket) = db.bucket(META_BUCKET)? {
+            if let Some(value) = db.get(&meta_bucket, FINISHED_COMPACT_REV_KEY)? {
+                compact_revision = parse_main_revision(&value)?;
+            }
+        }
+
+        Ok(OfflineSession {
+            filepath,
+            db,
+            key_bucket,
+            index,
+            revision,
+            compact_revision,
+        })
+    }
+
+    pub fn session_data(&self, id: i32) -> OfflineSessionData {
+        let key_count = self
+            .index
+            .values()
+            .filter(|revisions| revisions.last().is_some_and(|r| !r.tombstone))
+            .count();
+        OfflineSessionData {
+            id,
+            filepath: self.filepath.to_string_lossy().to_string(),
+            revision: self.revision,
+            compact_revision: self.compact_revision,
+            key_count,
+        }
+    }
+
+    /// 查找 key 在指定版本时的修改记录，`revision` 为 None 时查找最新的记录
+    fn find_revision(&self, key: &Vec<u8>, revision: Option<i64>) -> Option<&KeyRevision> {
+        let revisions = self.index.get(key)?;
+        let found = match revision {
+            Some(revision) => revisions.iter().rev().find(|r| r.main <= revision),
+            None => revisions.last(),
+        };
+        found.filter(|r| !r.tombstone)
+    }
+
+    fn read_kv(&mut self, revision_bytes: &Vec<u8>) -> Result<SerializableKeyValue, LogicError> {
+        let value =
+            self.db
+                .get(&self.key_bucket, revision_bytes)?
+                .ok_or(LogicError::ResourceNotExist(
+                    "The key does not exist or has expired.",
+                ))?;
+        let kv = decode_kv(&value)?;
+        Ok(to_serializable(kv))
+    }
+}
+
+#[async_trait]
+impl KvReader for OfflineSession {
+    async fn kv_get_all_keys(&mut self) -> Result<Vec<SerializableKeyValue>, LogicError> {
+        let mut result = Vec::new();
+        for (key, revisions) in &self.index {
+            if let Some(latest) = revisions.last() {
+                if latest.tombstone {
+                    continue;
+                }
+                let kv = MvccKeyValue {
+                    key: key.clone(),
+                    create_revision: latest.create_revision,
+                    mod_revision: latest.main,
+                    version: latest.version,
+                    value: vec![],
+                    lease: latest.lease,
+                };
+                result.push(to_serializable(kv));
+            }
+        }
+        Ok(result)
+    }
+
+    async fn kv_get(&mut self, key: Vec<u8>) -> Result<SerializableKeyValue, LogicError> {
+        let revision_bytes = self
+            .find_revision(&key, None)
+            .map(|r| r.revision_bytes.clone())
+            .ok_or(LogicError::ResourceNotExist(
+                "The key does not exist or has expired.",
+            ))?;
+        self.read_kv(&revision_bytes)
+    }
+
+    async fn kv_get_by_version(
+        &mut self,
+        key: Vec<u8>,
+        version: i64,
+    ) -> Result<SerializableKeyValue, LogicError> {
+        if version < self.compact_revision {
+            return Err(LogicError::MsgError(format!(
+                "The revision {} has been compacted, the minimum revision is {}",
+                version, self.compact_revision
+            )));
+        }
+        let revision_bytes = self
+            .find_revision(&key, Some(version))
+            .map(|r| r.revision_bytes.clone())
+            .ok_or(LogicError::ResourceNotExist(
+                "The key does not exist or has expired.",
+            ))?;
+        self.read_kv(&revision_bytes)
+    }
+
+    async fn kv_get_history_versions(
+        &mut self,
+        key: Vec<u8>,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<i64>, LogicError> {
+        let mut history = Vec::new();
+        if let Some(revisions) = self.index.get(&key) {
+            //  与在线会话一致，遇到删除或压缩的版本时终止搜索
+            for r in revisions.iter().rev().filter(|r| r.main <= end) {
+                if r.tombstone || r.main < start || r.main < self.compact_revision {
+                    break;
+                }
+                history.push(r.main);
+            }
+        }
+        Ok(history)
+    }
+}
+
+fn parse_main_revision(revision_bytes: &[u8]) -> Result<i64, LogicError> {
+    if revision_bytes.len() < REVISION_BYTES_LEN {
+        return Err(LogicError::MsgError(String::from(
+            "Invalid snapshot file: broken revision key",
+        )));
+    }
+    Ok(i64::from_be_bytes(revision_bytes[0..8].try_into().unwrap()))
+}
+
+fn decode_kv(value: &[u8]) -> Result<MvccKeyValue, LogicError> {
+    MvccKeyValue::decode(value).map_err(|e| {
+        LogicError::MsgError(format!("Invalid snapshot file: broken key value, {}", e))
+    })
+}
+
+fn to_serializable(kv: MvccKeyValue) -> SerializableKeyValue {
+    let key = String::from_utf8_lossy(&kv.key).to_string();
+    let formatted_value = k8s_formatter::try_format_proto(&key, &kv.value);
+    SerializableKeyValue {
+        key,
+        key_encoded_utf8: std::str::from_utf8(&kv.key).is_ok(),
+        key_bytes: kv.key,
+        create_revision: kv.create_revision,
+        mod_revision: kv.mod_revision,
+        version: kv.version,
+        value: kv.value,
+        lease: kv.lease.to_string(),
+        lease_info: None,
+        formatted_value,
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::LogicError;
+use crate::transport::maintenance::{SnapshotMetadata, SnapshotVerifyResult};
+
+/// etcd 在快照末尾追加的 sha256 长度
+pub const SNAPSHOT_HASH_LEN: usize = 32;
+
+/// 快照元数据文件后缀，与快照文件放在同一目录
+const SNAPSHOT_METADATA_SUFFIX: &str = ".meta.json";
+
+/// 流式计算快照的 sha256，始终保留最后 [`SNAPSHOT_HASH_LEN`] 个字节作为待校验的 hash
+pub struct SnapshotHasher {
+    hasher: Sha256,
+    tail: Vec<u8>,
+    size: u64,
+}
+
+impl Default for SnapshotHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnapshotHasher {
+    pub fn new() -> Self {
+        SnapshotHasher {
+            hasher: Sha256::new(),
+            tail: Vec::with_capacity(SNAPSHOT_HASH_LEN * 2),
+            size: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.size += data.len() as u64;
+        self.tail.extend_from_slice(data);
+        if self.tail.len() > SNAPSHOT_HASH_LEN {
+            let split = self.tail.len() - SNAPSHOT_HASH_LEN;
+            self.hasher.update(&self.tail[..split]);
+            self.tail.drain(..split);
+        }
+    }
+
+    /// 已接收的总字节数，包含末尾的 hash
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 校验快照内容与末尾 hash 是否一致，返回快照内容的 sha256 十六进制字符串
+    pub fn verify(self) -> Result<String, LogicError> {
+        if self.tail.len() < SNAPSHOT_HASH_LEN {
+            return Err(LogicError::MsgError(String::from(
+                "Snapshot file is too small to contain a sha256 hash",
+            )));
+        }
+
+        let digest = self.hasher.finalize();
+        if digest.as_slice() != self.tail.as_slice() {
+            return Err(LogicError::MsgError(String::from(
+                "Snapshot sha256 hash mismatch, the file may be corrupted",
+            )));
+        }
+        Ok(format!("{:x}", digest))
+    }
+}
+
+pub fn get_metadata_path(snapshot_path: &Path) -> PathBuf {
+    let mut name = snapshot_path.as_os_str().to_os_string();
+    name.push(SNAPSHOT_METADATA_SUFFIX);
+    PathBuf::from(name)
+}
+
+pub fn write_metadata(snapshot_path: &Path, metadata: &SnapshotMetadata) -> Result<(), LogicError> {
+    let content = serde_json::to_string_pretty(metadata)?;
+    fs::write(get_metadata_path(snapshot_path), content)?;
+    Ok(())
+}
+
+pub fn read_metadata(snapshot_path: &Path) -> Result<Option<SnapshotMetadata>, LogicError> {
+    let path = get_metadata_path(snapshot_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read(path)?;
+    Ok(Some(serde_json::from_slice(&content)?))
+}
+
+/// 重新校验本地快照文件，同时比对元数据中记录的大小与 hash
+pub fn verify_snapshot_file(snapshot_path: &Path) -> Result<SnapshotVerifyResult, LogicError> {
+    let mut file = fs::File::open(snapshot_path)?;
+    let mut hasher = SnapshotHasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    let size = hasher.size();
+    let (sha256, failed_msg) = match hasher.verify() {
+        Ok(sha256) => (Some(sha256), None),
+        Err(LogicError::MsgError(msg)) => (None, Some(msg)),
+        Err(e) => return Err(e),
+    };
+
+    let metadata = read_metadata(snapshot_path)?;
+    let metadata_matched = metadata
+        .as_ref()
+        .map(|m| m.size == size && sha256.as_ref().is_some_and(|hash| hash == &m.sha256));
+
+    Ok(SnapshotVerifyResult {
+        size,
+        hash_valid: sha256.is_some(),
+        failed_msg,
+        metadata,
+        metadata_matched,
+    })
+}
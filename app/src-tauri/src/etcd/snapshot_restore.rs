@@ -0,0 +1,565 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use prost::Message;
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+
+use crate::error::LogicError;
+use crate::transport::event::{SnapshotRestoreEvent, SnapshotRestoreStep};
+use crate::transport::maintenance::{SnapshotRestoreOptions, SnapshotRestoreResult};
+
+use super::bbolt::{BoltDb, BoltWriter};
+use super::snapshot;
+
+const MEMBERS_BUCKET: &[u8] = b"members";
+const MEMBERS_REMOVED_BUCKET: &[u8] = b"members_removed";
+const META_BUCKET: &[u8] = b"meta";
+const CONSISTENT_INDEX_KEY: &[u8] = b"consistent_index";
+const TERM_KEY: &[u8] = b"term";
+const CONF_STATE_KEY: &[u8] = b"confState";
+
+/// 每写入多少个 key 通知一次进度
+const PROGRESS_KEYS: u64 = 10000;
+
+/// WAL 记录类型
+const WAL_METADATA_TYPE: i64 = 1;
+const WAL_ENTRY_TYPE: i64 = 2;
+const WAL_STATE_TYPE: i64 = 3;
+const WAL_CRC_TYPE: i64 = 4;
+const WAL_SNAPSHOT_TYPE: i64 = 5;
+
+const ENTRY_CONF_CHANGE: i32 = 1;
+const CONF_CHANGE_ADD_NODE: i32 = 0;
+
+/// raft 初始任期，与 etcdutl 保持一致
+const RESTORE_TERM: u64 = 1;
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct RaftEntry {
+    #[prost(uint64, tag = "2")]
+    term: u64,
+    #[prost(uint64, tag = "3")]
+    index: u64,
+    #[prost(int32, tag = "1")]
+    r#type: i32,
+    #[prost(bytes = "vec", tag = "4")]
+    data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct RaftConfChange {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(int32, tag = "2")]
+    r#type: i32,
+    #[prost(uint64, tag = "3")]
+    node_id: u64,
+    #[prost(bytes = "vec", tag = "4")]
+    context: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct RaftHardState {
+    #[prost(uint64, tag = "1")]
+    term: u64,
+    #[prost(uint64, tag = "2")]
+    vote: u64,
+    #[prost(uint64, tag = "3")]
+    commit: u64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct RaftConfState {
+    #[prost(uint64, repeated, packed = "false", tag = "1")]
+    voters: Vec<u64>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct RaftSnapshotMetadata {
+    #[prost(message, optional, tag = "1")]
+    conf_state: Option<RaftConfState>,
+    #[prost(uint64, tag = "2")]
+    index: u64,
+    #[prost(uint64, tag = "3")]
+    term: u64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct RaftSnapshot {
+    #[prost(bytes = "vec", tag = "1")]
+    data: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    metadata: Option<RaftSnapshotMetadata>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct WalRecord {
+    #[prost(int64, tag = "1")]
+    r#type: i64,
+    #[prost(uint32, tag = "2")]
+    crc: u32,
+    #[prost(bytes = "vec", tag = "3")]
+    data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct WalSnapshot {
+    #[prost(uint64, tag = "1")]
+    index: u64,
+    #[prost(uint64, tag = "2")]
+    term: u64,
+    #[prost(message, optional, tag = "3")]
+    conf_state: Option<RaftConfState>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct WalMetadata {
+    #[prost(uint64, tag = "1")]
+    node_id: u64,
+    #[prost(uint64, tag = "2")]
+    cluster_id: u64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct SnapFile {
+    #[prost(uint32, tag = "1")]
+    crc: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    data: Vec<u8>,
+}
+
+struct RestoreMember {
+    id: u64,
+    name: String,
+    peer_urls: Vec<String>,
+}
+
+impl RestoreMember {
+    /// 与 etcd 相同的成员ID算法：sha1(排序后的 peer urls + 集群 token) 的前8字节
+    fn new(name: String, mut peer_urls: Vec<String>, cluster_token: &String) -> Self {
+        peer_urls.sort();
+        let mut hasher = Sha1::new();
+        hasher.update(peer_urls.join("").as_bytes());
+        hasher.update(cluster_token.as_bytes());
+        let hash = hasher.finalize();
+        let id = u64::from_be_bytes(hash[0..8].try_into().unwrap());
+        RestoreMember {
+            id,
+            name,
+            peer_urls,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "peerURLs": self.peer_urls,
+            "name": self.name,
+        })
+    }
+}
+
+/// 将快照恢复为一个新的 etcd 数据目录，与 `etcdutl snapshot restore` 的结果一致
+///
+/// 会重写快照中的成员信息与 consistent index，并生成新的 WAL 与 raft 快照
+pub fn restore<F>(
+    options: &SnapshotRestoreOptions,
+    mut progress: F,
+) -> Result<SnapshotRestoreResult, LogicError>
+where
+    F: FnMut(SnapshotRestoreEvent),
+{
+    let snapshot_path = PathBuf::from(&options.snapshot_path);
+    let members = parse_initial_cluster(options)?;
+    let local = members
+        .iter()
+        .find(|m| m.name == options.member_name)
+        .ok_or(LogicError::MsgError(format!(
+            "Member '{}' is not in the initial cluster",
+            options.member_name
+        )))?;
+    let cluster_id = cluster_id(&members);
+
+    let member_dir = PathBuf::from(&options.data_dir).join("member");
+    if member_dir.exists() {
+        return Err(LogicError::MsgError(format!(
+            "Data dir '{}' already exists",
+            member_dir.display()
+        )));
+    }
+
+    if !options.skip_hash_check {
+        progress(SnapshotRestoreEvent::new(SnapshotRestoreStep::VerifyHash));
+        let result = snapshot::verify_snapshot_file(&snapshot_path)?;
+        if !result.hash_valid {
+            return Err(LogicError::MsgError(result.failed_msg.unwrap_or_default()));
+        }
+    }
+
+    let snap_dir = member_dir.join("snap");
+    let wal_dir = member_dir.join("wal");
+    fs::create_dir_all(&snap_dir)?;
+    fs::create_dir_all(&wal_dir)?;
+
+    let commit = members.len() as u64;
+    restore_db(
+        &snapshot_path,
+        &snap_dir.join("db"),
+        &members,
+        commit,
+        &mut progress,
+    )?;
+
+    progress(SnapshotRestoreEvent::new(SnapshotRestoreStep::WriteWal));
+    let voters = members.iter().map(|m| m.id).collect::<Vec<u64>>();
+    let conf_state = RaftConfState { voters };
+    write_wal(
+        &wal_dir,
+        &members,
+        local.id,
+        cluster_id,
+        commit,
+        &conf_state,
+    )?;
+
+    progress(SnapshotRestoreEvent::new(SnapshotRestoreStep::WriteSnap));
+    write_snap(&snap_dir, &members, commit, conf_state)?;
+
+    Ok(SnapshotRestoreResult {
+        data_dir: options.data_dir.clone(),
+        cluster_id: format!("{:x}", cluster_id),
+        member_id: format!("{:x}", local.id),
+    })
+}
+
+/// 解析 `name=url,name=url` 格式的初始集群配置，未配置时使用当前成员
+fn parse_initial_cluster(
+    options: &SnapshotRestoreOptions,
+) -> Result<Vec<RestoreMember>, LogicError> {
+    let mut urls: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    match &options.initial_cluster {
+        Some(initial_cluster) if !initial_cluster.trim().is_empty() => {
+            for item in initial_cluster.split(',') {
+                let (name, url) =
+                    item.trim()
+                        .split_once('=')
+                        .ok_or(LogicError::MsgError(format!(
+                            "Invalid initial cluster item: {}",
+                            item
+                        )))?;
+                urls.entry(name.to_string())
+                    .or_default()
+                    .push(url.to_string());
+            }
+        }
+        _ => {
+            if options.peer_urls.is_empty() {
+                return Err(LogicError::ArgumentError);
+            }
+            urls.insert(options.member_name.clone(), options.peer_urls.clone());
+        }
+    }
+
+    //  与 etcd 一致按成员ID排序，投票给第一个成员，配置变更也按此顺序写入
+    let mut members = urls
+        .into_iter()
+        .map(|(name, peer_urls)| RestoreMember::new(name, peer_urls, &options.cluster_name))
+        .collect::<Vec<RestoreMember>>();
+    members.sort_by_key(|m| m.id);
+    Ok(members)
+}
+
+fn cluster_id(members: &Vec<RestoreMember>) -> u64 {
+    let mut ids = members.iter().map(|m| m.id).collect::<Vec<u64>>();
+    ids.sort();
+    let mut hasher = Sha1::new();
+    for id in ids {
+        hasher.update(id.to_be_bytes());
+    }
+    let hash = hasher.finalize();
+    u64::from_be_bytes(hash[0..8].try_into().unwrap())
+}
+
+/// 复制快照中的所有 bucket，替换成员信息并更新 consistent index
+fn restore_db<F>(
+    snapshot_path: &Path,
+    db_path: &Path,
+    members: &Vec<RestoreMember>,
+    commit: u64,
+    progress: &mut F,
+) -> Result<(), LogicError>
+where
+    F: FnMut(SnapshotRestoreEvent),
+{
+    let mut source = BoltDb::open(snapshot_path)?;
+    let mut writer = BoltWriter::create(db_path, source.page_size())?;
+
+    for name in source.bucket_names()? {
+        let bucket_name = String::from_utf8_lossy(&name).to_string();
+        progress(SnapshotRestoreEvent {
+            step: SnapshotRestoreStep::RestoreDb,
+            bucket: Some(bucket_name.clone()),
+            keys: 0,
+        });
+
+        let bucket = match source.bucket(&name)? {
+            Some(bucket) => bucket,
+            None => continue,
+        };
+
+        //  恢复后是一个全新的集群，旧集群移除的成员记录没有意义
+        writer.begin_bucket(&name)?;
+        if name == MEMBERS_REMOVED_BUCKET {
+            continue;
+        } else if name == MEMBERS_BUCKET {
+            let mut items = BTreeMap::new();
+            for m in members {
+                items.insert(format!("{:x}", m.id), serde_json::to_vec(&m.to_json())?);
+            }
+            for (k, v) in items {
+                writer.put(k.as_bytes(), &v)?;
+            }
+        } else if name == META_BUCKET {
+            let mut items: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+            source.for_each(&bucket, |k, v| {
+                items.insert(k.to_vec(), v.to_vec());
+                Ok(true)
+            })?;
+            items.insert(CONSISTENT_INDEX_KEY.to_vec(), commit.to_be_bytes().to_vec());
+            items.insert(TERM_KEY.to_vec(), RESTORE_TERM.to_be_bytes().to_vec());
+            if items.contains_key(CONF_STATE_KEY) {
+                let voters = members.iter().map(|m| m.id).collect::<Vec<u64>>();
+                items.insert(
+                    CONF_STATE_KEY.to_vec(),
+                    serde_json::to_vec(&json!({ "voters": voters }))?,
+                );
+            }
+            for (k, v) in items {
+                writer.put(&k, &v)?;
+            }
+        } else {
+            let mut keys = 0u64;
+            source.for_each(&bucket, |k, v| {
+                writer.put(k, v)?;
+                keys += 1;
+                if keys % PROGRESS_KEYS == 0 {
+                    progress(SnapshotRestoreEvent {
+                        step: SnapshotRestoreStep::RestoreDb,
+                        bucket: Some(bucket_name.clone()),
+                        keys,
+                    });
+                }
+                Ok(true)
+            })?;
+        }
+    }
+
+    if !source.bucket_names()?.iter().any(|n| n == MEMBERS_BUCKET) {
+        writer.begin_bucket(MEMBERS_BUCKET)?;
+        for m in members {
+            writer.put(
+                format!("{:x}", m.id).as_bytes(),
+                &serde_json::to_vec(&m.to_json())?,
+            )?;
+        }
+    }
+
+    writer.finish(source.txid())
+}
+
+fn write_wal(
+    wal_dir: &Path,
+    members: &Vec<RestoreMember>,
+    node_id: u64,
+    cluster_id: u64,
+    commit: u64,
+    conf_state: &RaftConfState,
+) -> Result<(), LogicError> {
+    let mut encoder = WalEncoder::new();
+    encoder.encode(WAL_CRC_TYPE, vec![]);
+    encoder.encode(
+        WAL_METADATA_TYPE,
+        WalMetadata {
+            node_id,
+            cluster_id,
+        }
+        .encode_to_vec(),
+    );
+    encoder.encode(WAL_SNAPSHOT_TYPE, WalSnapshot::default().encode_to_vec());
+
+    for (i, m) in members.iter().enumerate() {
+        let change = RaftConfChange {
+            id: 0,
+            r#type: CONF_CHANGE_ADD_NODE,
+            node_id: m.id,
+            context: serde_json::to_vec(&m.to_json())?,
+        };
+        let entry = RaftEntry {
+            term: RESTORE_TERM,
+            index: i as u64 + 1,
+            r#type: ENTRY_CONF_CHANGE,
+            data: change.encode_to_vec(),
+        };
+        encoder.encode(WAL_ENTRY_TYPE, entry.encode_to_vec());
+    }
+
+    let hard_state = RaftHardState {
+        term: RESTORE_TERM,
+        vote: members[0].id,
+        commit,
+    };
+    encoder.encode(WAL_STATE_TYPE, hard_state.encode_to_vec());
+    encoder.encode(
+        WAL_SNAPSHOT_TYPE,
+        WalSnapshot {
+            index: commit,
+            term: RESTORE_TERM,
+            conf_state: Some(conf_state.clone()),
+        }
+        .encode_to_vec(),
+    );
+
+    let path = wal_dir.join(format!("{:016x}-{:016x}.wal", 0, 0));
+    let mut file = fs::File::create(path)?;
+    file.write_all(&encoder.buf)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+fn write_snap(
+    snap_dir: &Path,
+    members: &Vec<RestoreMember>,
+    commit: u64,
+    conf_state: RaftConfState,
+) -> Result<(), LogicError> {
+    let raft_snapshot = RaftSnapshot {
+        data: v2_store_json(members, commit)?,
+        metadata: Some(RaftSnapshotMetadata {
+            conf_state: Some(conf_state),
+            index: commit,
+            term: RESTORE_TERM,
+        }),
+    };
+    let data = raft_snapshot.encode_to_vec();
+    let snap = SnapFile {
+        crc: crc32c_update(0, &data),
+        data,
+    };
+
+    let path = snap_dir.join(format!("{:016x}-{:016x}.snap", RESTORE_TERM, commit));
+    let mut file = fs::File::create(path)?;
+    file.write_all(&snap.encode_to_vec())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// etcd v2 store 的序列化数据，保存集群成员的 raft 属性
+fn v2_store_json(members: &Vec<RestoreMember>, index: u64) -> Result<Vec<u8>, LogicError> {
+    fn node(path: String, value: &str, children: Option<Value>) -> Value {
+        json!({
+            "Path": path,
+            "CreatedIndex": 0,
+            "ModifiedIndex": 0,
+            "ExpireTime": "0001-01-01T00:00:00Z",
+            "Value": value,
+            "Children": children,
+        })
+    }
+
+    let mut member_nodes = serde_json::Map::new();
+    for m in members {
+        let id = format!("{:x}", m.id);
+        let member_path = format!("/0/members/{}", id);
+        let raft_attributes = serde_json::to_string(&json!({ "peerURLs": m.peer_urls }))?;
+        let attributes = node(
+            format!("{}/raftAttributes", member_path),
+            &raft_attributes,
+            None,
+        );
+        member_nodes.insert(
+            id,
+            node(
+                member_path,
+                "",
+                Some(json!({ "raftAttributes": attributes })),
+            ),
+        );
+    }
+
+    let members_node = node(
+        String::from("/0/members"),
+        "",
+        Some(Value::Object(member_nodes)),
+    );
+    let cluster_node = node(
+        String::from("/0"),
+        "",
+        Some(json!({ "members": members_node })),
+    );
+    let keys_node = node(String::from("/1"), "", Some(json!({})));
+    let root = node(
+        String::from("/"),
+        "",
+        Some(json!({ "0": cluster_node, "1": keys_node })),
+    );
+
+    let store = json!({
+        "Root": root,
+        "CurrentVersion": 2,
+        "CurrentIndex": index,
+    });
+    Ok(serde_json::to_vec(&store)?)
+}
+
+/// WAL 编码：每条记录携带从文件开始累计的 crc32c，记录按8字节对齐
+struct WalEncoder {
+    buf: Vec<u8>,
+    crc: u32,
+}
+
+impl WalEncoder {
+    fn new() -> Self {
+        WalEncoder {
+            buf: vec![],
+            crc: 0,
+        }
+    }
+
+    fn encode(&mut self, record_type: i64, data: Vec<u8>) {
+        self.crc = crc32c_update(self.crc, &data);
+        let record = WalRecord {
+            r#type: record_type,
+            crc: self.crc,
+            data,
+        };
+        let bytes = record.encode_to_vec();
+
+        let pad = (8 - bytes.len() % 8) % 8;
+        let mut len_field = bytes.len() as u64;
+        if pad != 0 {
+            len_field |= (0x80 | pad as u64) << 56;
+        }
+        self.buf.extend_from_slice(&len_field.to_le_bytes());
+        self.buf.extend_from_slice(&bytes);
+        self.buf.extend(std::iter::repeat(0u8).take(pad));
+    }
+}
+
+/// Castagnoli 多项式的 crc32，与 go 的 `crc32.Update` 语义一致
+pub fn crc32c_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F63B78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
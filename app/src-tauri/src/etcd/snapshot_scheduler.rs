@@ -0,0 +1,257 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tauri::api::notification::Notification;
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+
+use crate::api::maintenance::{remove_snapshot_task, report_snapshot_failure, start_snapshot_task};
+use crate::error::LogicError;
+use crate::transport::connection::{Connection, SnapshotSchedule};
+use crate::transport::maintenance::{SnapshotBackupRecord, SnapshotScheduleState};
+use crate::utils::{file_util, md5};
+
+use super::etcd_connector::EtcdConnector;
+use super::etcd_connector_handler::EtcdConnectorHandler;
+use super::{now_timestamp, snapshot};
+
+/// 每个连接最多保留的备份历史条数
+const HISTORY_LIMIT: usize = 100;
+
+/// 连接级别的定时快照备份任务，按照 [`SnapshotSchedule`] 周期性地保存快照并轮转旧文件
+pub struct SnapshotScheduler {
+    shutdown_sender: Option<oneshot::Sender<()>>,
+}
+
+impl SnapshotScheduler {
+    pub fn start(
+        app: AppHandle,
+        connection_name: String,
+        connection: Connection,
+        schedule: SnapshotSchedule,
+    ) -> Self {
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            let schedule_task = async {
+                let interval = interval_millis(&schedule);
+
+                info!("Started snapshot scheduler: {}", connection_name);
+                loop {
+                    let mut state = load_state(&connection_name).unwrap_or_else(|e| {
+                        warn!("Failed to load snapshot schedule state: {:?}", e);
+                        SnapshotScheduleState::default()
+                    });
+
+                    //  错过的备份在启动后立即执行，间隔被调小时以新的间隔为准
+                    let now = now_timestamp() as u64;
+                    if state.next_run == 0 {
+                        //  从未执行过，等待一个完整的间隔
+                        state.next_run = now + interval;
+                        if let Err(e) = save_state(&connection_name, &state) {
+                            warn!("Failed to save snapshot schedule state: {:?}", e);
+                        }
+                    }
+                    let next_run = state.next_run.min(now + interval);
+                    if next_run > now {
+                        tokio::time::sleep(Duration::from_millis(next_run - now)).await;
+                    }
+
+                    let record = run_backup(&app, &connection_name, &connection, &schedule).await;
+                    if let Some(msg) = &record.failed_msg {
+                        let _ = Notification::new("com.beifengtz.etcdworkbench")
+                            .title("Scheduled snapshot failed")
+                            .body(format!("{}: {}", connection_name, msg))
+                            .show();
+                    }
+
+                    state.history.push(record);
+                    if state.history.len() > HISTORY_LIMIT {
+                        let overflow = state.history.len() - HISTORY_LIMIT;
+                        state.history.drain(0..overflow);
+                    }
+                    state.next_run = now_timestamp() as u64 + interval;
+                    if let Err(e) = save_state(&connection_name, &state) {
+                        warn!("Failed to save snapshot schedule state: {:?}", e);
+                    }
+                }
+            };
+
+            tokio::select! {
+                _ = shutdown_receiver => {
+                    info!("Snapshot scheduler shutdown: {}", connection_name);
+                },
+                _ = schedule_task => {}
+            }
+        });
+
+        SnapshotScheduler {
+            shutdown_sender: Some(shutdown_sender),
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        if let Some(sender) = self.shutdown_sender.take() {
+            let _ = sender.send(());
+        }
+    }
+}
+
+impl Drop for SnapshotScheduler {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+async fn run_backup(
+    app: &AppHandle,
+    connection_name: &String,
+    connection: &Connection,
+    schedule: &SnapshotSchedule,
+) -> SnapshotBackupRecord {
+    let time = now_timestamp() as u64;
+    let prefix = file_prefix(connection_name);
+    let mut file_path = PathBuf::from(&schedule.folder);
+    file_path.push(format!("{}-{}.db", prefix, time));
+
+    let result = backup(app, connection, file_path.clone()).await;
+    if result.is_ok() {
+        if let Err(e) = rotate(&schedule.folder, &prefix, schedule.keep) {
+            warn!("Failed to rotate snapshot files: {:?}", e);
+        }
+    }
+
+    SnapshotBackupRecord {
+        filepath: file_path.to_string_lossy().to_string(),
+        time,
+        duration: now_timestamp() as u64 - time,
+        success: result.is_ok(),
+        failed_msg: result.err(),
+    }
+}
+
+async fn backup(
+    app: &AppHandle,
+    connection: &Connection,
+    file_path: PathBuf,
+) -> Result<(), String> {
+    let mut connector =
+        match EtcdConnector::new(connection.clone(), EtcdConnectorHandler::default()).await {
+            Ok(connector) => connector,
+            Err(e) => {
+                let msg = format!("{:?}", e);
+                report_snapshot_failure(app, &file_path, msg.clone());
+                return Err(msg);
+            }
+        };
+
+    //  任务未能启动时 `start_snapshot_task` 已通过 `snapshot_state` 事件通知失败原因
+    let (info, done_receiver) = start_snapshot_task(app.clone(), &mut connector, file_path)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+
+    //  快照数据通过连接传输，需要等任务结束后再释放连接
+    let result = done_receiver
+        .await
+        .unwrap_or_else(|_| Some(String::from("Snapshot task was interrupted")));
+    drop(connector);
+    //  定时任务的结果记录在备份历史中，不保留在快照任务列表里
+    remove_snapshot_task(info.id);
+
+    match result {
+        Some(msg) => Err(msg),
+        None => Ok(()),
+    }
+}
+
+/// 只保留最新的 `keep` 个备份文件，同时删除对应的元数据文件
+fn rotate(folder: &String, prefix: &String, keep: usize) -> Result<(), LogicError> {
+    let mut backups = vec![];
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+        let timestamp = name
+            .strip_prefix(prefix.as_str())
+            .and_then(|s| s.strip_prefix('-'))
+            .and_then(|s| s.strip_suffix(".db"))
+            .and_then(|s| s.parse::<u64>().ok());
+        if let Some(timestamp) = timestamp {
+            backups.push((timestamp, path));
+        }
+    }
+
+    backups.sort_by(|a, b| b.0.cmp(&a.0));
+    for (_, path) in backups.into_iter().skip(keep.max(1)) {
+        debug!("Remove expired snapshot: {}", path.display());
+        fs::remove_file(&path)?;
+
+        let metadata_path = snapshot::get_metadata_path(&path);
+        if metadata_path.exists() {
+            fs::remove_file(metadata_path)?;
+        }
+    }
+    Ok(())
+}
+
+fn interval_millis(schedule: &SnapshotSchedule) -> u64 {
+    schedule.interval_hours.max(1) * 3600 * 1000
+}
+
+fn file_prefix(connection_name: &String) -> String {
+    connection_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn get_state_path(connection_name: &String) -> PathBuf {
+    let mut path = file_util::get_snapshot_schedule_dir_path();
+    path.push(md5(connection_name));
+    path
+}
+
+pub fn load_state(connection_name: &String) -> Result<SnapshotScheduleState, LogicError> {
+    let path = get_state_path(connection_name);
+    if !path.exists() {
+        return Ok(SnapshotScheduleState::default());
+    }
+    let content = fs::read(path)?;
+    Ok(serde_json::from_slice(&content)?)
+}
+
+fn save_state(connection_name: &String, state: &SnapshotScheduleState) -> Result<(), LogicError> {
+    let content = serde_json::to_string(state)?;
+    fs::write(get_state_path(connection_name), content)?;
+    Ok(())
+}
+
+/// 启用定时备份时从当前时间开始计算下一次执行时间，避免启用后立即执行一次备份
+pub fn reset_next_run(connection_name: &String, schedule: &SnapshotSchedule) {
+    let mut state = load_state(connection_name).unwrap_or_else(|e| {
+        warn!("Failed to load snapshot schedule state: {:?}", e);
+        SnapshotScheduleState::default()
+    });
+    state.next_run = now_timestamp() as u64 + interval_millis(schedule);
+    if let Err(e) = save_state(connection_name, &state) {
+        warn!("Failed to save snapshot schedule state: {:?}", e);
+    }
+}
+
+/// 删除连接的定时快照状态
+pub fn remove_state(connection_name: &String) {
+    let path = get_state_path(connection_name);
+    if path.exists() {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,128 @@
+use async_trait::async_trait;
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::TokioAsyncResolver;
+use log::info;
+
+use crate::error::LogicError;
+
+/// 启用 tls 的客户端服务名，与 etcd 的 `--discovery-srv` 一致
+const CLIENT_SSL_SERVICE: &str = "_etcd-client-ssl";
+
+/// 未启用 tls 的客户端服务名
+const CLIENT_SERVICE: &str = "_etcd-client";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    /// 目标主机名，不带末尾的 `.`
+    pub target: String,
+}
+
+/// SRV 记录查询，便于替换为本地的 DNS 替身
+#[async_trait]
+pub trait SrvResolver: Send + Sync {
+    /// 查询 SRV 记录，没有记录时返回空列表
+    async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>, LogicError>;
+}
+
+/// 使用系统 DNS 配置的解析器
+pub struct SystemSrvResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl SystemSrvResolver {
+    pub fn new() -> Result<Self, LogicError> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|e| LogicError::MsgError(format!("Failed to create dns resolver: {}", e)))?;
+        Ok(SystemSrvResolver { resolver })
+    }
+}
+
+#[async_trait]
+impl SrvResolver for SystemSrvResolver {
+    async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>, LogicError> {
+        match self.resolver.srv_lookup(name).await {
+            Ok(lookup) => Ok(lookup
+                .iter()
+                .map(|srv| SrvRecord {
+                    priority: srv.priority(),
+                    weight: srv.weight(),
+                    port: srv.port(),
+                    target: srv.target().to_utf8().trim_end_matches('.').to_string(),
+                })
+                .collect()),
+            Err(e) => match e.kind() {
+                ResolveErrorKind::NoRecordsFound { .. } => Ok(vec![]),
+                _ => Err(LogicError::MsgError(format!(
+                    "Failed to lookup SRV record '{}': {}",
+                    name, e
+                ))),
+            },
+        }
+    }
+}
+
+/// 通过 SRV 记录发现的节点
+#[derive(Debug, Clone, PartialEq)]
+pub struct SrvEndpoint {
+    pub host: String,
+    pub port: u16,
+    /// 来自 `_etcd-client-ssl` 记录的节点使用 https
+    pub tls: bool,
+}
+
+impl SrvEndpoint {
+    pub fn url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, self.host, self.port)
+    }
+}
+
+/// 参考 etcd 客户端的 SRV 发现，查询 `_etcd-client-ssl._tcp.<domain>` 与 `_etcd-client._tcp.<domain>`
+///
+/// `service_name` 对应 `--discovery-srv-name`，不为空时服务名追加 `-<service_name>`。
+/// 返回的节点 tls 在前，同一服务内按优先级升序、权重降序排列
+pub async fn discover(
+    resolver: &dyn SrvResolver,
+    domain: &str,
+    service_name: Option<&str>,
+) -> Result<Vec<SrvEndpoint>, LogicError> {
+    let domain = domain.trim().trim_end_matches('.');
+    let suffix = match service_name.map(|s| s.trim()) {
+        Some(name) if !name.is_empty() => format!("-{}", name),
+        _ => String::new(),
+    };
+
+    let mut endpoints: Vec<SrvEndpoint> = vec![];
+    for (service, tls) in [(CLIENT_SSL_SERVICE, true), (CLIENT_SERVICE, false)] {
+        let name = format!("{}{}._tcp.{}", service, suffix, domain);
+        let mut records = resolver.lookup_srv(&name).await?;
+        records.sort_by(|a, b| a.priority.cmp(&b.priority).then(b.weight.cmp(&a.weight)));
+
+        for record in records {
+            let endpoint = SrvEndpoint {
+                host: record.target,
+                port: record.port,
+                tls,
+            };
+            if !endpoints.contains(&endpoint) {
+                endpoints.push(endpoint);
+            }
+        }
+    }
+
+    if endpoints.is_empty() {
+        return Err(LogicError::MsgError(format!(
+            "No etcd client SRV records found for domain: {}",
+            domain
+        )));
+    }
+    info!(
+        "Discovered etcd endpoints from SRV records of {}: {:?}",
+        domain,
+        endpoints.iter().map(|e| e.url()).collect::<Vec<String>>()
+    );
+    Ok(endpoints)
+}
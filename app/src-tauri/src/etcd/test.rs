@@ -144,4 +144,779 @@ mod test_connect {
     fn is_valid_utf8(bytes: &[u8]) -> bool {
         std::str::from_utf8(bytes).is_ok()
     }
-}
\ No newline at end of file
+}
+mod test_auto_compactor {
+    use std::collections::VecDeque;
+    use crate::etcd::auto_compactor::resolve_period_revision;
+
+    #[test]
+    fn period_revision() {
+        let mut samples: VecDeque<(u64, i64)> = VecDeque::new();
+        samples.push_back((1000, 10));
+        assert_eq!(resolve_period_revision(&mut samples, 500), None);
+
+        samples.push_back((2000, 20));
+        samples.push_back((3000, 30));
+        assert_eq!(resolve_period_revision(&mut samples, 2500), Some(20));
+        assert_eq!(samples.len(), 2);
+
+        assert_eq!(resolve_period_revision(&mut samples, 3000), Some(30));
+        assert_eq!(samples.len(), 1);
+    }
+}
+
+mod test_health_probe {
+    use crate::etcd::health_probe::latency_stats;
+
+    #[test]
+    fn latency_percentile() {
+        assert!(latency_stats(vec![]).is_none());
+
+        let samples = (1..=100).rev().map(|i| i as f64).collect::<Vec<f64>>();
+        let stats = latency_stats(samples).unwrap();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 100.0);
+        assert_eq!(stats.avg, 50.5);
+        assert_eq!(stats.p50, 50.0);
+        assert_eq!(stats.p90, 90.0);
+        assert_eq!(stats.p99, 99.0);
+
+        let stats = latency_stats(vec![3.0]).unwrap();
+        assert_eq!(stats.p50, 3.0);
+        assert_eq!(stats.p99, 3.0);
+    }
+}
+
+mod test_srv_discovery {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+
+    use crate::error::LogicError;
+    use crate::etcd::srv_discovery::{discover, SrvEndpoint, SrvRecord, SrvResolver};
+
+    /// 本地 DNS 替身，只返回预设的记录
+    struct StandInResolver {
+        records: HashMap<String, Vec<SrvRecord>>,
+    }
+
+    #[async_trait]
+    impl SrvResolver for StandInResolver {
+        async fn lookup_srv(&self, name: &str) -> Result<Vec<SrvRecord>, LogicError> {
+            Ok(self.records.get(name).cloned().unwrap_or_default())
+        }
+    }
+
+    fn record(priority: u16, weight: u16, target: &str) -> SrvRecord {
+        SrvRecord {
+            priority,
+            weight,
+            port: 2379,
+            target: String::from(target),
+        }
+    }
+
+    #[tokio::test]
+    async fn discover_srv() {
+        let mut records = HashMap::new();
+        records.insert(
+            String::from("_etcd-client-ssl._tcp.example.com"),
+            vec![
+                record(10, 10, "etcd-2.example.com"),
+                record(0, 10, "etcd-1.example.com"),
+                record(10, 50, "etcd-3.example.com"),
+            ],
+        );
+        records.insert(
+            String::from("_etcd-client-prod._tcp.example.com"),
+            vec![record(0, 0, "etcd-p.example.com")],
+        );
+        let resolver = StandInResolver { records };
+
+        let endpoints = discover(&resolver, "example.com.", None).await.unwrap();
+        let urls = endpoints.iter().map(|e| e.url()).collect::<Vec<String>>();
+        assert_eq!(
+            urls,
+            vec![
+                "https://etcd-1.example.com:2379",
+                "https://etcd-3.example.com:2379",
+                "https://etcd-2.example.com:2379",
+            ]
+        );
+
+        let endpoints = discover(&resolver, "example.com", Some("prod"))
+            .await
+            .unwrap();
+        assert_eq!(
+            endpoints,
+            vec![SrvEndpoint {
+                host: String::from("etcd-p.example.com"),
+                port: 2379,
+                tls: false,
+            }]
+        );
+
+        assert!(discover(&resolver, "example.org", None).await.is_err());
+    }
+}
+
+mod test_gateway_proto {
+    use crate::etcd::gateway_proto::{
+        LeaseGrantRequest, RangeRequest, RangeResponse, WatchStreamChunk,
+    };
+
+    #[test]
+    fn gateway_json() {
+        let request = RangeRequest {
+            key: Vec::from("/foo"),
+            range_end: Vec::from("/fop"),
+            keys_only: true,
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["key"], "L2Zvbw==");
+        assert_eq!(json["range_end"], "L2ZvcA==");
+        assert_eq!(json["keys_only"], true);
+
+        let json = serde_json::to_value(&LeaseGrantRequest { ttl: 60, id: 0 }).unwrap();
+        assert_eq!(json["TTL"], 60);
+
+        let response: RangeResponse = serde_json::from_str(
+            r#"{"header":{"cluster_id":"14841639068965178418","revision":"12"},
+            "kvs":[{"key":"L2Zvbw==","create_revision":"3","mod_revision":"12","version":"2","value":"YmFy"}],
+            "count":"1"}"#,
+        )
+        .unwrap();
+        assert_eq!(response.header.unwrap().cluster_id, 14841639068965178418);
+        assert_eq!(response.count, 1);
+        assert_eq!(response.kvs[0].key, b"/foo");
+        assert_eq!(response.kvs[0].value, b"bar");
+        assert_eq!(response.kvs[0].mod_revision, 12);
+        assert!(!response.more);
+
+        let chunk: WatchStreamChunk = serde_json::from_str(
+            r#"{"result":{"header":{"revision":"13"},"events":[{"type":"DELETE","kv":{"key":"L2Zvbw==","mod_revision":"13"}}]}}"#,
+        )
+        .unwrap();
+        let result = chunk.result.unwrap();
+        assert_eq!(result.events.len(), 1);
+        assert_eq!(result.events[0].event_type, 1);
+        assert_eq!(result.events[0].kv.as_ref().unwrap().key, b"/foo");
+    }
+}
+
+mod test_v2_client {
+    use reqwest::StatusCode;
+
+    use crate::etcd::v2_client::{
+        export_content, flatten_values, migrate_key, migrate_ttl, parse_response,
+    };
+    use crate::transport::kv::V2Node;
+    use crate::utils::hex_to_vec;
+
+    #[test]
+    fn v2_response() {
+        let node = parse_response(
+            StatusCode::OK,
+            br#"{"action":"get","node":{"key":"/app","dir":true,"nodes":[
+            {"key":"/app/a","value":"1","modifiedIndex":4,"createdIndex":4},
+            {"key":"/app/conf","dir":true,"nodes":[
+                {"key":"/app/conf/b","value":"2","expiration":"2026-10-19T08:00:00Z","ttl":30,"modifiedIndex":6,"createdIndex":5}
+            ],"modifiedIndex":5,"createdIndex":5},
+            {"key":"/app/empty","dir":true,"modifiedIndex":7,"createdIndex":7}
+            ],"modifiedIndex":3,"createdIndex":3}}"#,
+        )
+        .unwrap();
+        assert!(node.dir);
+        assert_eq!(node.nodes.len(), 3);
+
+        let values = flatten_values(node);
+        let keys = values.iter().map(|n| n.key.as_str()).collect::<Vec<&str>>();
+        assert_eq!(keys, vec!["/app/a", "/app/conf/b"]);
+        assert_eq!(values[0].ttl, None);
+        assert_eq!(values[1].ttl, Some(30));
+        assert_eq!(values[1].value.as_deref(), Some("2"));
+        assert_eq!(values[1].created_index, 5);
+
+        let error = parse_response(
+            StatusCode::NOT_FOUND,
+            br#"{"errorCode":100,"message":"Key not found","cause":"/foo","index":12}"#,
+        );
+        assert!(
+            matches!(error, Err(crate::error::LogicError::MsgError(msg)) if msg == "Key not found (100): /foo")
+        );
+        assert!(parse_response(StatusCode::NOT_FOUND, b"404 page not found").is_err());
+    }
+
+    fn value_node(key: &str, value: Option<&str>, ttl: Option<i64>) -> V2Node {
+        V2Node {
+            key: String::from(key),
+            dir: false,
+            value: value.map(String::from),
+            ttl,
+            expiration: None,
+            created_index: 1,
+            modified_index: 1,
+            nodes: vec![],
+        }
+    }
+
+    #[test]
+    fn v2_export() {
+        let values = vec![
+            value_node("/app/a", Some("1"), None),
+            value_node("/app/empty", None, None),
+        ];
+        let content = export_content(&values);
+        let lines = content.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            lines,
+            vec!["2F6170702F61", "31", "2F6170702F656D707479", ""]
+        );
+        assert!(content.ends_with('\n'));
+
+        //  与批量导入读取的格式一致
+        assert_eq!(hex_to_vec(lines[0]).unwrap(), b"/app/a");
+        assert_eq!(hex_to_vec(lines[1]).unwrap(), b"1");
+        assert_eq!(hex_to_vec(lines[3]).unwrap(), b"");
+    }
+
+    #[test]
+    fn v2_migrate() {
+        assert_eq!(migrate_key("/v2", "/app/a"), "/v2/app/a");
+        assert_eq!(migrate_key("/v2/", "/app/a"), "/v2/app/a");
+        assert_eq!(migrate_key("", "/app/a"), "/app/a");
+        assert_eq!(migrate_key("/", "/app/a"), "/app/a");
+
+        assert_eq!(
+            migrate_ttl(&value_node("/a", Some("1"), Some(30))),
+            Some(30)
+        );
+        assert_eq!(migrate_ttl(&value_node("/a", Some("1"), Some(0))), None);
+        assert_eq!(migrate_ttl(&value_node("/a", Some("1"), None)), None);
+    }
+}
+
+mod test_snapshot_hasher {
+    use sha2::{Digest, Sha256};
+
+    use crate::etcd::snapshot::SnapshotHasher;
+
+    fn snapshot_data(content: &[u8]) -> Vec<u8> {
+        let mut data = content.to_vec();
+        data.extend_from_slice(Sha256::digest(content).as_slice());
+        data
+    }
+
+    #[test]
+    fn verify_digest() {
+        let content = (0..1000u32).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        let data = snapshot_data(&content);
+
+        //  分块大小不同时 hash 的边界也不同
+        for chunk in [1, 7, 32, 33, 4096] {
+            let mut hasher = SnapshotHasher::new();
+            for slice in data.chunks(chunk) {
+                hasher.update(slice);
+            }
+            assert_eq!(hasher.size(), data.len() as u64);
+            assert_eq!(
+                hasher.verify().unwrap(),
+                format!("{:x}", Sha256::digest(&content))
+            );
+        }
+
+        let mut corrupted = data.clone();
+        corrupted[10] ^= 0xff;
+        let mut hasher = SnapshotHasher::new();
+        hasher.update(&corrupted);
+        assert!(hasher.verify().is_err());
+
+        let mut hasher = SnapshotHasher::new();
+        hasher.update(&data[..20]);
+        assert!(hasher.verify().is_err());
+    }
+}
+
+mod test_bbolt {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::etcd::bbolt::{BoltDb, BoltWriter};
+    use crate::utils::file_util;
+
+    const PAGE_SIZE: usize = 4096;
+
+    fn write_db(big_value: &[u8]) -> PathBuf {
+        let path = PathBuf::from(file_util::create_temp_file(&[]).unwrap());
+        let mut writer = BoltWriter::create(&path, PAGE_SIZE).unwrap();
+        writer.begin_bucket(b"key").unwrap();
+        writer.put(b"big", big_value).unwrap();
+        writer.begin_bucket(b"meta").unwrap();
+        for i in 0..1000 {
+            let key = format!("key-{:04}", i);
+            writer.put(key.as_bytes(), &[i as u8; 32]).unwrap();
+        }
+        writer.finish(7).unwrap();
+        path
+    }
+
+    #[test]
+    fn write_and_read() {
+        let big_value = (0..3 * PAGE_SIZE).map(|i| i as u8).collect::<Vec<u8>>();
+        let path = write_db(&big_value);
+
+        let mut db = BoltDb::open(&path).unwrap();
+        assert_eq!(db.page_size(), PAGE_SIZE);
+        //  两个 meta 页的事务号分别为 7、8，选择较大的一个
+        assert_eq!(db.txid(), 8);
+        assert_eq!(
+            db.bucket_names().unwrap(),
+            vec![b"key".to_vec(), b"meta".to_vec()]
+        );
+        assert!(db.bucket(b"lease").unwrap().is_none());
+
+        let key_bucket = db.bucket(b"key").unwrap().unwrap();
+        assert_eq!(db.get(&key_bucket, b"big").unwrap(), Some(big_value));
+        assert_eq!(db.get(&key_bucket, b"small").unwrap(), None);
+
+        //  1000 个键值对会写入多个叶子页以及分支页
+        let meta_bucket = db.bucket(b"meta").unwrap().unwrap();
+        assert_eq!(
+            db.get(&meta_bucket, b"key-0999").unwrap(),
+            Some(vec![(999 % 256) as u8; 32])
+        );
+        let mut keys = vec![];
+        db.for_each(&meta_bucket, |key, _| {
+            keys.push(String::from_utf8(key.to_vec()).unwrap());
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(keys.len(), 1000);
+        assert_eq!(keys[0], "key-0000");
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn page_out_of_range() {
+        let path = write_db(&vec![1u8; 3 * PAGE_SIZE]);
+
+        //  "key" bucket 的叶子页是第一个数据页 (pgid 3)，将它的溢出页数量改为超出文件范围
+        let mut data = fs::read(&path).unwrap();
+        let header = 3 * PAGE_SIZE + 12;
+        data[header..header + 4].copy_from_slice(&1_000_000u32.to_le_bytes());
+        fs::write(&path, &data).unwrap();
+        let mut db = BoltDb::open(&path).unwrap();
+        let key_bucket = db.bucket(b"key").unwrap().unwrap();
+        assert!(db.get(&key_bucket, b"big").is_err());
+
+        //  根 bucket 写在文件末尾，截断后无法读取
+        data.truncate(data.len() - PAGE_SIZE);
+        fs::write(&path, &data).unwrap();
+        let mut db = BoltDb::open(&path).unwrap();
+        assert!(db.bucket_names().is_err());
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn cyclic_branch_page() {
+        let path = write_db(&[1u8; 16]);
+
+        //  "meta" bucket 的 1000 个键值对有分支页，将分支页的第一个子页面指向自身
+        let mut data = fs::read(&path).unwrap();
+        let pgid = (0..data.len() / PAGE_SIZE)
+            .find(|pgid| data[pgid * PAGE_SIZE + 8] == 0x01)
+            .unwrap();
+        let element = pgid * PAGE_SIZE + 16;
+        data[element + 8..element + 16].copy_from_slice(&(pgid as u64).to_le_bytes());
+        fs::write(&path, &data).unwrap();
+
+        let mut db = BoltDb::open(&path).unwrap();
+        let meta_bucket = db.bucket(b"meta").unwrap().unwrap();
+        assert!(db.get(&meta_bucket, b"key-0000").is_err());
+        assert!(db.for_each(&meta_bucket, |_, _| Ok(true)).is_err());
+
+        let _ = fs::remove_file(path);
+    }
+}
+
+mod test_offline_session {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use prost::Message;
+
+    use crate::etcd::bbolt::BoltWriter;
+    use crate::etcd::kv_reader::KvReader;
+    use crate::etcd::offline_session::OfflineSession;
+    use crate::utils::file_util;
+
+    /// 与 mvccpb.KeyValue 一致
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct KeyValue {
+        #[prost(bytes = "vec", tag = "1")]
+        key: Vec<u8>,
+        #[prost(int64, tag = "2")]
+        create_revision: i64,
+        #[prost(int64, tag = "3")]
+        mod_revision: i64,
+        #[prost(int64, tag = "4")]
+        version: i64,
+        #[prost(bytes = "vec", tag = "5")]
+        value: Vec<u8>,
+    }
+
+    fn revision(main: i64, tombstone: bool) -> Vec<u8> {
+        let mut bytes = main.to_be_bytes().to_vec();
+        bytes.push(b'_');
+        bytes.extend_from_slice(&0i64.to_be_bytes());
+        if tombstone {
+            bytes.push(b't');
+        }
+        bytes
+    }
+
+    fn kv(
+        key: &str,
+        create_revision: i64,
+        mod_revision: i64,
+        version: i64,
+        value: &str,
+    ) -> Vec<u8> {
+        KeyValue {
+            key: Vec::from(key),
+            create_revision,
+            mod_revision,
+            version,
+            value: Vec::from(value),
+        }
+        .encode_to_vec()
+    }
+
+    #[tokio::test]
+    async fn read_snapshot() {
+        let path = PathBuf::from(file_util::create_temp_file(&[]).unwrap());
+        let mut writer = BoltWriter::create(&path, 4096).unwrap();
+        writer.begin_bucket(b"key").unwrap();
+        let records = [
+            (revision(2, false), kv("/a", 2, 2, 1, "1")),
+            (revision(3, false), kv("/b", 3, 3, 1, "x")),
+            (revision(4, false), kv("/a", 2, 4, 2, "2")),
+            (revision(5, true), kv("/b", 0, 0, 0, "")),
+        ];
+        for (revision, value) in &records {
+            writer.put(revision, value).unwrap();
+        }
+        writer.begin_bucket(b"meta").unwrap();
+        writer
+            .put(b"finishedCompactRev", &revision(3, false))
+            .unwrap();
+        writer.finish(1).unwrap();
+
+        let mut session = OfflineSession::open(path.clone()).unwrap();
+        let data = session.session_data(1);
+        assert_eq!(data.revision, 5);
+        assert_eq!(data.compact_revision, 3);
+        assert_eq!(data.key_count, 1);
+
+        let keys = session.kv_get_all_keys().await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key, "/a");
+        assert!(keys[0].value.is_empty());
+
+        let latest = session.kv_get(Vec::from("/a")).await.unwrap();
+        assert_eq!(latest.value, b"2");
+        assert_eq!(latest.mod_revision, 4);
+        assert_eq!(latest.version, 2);
+
+        let old = session.kv_get_by_version(Vec::from("/a"), 3).await.unwrap();
+        assert_eq!(old.value, b"1");
+        assert!(session.kv_get_by_version(Vec::from("/a"), 2).await.is_err());
+        assert!(session.kv_get(Vec::from("/b")).await.is_err());
+
+        let history = session
+            .kv_get_history_versions(Vec::from("/a"), 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(history, vec![4]);
+
+        let _ = fs::remove_file(path);
+    }
+}
+
+mod test_snapshot_restore {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use prost::Message;
+    use uuid::Uuid;
+
+    use crate::etcd::bbolt::{BoltDb, BoltWriter};
+    use crate::etcd::snapshot_restore::{crc32c_update, restore};
+    use crate::transport::maintenance::SnapshotRestoreOptions;
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct WalRecord {
+        #[prost(int64, tag = "1")]
+        r#type: i64,
+        #[prost(uint32, tag = "2")]
+        crc: u32,
+        #[prost(bytes = "vec", tag = "3")]
+        data: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct RaftEntry {
+        #[prost(uint64, tag = "3")]
+        index: u64,
+        #[prost(bytes = "vec", tag = "4")]
+        data: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct RaftConfChange {
+        #[prost(uint64, tag = "3")]
+        node_id: u64,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct RaftHardState {
+        #[prost(uint64, tag = "1")]
+        term: u64,
+        #[prost(uint64, tag = "2")]
+        vote: u64,
+        #[prost(uint64, tag = "3")]
+        commit: u64,
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("etcd-workbench-test-{}", Uuid::new_v4()))
+    }
+
+    fn write_snapshot(dir: &PathBuf) -> PathBuf {
+        let path = dir.join("snapshot.db");
+        let mut writer = BoltWriter::create(&path, 4096).unwrap();
+        writer.begin_bucket(b"key").unwrap();
+        writer.put(b"revision", b"kv").unwrap();
+        writer.begin_bucket(b"members").unwrap();
+        writer.put(b"1234", b"{}").unwrap();
+        writer.begin_bucket(b"members_removed").unwrap();
+        writer.put(b"5678", b"removed").unwrap();
+        writer.begin_bucket(b"meta").unwrap();
+        writer
+            .put(b"consistent_index", &100u64.to_be_bytes())
+            .unwrap();
+        writer.finish(10).unwrap();
+        path
+    }
+
+    fn options(snapshot: &PathBuf, data_dir: &PathBuf) -> SnapshotRestoreOptions {
+        SnapshotRestoreOptions {
+            snapshot_path: snapshot.to_string_lossy().to_string(),
+            data_dir: data_dir.to_string_lossy().to_string(),
+            cluster_name: String::from("etcd-cluster"),
+            member_name: String::from("default"),
+            peer_urls: vec![String::from("http://localhost:2380")],
+            initial_cluster: None,
+            skip_hash_check: true,
+        }
+    }
+
+    /// 按帧读取 WAL，检查8字节对齐与累计 crc
+    fn read_wal(data: &[u8]) -> Vec<WalRecord> {
+        let mut records = vec![];
+        let mut offset = 0;
+        let mut crc = 0;
+        while offset < data.len() {
+            let len_field = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            let pad = if len_field >> 63 == 1 {
+                ((len_field >> 56) & 0x7) as usize
+            } else {
+                0
+            };
+            let len = (len_field & 0x00ff_ffff_ffff_ffff) as usize;
+            assert_eq!((len + pad) % 8, 0);
+
+            let start = offset + 8;
+            let record = WalRecord::decode(&data[start..start + len]).unwrap();
+            assert!(data[start + len..start + len + pad].iter().all(|b| *b == 0));
+            crc = crc32c_update(crc, &record.data);
+            assert_eq!(record.crc, crc);
+
+            records.push(record);
+            offset = start + len + pad;
+        }
+        assert_eq!(offset, data.len());
+        records
+    }
+
+    #[test]
+    fn crc32c() {
+        assert_eq!(crc32c_update(0, b"123456789"), 0xE3069283);
+        assert_eq!(crc32c_update(0, &[0u8; 32]), 0x8A9136AA);
+        assert_eq!(
+            crc32c_update(crc32c_update(0, b"1234"), b"56789"),
+            0xE3069283
+        );
+    }
+
+    #[test]
+    fn restore_single_member() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let snapshot = write_snapshot(&dir);
+        let data_dir = dir.join("default.etcd");
+
+        //  与 etcd 默认配置启动时的成员ID、集群ID一致
+        let result = restore(&options(&snapshot, &data_dir), |_| {}).unwrap();
+        assert_eq!(result.member_id, "8e9e05c52164694d");
+        assert_eq!(result.cluster_id, "cdf818194e3a8c32");
+
+        let mut db = BoltDb::open(&data_dir.join("member/snap/db")).unwrap();
+        let members = db.bucket(b"members").unwrap().unwrap();
+        assert!(db.get(&members, b"8e9e05c52164694d").unwrap().is_some());
+        assert!(db.get(&members, b"1234").unwrap().is_none());
+        let removed = db.bucket(b"members_removed").unwrap().unwrap();
+        assert!(db.get(&removed, b"5678").unwrap().is_none());
+        let meta = db.bucket(b"meta").unwrap().unwrap();
+        assert_eq!(
+            db.get(&meta, b"consistent_index").unwrap(),
+            Some(1u64.to_be_bytes().to_vec())
+        );
+        let key = db.bucket(b"key").unwrap().unwrap();
+        assert_eq!(db.get(&key, b"revision").unwrap(), Some(Vec::from("kv")));
+
+        //  已存在的数据目录不能被覆盖
+        assert!(restore(&options(&snapshot, &data_dir), |_| {}).is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn restore_members_sorted() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let snapshot = write_snapshot(&dir);
+        let data_dir = dir.join("infra2.etcd");
+
+        let mut options = options(&snapshot, &data_dir);
+        options.member_name = String::from("infra2");
+        options.initial_cluster = Some(String::from(
+            "infra0=http://10.0.1.10:2380,infra1=http://10.0.1.11:2380,infra2=http://10.0.1.12:2380",
+        ));
+        restore(&options, |_| {}).unwrap();
+
+        let wal =
+            fs::read(data_dir.join("member/wal/0000000000000000-0000000000000000.wal")).unwrap();
+        let records = read_wal(&wal);
+
+        let node_ids = records
+            .iter()
+            .filter(|r| r.r#type == 2)
+            .map(|r| {
+                let entry = RaftEntry::decode(r.data.as_slice()).unwrap();
+                RaftConfChange::decode(entry.data.as_slice())
+                    .unwrap()
+                    .node_id
+            })
+            .collect::<Vec<u64>>();
+        assert_eq!(node_ids.len(), 3);
+        assert!(node_ids.windows(2).all(|w| w[0] < w[1]));
+
+        let state = records.iter().find(|r| r.r#type == 3).unwrap();
+        let hard_state = RaftHardState::decode(state.data.as_slice()).unwrap();
+        assert_eq!(hard_state.vote, node_ids[0]);
+        assert_eq!(hard_state.term, 1);
+        assert_eq!(hard_state.commit, 3);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+mod test_gateway_proxy {
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+
+    use hyper::header::AUTHORIZATION;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server};
+    use prost::Message;
+
+    use crate::etcd::gateway_proto::RangeRequest;
+    use crate::etcd::gateway_proxy::{GatewayEndpoint, GatewayProxy, DEFAULT_GATEWAY_PREFIX};
+
+    /// grpc-gateway 替身，记录收到的 `Authorization` 头
+    fn stand_in_gateway(received: Arc<Mutex<Vec<Option<String>>>>) -> u16 {
+        let make_service = make_service_fn(move |_| {
+            let received = Arc::clone(&received);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                    let authorization = request
+                        .headers()
+                        .get(AUTHORIZATION)
+                        .map(|v| v.to_str().unwrap().to_string());
+                    received.lock().unwrap().push(authorization);
+                    async {
+                        Ok::<_, Infallible>(Response::new(Body::from(
+                            r#"{"header":{"revision":"1"},"count":"0"}"#,
+                        )))
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let port = server.local_addr().port();
+        tokio::spawn(server);
+        port
+    }
+
+    #[tokio::test]
+    async fn token_to_authorization() {
+        let received = Arc::new(Mutex::new(vec![]));
+        let address = format!(
+            "http://127.0.0.1:{}",
+            stand_in_gateway(Arc::clone(&received))
+        );
+        let endpoint = GatewayEndpoint::new(&address, &address, DEFAULT_GATEWAY_PREFIX, &None)
+            .await
+            .unwrap();
+        let proxy = GatewayProxy::start(vec![endpoint]).await.unwrap();
+
+        let message = RangeRequest {
+            key: Vec::from("/foo"),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        let mut frame = vec![0u8];
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&message);
+
+        let client = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        let response = client
+            .post(format!(
+                "http://127.0.0.1:{}/etcdserverpb.KV/Range",
+                proxy.port()
+            ))
+            .header("content-type", "application/grpc")
+            .header("token", "etcd-auth-token")
+            .body(frame)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        response.bytes().await.unwrap();
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![Some(String::from("etcd-auth-token"))]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+
+use crate::error::LogicError;
+use crate::proxy::TcpForwarder;
+use crate::transport::connection::{ConnectionTls, ConnectionUser};
+use crate::transport::kv::V2Node;
+use crate::utils::{http_util, vec_to_hex};
+
+use super::metrics_endpoint::url_host;
+
+/// v2 接口返回的节点
+#[derive(Deserialize)]
+struct V2Response {
+    node: V2Node,
+}
+
+/// v2 接口返回的错误，例如 `{"errorCode":100,"message":"Key not found","cause":"/foo","index":12}`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct V2Error {
+    error_code: i32,
+    message: String,
+    #[serde(default)]
+    cause: Option<String>,
+}
+
+/// etcd v2 http 接口的只读客户端，只用于浏览与迁移 v2 存储中的数据
+pub struct V2Client {
+    client: reqwest::Client,
+    url: Url,
+    user: Option<ConnectionUser>,
+}
+
+impl V2Client {
+    /// `url` 为节点的客户端地址，经过 ssh 或代理时通过隧道转发到本地端口
+    pub async fn new(
+        url: Url,
+        forwarder: Option<&mut dyn TcpForwarder>,
+        tls: &Option<ConnectionTls>,
+        user: Option<ConnectionUser>,
+        timeout: Duration,
+    ) -> Result<Self, LogicError> {
+        let host = url_host(&url)?;
+        let port = url
+            .port_or_known_default()
+            .ok_or(LogicError::MsgError(format!("Invalid v2 url: {}", url)))?;
+
+        let (connect_host, connect_port) = match forwarder {
+            Some(forwarder) => (
+                String::from("127.0.0.1"),
+                forwarder.forward(host, port).await?,
+            ),
+            None => (host, port),
+        };
+
+        let (client, url) =
+            http_util::build_client(&url, &connect_host, connect_port, tls, Some(timeout)).await?;
+        Ok(V2Client { client, url, user })
+    }
+
+    /// 读取节点，`recursive` 为 true 时返回目录下的所有子孙节点
+    pub async fn get(&self, key: &str, recursive: bool) -> Result<V2Node, LogicError> {
+        let mut url = self.url.clone();
+        url.path_segments_mut()
+            .map_err(|_| LogicError::MsgError(format!("Invalid v2 url: {}", self.url)))?
+            .clear()
+            .push("v2")
+            .push("keys")
+            .extend(key.split('/').filter(|s| !s.is_empty()));
+        url.query_pairs_mut()
+            .append_pair("recursive", if recursive { "true" } else { "false" })
+            .append_pair("sorted", "true");
+
+        let mut request = self.client.get(url);
+        if let Some(user) = &self.user {
+            request = request.basic_auth(&user.username, Some(&user.password));
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        parse_response(status, &body)
+    }
+}
+
+pub fn parse_response(status: StatusCode, body: &[u8]) -> Result<V2Node, LogicError> {
+    if status.is_success() {
+        let response: V2Response = serde_json::from_slice(body)?;
+        return Ok(response.node);
+    }
+
+    match serde_json::from_slice::<V2Error>(body) {
+        Ok(error) => Err(LogicError::MsgError(match error.cause {
+            Some(cause) => format!("{} ({}): {}", error.message, error.error_code, cause),
+            None => format!("{} ({})", error.message, error.error_code),
+        })),
+        //  未开启 v2 接口（etcd 3.4 之后默认关闭）时返回的是普通的 404 页面
+        Err(_) => Err(LogicError::MsgError(format!(
+            "The v2 api request failed with status {}, make sure the server is started with --enable-v2",
+            status
+        ))),
+    }
+}
+
+/// 展开目录，返回所有的值节点，子节点按 key 排序
+pub fn flatten_values(node: V2Node) -> Vec<V2Node> {
+    let mut values = vec![];
+    let mut stack = vec![node];
+    while let Some(mut node) = stack.pop() {
+        if node.dir {
+            let children = std::mem::take(&mut node.nodes);
+            stack.extend(children.into_iter().rev());
+        } else {
+            values.push(node);
+        }
+    }
+    values
+}
+
+/// 导出为批量导入所用的文件格式：每个值两行，分别为 key 与 value 的十六进制编码
+pub fn export_content(values: &[V2Node]) -> String {
+    let mut content = String::new();
+    for node in values {
+        content.push_str(&vec_to_hex(node.key.as_bytes()));
+        content.push('\n');
+        content.push_str(&vec_to_hex(node.value.as_deref().unwrap_or_default()));
+        content.push('\n');
+    }
+    content
+}
+
+/// 迁移到 v3 后的 key，v2 的 key 总是以 `/` 开头，前缀末尾的 `/` 不再重复
+pub fn migrate_key(prefix: &str, key: &str) -> String {
+    format!("{}{}", prefix.trim_end_matches('/'), key)
+}
+
+/// 迁移时为值授权租约的 TTL，即将过期的值剩余 TTL 可能为 0，无法用于授权租约
+pub fn migrate_ttl(node: &V2Node) -> Option<i64> {
+    node.ttl.filter(|ttl| *ttl > 0)
+}
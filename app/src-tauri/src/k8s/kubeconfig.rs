@@ -0,0 +1,293 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use log::debug;
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use serde::Deserialize;
+
+use crate::error::LogicError;
+use crate::utils::tls_util::{pem_certs, pem_private_key};
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct Kubeconfig {
+    #[serde(default)]
+    current_context: Option<String>,
+    #[serde(default)]
+    clusters: Vec<NamedCluster>,
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+    #[serde(default)]
+    users: Vec<NamedUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedCluster {
+    name: String,
+    cluster: Cluster,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedContext {
+    name: String,
+    context: Context,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedUser {
+    name: String,
+    user: User,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct Cluster {
+    server: String,
+    certificate_authority: Option<String>,
+    certificate_authority_data: Option<String>,
+    #[serde(default)]
+    insecure_skip_tls_verify: bool,
+    tls_server_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Context {
+    cluster: String,
+    user: Option<String>,
+    namespace: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct User {
+    client_certificate: Option<String>,
+    client_certificate_data: Option<String>,
+    client_key: Option<String>,
+    client_key_data: Option<String>,
+    token: Option<String>,
+    #[serde(rename = "tokenFile")]
+    token_file: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    exec: Option<serde_yaml::Value>,
+    auth_provider: Option<serde_yaml::Value>,
+}
+
+/// 从 kubeconfig 中解析出的 API server 访问信息
+pub struct KubeApiServer {
+    /// API server 地址，例如 `https://10.0.0.1:6443`
+    pub server: String,
+    /// context 中配置的命名空间
+    pub namespace: Option<String>,
+    /// 请求头 `Authorization` 的值
+    pub authorization: Option<String>,
+    /// https 的 API server 使用的 tls 配置
+    pub tls: Option<Arc<ClientConfig>>,
+    /// 校验证书使用的主机名，为空时使用 server 中的主机
+    pub tls_server_name: Option<String>,
+}
+
+/// 读取 kubeconfig 文件，`path` 为空时使用 `KUBECONFIG` 环境变量中的第一个文件或 `~/.kube/config`
+pub fn load(path: Option<&str>, context: Option<&str>) -> Result<KubeApiServer, LogicError> {
+    let path = match path.map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => default_path().ok_or(LogicError::MsgError(String::from(
+            "Unable to locate kubeconfig",
+        )))?,
+    };
+    let content = fs::read_to_string(&path).map_err(|e| {
+        LogicError::MsgError(format!("Failed to read kubeconfig {:?}: {}", path, e))
+    })?;
+    parse(&content, context, path.parent())
+}
+
+fn default_path() -> Option<PathBuf> {
+    if let Ok(paths) = std::env::var("KUBECONFIG") {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        if let Some(path) = paths.split(separator).find(|p| !p.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    Some(tauri::api::path::home_dir()?.join(".kube").join("config"))
+}
+
+/// 解析 kubeconfig 内容，`context` 为空时使用 current-context，文件中的相对路径相对于 `base_dir`
+///
+/// 不支持 exec 与 auth-provider 认证插件
+pub fn parse(
+    content: &str,
+    context: Option<&str>,
+    base_dir: Option<&Path>,
+) -> Result<KubeApiServer, LogicError> {
+    let config: Kubeconfig = serde_yaml::from_str(content)
+        .map_err(|e| LogicError::MsgError(format!("Invalid kubeconfig: {}", e)))?;
+
+    let context_name = context
+        .map(String::from)
+        .or(config.current_context.clone())
+        .ok_or(LogicError::MsgError(String::from(
+            "No context specified in kubeconfig",
+        )))?;
+    let context = config
+        .contexts
+        .iter()
+        .find(|c| c.name == context_name)
+        .map(|c| &c.context)
+        .ok_or(LogicError::MsgError(format!(
+            "Context '{}' not found in kubeconfig",
+            context_name
+        )))?;
+    let cluster = config
+        .clusters
+        .iter()
+        .find(|c| c.name == context.cluster)
+        .map(|c| &c.cluster)
+        .ok_or(LogicError::MsgError(format!(
+            "Cluster '{}' not found in kubeconfig",
+            context.cluster
+        )))?;
+    let default_user = User::default();
+    let user = match &context.user {
+        Some(name) => config
+            .users
+            .iter()
+            .find(|u| &u.name == name)
+            .map(|u| &u.user)
+            .ok_or(LogicError::MsgError(format!(
+                "User '{}' not found in kubeconfig",
+                name
+            )))?,
+        None => &default_user,
+    };
+    debug!(
+        "Use kubeconfig context {}, server: {}",
+        context_name, cluster.server
+    );
+
+    if user.exec.is_some() || user.auth_provider.is_some() {
+        return Err(LogicError::MsgError(String::from(
+            "Credential plugins (exec, auth-provider) in kubeconfig are not supported",
+        )));
+    }
+
+    let authorization = if let Some(token) = &user.token {
+        Some(format!("Bearer {}", token.trim()))
+    } else if let Some(token_file) = &user.token_file {
+        let token = fs::read_to_string(resolve_path(token_file, base_dir))?;
+        Some(format!("Bearer {}", token.trim()))
+    } else if let (Some(username), Some(password)) = (&user.username, &user.password) {
+        let basic = BASE64_STANDARD.encode(format!("{}:{}", username, password));
+        Some(format!("Basic {}", basic))
+    } else {
+        None
+    };
+
+    let tls = if cluster.server.starts_with("https://") {
+        Some(Arc::new(tls_config(cluster, user, base_dir)?))
+    } else {
+        None
+    };
+
+    Ok(KubeApiServer {
+        server: cluster.server.clone(),
+        namespace: context.namespace.clone(),
+        authorization,
+        tls,
+        tls_server_name: cluster.tls_server_name.clone(),
+    })
+}
+
+fn resolve_path(path: &str, base_dir: Option<&Path>) -> PathBuf {
+    let path = PathBuf::from(path);
+    match base_dir {
+        Some(base_dir) if path.is_relative() => base_dir.join(path),
+        _ => path,
+    }
+}
+
+/// 读取 `*-data`（base64）或对应文件中的内容
+fn read_data(
+    data: &Option<String>,
+    file: &Option<String>,
+    base_dir: Option<&Path>,
+) -> Result<Option<Vec<u8>>, LogicError> {
+    if let Some(data) = data {
+        let bytes = BASE64_STANDARD.decode(data.trim()).map_err(|e| {
+            LogicError::MsgError(format!("Invalid base64 data in kubeconfig: {}", e))
+        })?;
+        return Ok(Some(bytes));
+    }
+    match file {
+        Some(file) => Ok(Some(fs::read(resolve_path(file, base_dir))?)),
+        None => Ok(None),
+    }
+}
+
+fn tls_config(
+    cluster: &Cluster,
+    user: &User,
+    base_dir: Option<&Path>,
+) -> Result<ClientConfig, LogicError> {
+    let builder = ClientConfig::builder().with_safe_defaults();
+    let builder = if cluster.insecure_skip_tls_verify {
+        builder.with_custom_certificate_verifier(Arc::new(NoCertificateVerification))
+    } else {
+        let mut roots = RootCertStore::empty();
+        match read_data(
+            &cluster.certificate_authority_data,
+            &cluster.certificate_authority,
+            base_dir,
+        )? {
+            Some(ca) => {
+                for cert in pem_certs(&ca)? {
+                    roots.add(&cert).map_err(|e| {
+                        LogicError::MsgError(format!("Invalid certificate authority: {}", e))
+                    })?;
+                }
+            }
+            None => roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    ta.subject,
+                    ta.spki,
+                    ta.name_constraints,
+                )
+            })),
+        }
+        builder.with_root_certificates(roots)
+    };
+
+    let cert = read_data(
+        &user.client_certificate_data,
+        &user.client_certificate,
+        base_dir,
+    )?;
+    let key = read_data(&user.client_key_data, &user.client_key, base_dir)?;
+    match (cert, key) {
+        (Some(cert), Some(key)) => builder
+            .with_client_auth_cert(pem_certs(&cert)?, pem_private_key(&key)?)
+            .map_err(|e| LogicError::MsgError(format!("Invalid client certificate: {}", e))),
+        _ => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// 对应 `insecure-skip-tls-verify: true`，不校验 API server 的证书
+struct NoCertificateVerification;
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod kubeconfig;
+pub mod port_forward;
+mod test;
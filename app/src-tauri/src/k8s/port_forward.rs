@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use rustls::ServerName;
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::select;
+use tokio::sync::watch;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::api::settings::get_settings;
+use crate::error::LogicError;
+use crate::k8s::kubeconfig::{self, KubeApiServer};
+use crate::proxy::{proxy_tunnel, TcpForwarder};
+use crate::transport::connection::{ConnectionK8s, ConnectionProxy};
+
+/// portforward 使用的 websocket 子协议
+const PORT_FORWARD_PROTOCOL: &str = "v4.channel.k8s.io";
+
+/// 只转发一个端口时的数据通道与错误通道
+const DATA_CHANNEL: u8 = 0;
+const ERROR_CHANNEL: u8 = 1;
+
+trait KubeStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> KubeStream for T {}
+
+type PortForwardStream = WebSocketStream<Box<dyn KubeStream>>;
+
+/// pod 的端口转发目标
+struct PodTarget {
+    api: KubeApiServer,
+    namespace: String,
+    pod: String,
+    /// API server 的连接经过的代理
+    proxy: Option<ConnectionProxy>,
+    connect_timeout: Duration,
+}
+
+/// 经过 Kubernetes API server 的 portforward 子资源转发到 pod 的端口，与 ssh 隧道一样在 `127.0.0.1:0` 上监听，
+/// 每个本地连接都打开一个新的 websocket 连接
+///
+/// 转发只能到达 pod 自身的端口，所有转发目标的 host 都会被忽略
+pub struct K8sPortForward {
+    proxy_port: u16,
+    target: Arc<PodTarget>,
+    simple_info: Arc<String>,
+    send_abort: watch::Sender<()>,
+    /// 转发目标：pod 端口 -> 本地端口
+    forwards: HashMap<u16, u16>,
+}
+
+impl K8sPortForward {
+    pub async fn new(
+        k8s: ConnectionK8s,
+        pod_port: u16,
+        proxy: Option<ConnectionProxy>,
+    ) -> Result<Self, LogicError> {
+        let settings = get_settings().await?;
+        let api = kubeconfig::load(k8s.kubeconfig.as_deref(), k8s.context.as_deref())?;
+        let namespace = k8s
+            .namespace
+            .filter(|ns| !ns.trim().is_empty())
+            .or(api.namespace.clone())
+            .unwrap_or(String::from("default"));
+        Self::connect(
+            api,
+            namespace,
+            k8s.pod,
+            pod_port,
+            proxy,
+            Duration::from_secs(settings.connect_timeout_seconds),
+        )
+        .await
+    }
+
+    pub async fn connect(
+        api: KubeApiServer,
+        namespace: String,
+        pod: String,
+        pod_port: u16,
+        proxy: Option<ConnectionProxy>,
+        connect_timeout: Duration,
+    ) -> Result<Self, LogicError> {
+        let simple_info = format!("{}/{} via {}", namespace, pod, api.server);
+        let target = Arc::new(PodTarget {
+            api,
+            namespace,
+            pod,
+            proxy,
+            connect_timeout,
+        });
+
+        //  先建立一次端口转发，API server 不可用、无权限或 pod 不存在时尽早给出明确的错误
+        let mut stream = open(&target, pod_port).await?;
+        let _ = stream.close(None).await;
+
+        let (send_abort, _) = watch::channel(());
+        let mut tunnel = K8sPortForward {
+            proxy_port: 0,
+            target,
+            simple_info: Arc::new(simple_info),
+            send_abort,
+            forwards: HashMap::new(),
+        };
+        tunnel.proxy_port = tunnel.forward(String::new(), pod_port).await?;
+        Ok(tunnel)
+    }
+
+    pub fn get_proxy_port(&self) -> u16 {
+        self.proxy_port
+    }
+
+    fn handle_tcp_proxy(&self, listener: TcpListener, pod_port: u16) -> Result<(), LogicError> {
+        let local_port = listener.local_addr()?.port();
+        let target = Arc::clone(&self.target);
+        let simple_info = Arc::clone(&self.simple_info);
+        let mut rcv_abort = self.send_abort.subscribe();
+        let stream_abort = rcv_abort.clone();
+        let accept_info = Arc::clone(&simple_info);
+
+        tokio::spawn(async move {
+            let accept_task = async move {
+                let simple_info = accept_info;
+                loop {
+                    let (stream, addr) = match listener.accept().await {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!("k8s port-forward listener error: {e} | {}", simple_info);
+                            break;
+                        }
+                    };
+                    debug!(
+                        "k8s port-forward stream task started, chain: local({}) -> local(127.0.0.1:{}) -> pod({}:{})",
+                        addr, local_port, simple_info, pod_port
+                    );
+
+                    let target = Arc::clone(&target);
+                    let simple_info = Arc::clone(&simple_info);
+                    let mut rcv_abort = stream_abort.clone();
+                    tokio::spawn(async move {
+                        match open(&target, pod_port).await {
+                            Ok(upstream) => {
+                                select! {
+                                    _ = pipe(stream, upstream, &simple_info) => {
+                                        debug!("k8s port-forward stream finished | {}", simple_info)
+                                    }
+                                    _ = rcv_abort.changed() => {
+                                        debug!("k8s port-forward stream task received abort event | {}", simple_info)
+                                    }
+                                }
+                            }
+                            Err(e) => warn!(
+                                "Unable to forward messages via k8s {}: {:?}",
+                                simple_info, e
+                            ),
+                        }
+                    });
+                }
+            };
+
+            select! {
+                _ = accept_task => {
+                    debug!("k8s port-forward accept task finished | {}", simple_info)
+                }
+                _ = rcv_abort.changed() => {
+                    debug!("k8s port-forward accept task received abort event | {}", simple_info)
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TcpForwarder for K8sPortForward {
+    async fn forward(&mut self, _host: String, port: u16) -> Result<u16, LogicError> {
+        if let Some(local_port) = self.forwards.get(&port) {
+            return Ok(*local_port);
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let local_port = listener.local_addr()?.port();
+
+        info!(
+            "{} create k8s port-forward to port {}, local port is {}",
+            self.simple_info, port, local_port
+        );
+
+        self.handle_tcp_proxy(listener, port)?;
+        self.forwards.insert(port, local_port);
+        Ok(local_port)
+    }
+}
+
+impl Drop for K8sPortForward {
+    fn drop(&mut self) {
+        let _ = self.send_abort.send(());
+        debug!("drop k8s port-forward | {}", self.simple_info);
+    }
+}
+
+/// 连接 API server 并升级为 portforward 的 websocket 连接
+async fn open(target: &PodTarget, pod_port: u16) -> Result<PortForwardStream, LogicError> {
+    let api = &target.api;
+    let url = reqwest::Url::parse(&api.server)
+        .map_err(|e| LogicError::MsgError(format!("Invalid API server '{}': {}", api.server, e)))?;
+    let host = url
+        .host_str()
+        .ok_or(LogicError::MsgError(format!(
+            "Invalid API server: {}",
+            api.server
+        )))?
+        .to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let tcp = match &target.proxy {
+        Some(proxy) => proxy_tunnel::connect(proxy, &host, port, target.connect_timeout).await?,
+        None => {
+            let addr = host.trim_start_matches('[').trim_end_matches(']');
+            timeout(target.connect_timeout, TcpStream::connect((addr, port)))
+                .await
+                .map_err(|_| {
+                    io::Error::new(
+                        ErrorKind::ConnectionAborted,
+                        "API server connection timeout",
+                    )
+                })??
+        }
+    };
+
+    let stream: Box<dyn KubeStream> = match &api.tls {
+        Some(tls) => {
+            let server_name = api
+                .tls_server_name
+                .as_deref()
+                .unwrap_or(host.trim_start_matches('[').trim_end_matches(']'));
+            let server_name = ServerName::try_from(server_name).map_err(|e| {
+                LogicError::MsgError(format!("Invalid server name '{}': {}", server_name, e))
+            })?;
+            let connector = TlsConnector::from(Arc::clone(tls));
+            Box::new(connector.connect(server_name, tcp).await?)
+        }
+        None => Box::new(tcp),
+    };
+
+    let scheme = if api.tls.is_some() { "wss" } else { "ws" };
+    let request_url = format!(
+        "{}://{}:{}{}/api/v1/namespaces/{}/pods/{}/portforward?ports={}",
+        scheme,
+        host,
+        port,
+        url.path().trim_end_matches('/'),
+        target.namespace,
+        target.pod,
+        pod_port
+    );
+    let mut request = request_url
+        .into_client_request()
+        .map_err(|e| LogicError::MsgError(format!("Invalid port-forward request: {}", e)))?;
+    let headers = request.headers_mut();
+    headers.insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_static(PORT_FORWARD_PROTOCOL),
+    );
+    if let Some(authorization) = &api.authorization {
+        let value = HeaderValue::from_str(authorization)
+            .map_err(|e| LogicError::MsgError(format!("Invalid kubeconfig credential: {}", e)))?;
+        headers.insert("Authorization", value);
+    }
+
+    let (stream, _) = timeout(
+        target.connect_timeout,
+        tokio_tungstenite::client_async(request, stream),
+    )
+    .await
+    .map_err(|_| io::Error::new(ErrorKind::ConnectionAborted, "port-forward upgrade timeout"))?
+    .map_err(|e| {
+        LogicError::MsgError(format!(
+            "Failed to open port-forward to {}/{}:{}: {}",
+            target.namespace, target.pod, pod_port, e
+        ))
+    })?;
+    Ok(stream)
+}
+
+/// 在本地连接与 portforward 的数据通道之间转发数据
+///
+/// 每个通道收到的第一帧为 2 字节的小端端口号，之后才是数据，错误通道中的内容只记录日志
+async fn pipe(stream: TcpStream, upstream: PortForwardStream, simple_info: &str) {
+    let (mut reader, mut writer) = stream.into_split();
+    let (mut sink, mut source) = upstream.split();
+
+    let to_pod = async {
+        let mut buf = vec![0u8; 8192];
+        loop {
+            let n = match reader.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            let mut frame = Vec::with_capacity(n + 1);
+            frame.push(DATA_CHANNEL);
+            frame.extend_from_slice(&buf[..n]);
+            if let Err(e) = sink.send(Message::Binary(frame)).await {
+                warn!("k8s port-forward write error: {} | {}", e, simple_info);
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    };
+
+    let to_local = async {
+        let mut port_received = [false; 2];
+        while let Some(message) = source.next().await {
+            let data = match message {
+                Ok(Message::Binary(data)) if !data.is_empty() => data,
+                Ok(Message::Close(_)) | Err(_) => break,
+                _ => continue,
+            };
+            let channel = data[0];
+            let mut payload = &data[1..];
+            if let Some(received) = port_received.get_mut(channel as usize) {
+                if !*received {
+                    *received = true;
+                    payload = payload.get(2..).unwrap_or_default();
+                }
+            }
+            match channel {
+                DATA_CHANNEL if !payload.is_empty() => {
+                    if writer.write_all(payload).await.is_err() {
+                        break;
+                    }
+                }
+                ERROR_CHANNEL if !payload.is_empty() => {
+                    warn!(
+                        "k8s port-forward error: {} | {}",
+                        String::from_utf8_lossy(payload),
+                        simple_info
+                    );
+                }
+                _ => {}
+            }
+        }
+        let _ = writer.shutdown().await;
+    };
+
+    select! {
+        _ = to_pod => {}
+        _ = to_local => {}
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg(test)]
+mod test_k8s_port_forward {
+    use std::time::Duration;
+
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::tungstenite::Message;
+
+    use crate::k8s::kubeconfig::parse;
+    use crate::k8s::port_forward::K8sPortForward;
+
+    fn kubeconfig(server: &str, user: &str) -> String {
+        format!(
+            r#"
+apiVersion: v1
+kind: Config
+current-context: test
+clusters:
+- name: local
+  cluster:
+    server: {}
+contexts:
+- name: test
+  context:
+    cluster: local
+    user: admin
+    namespace: kube-system
+users:
+- name: admin
+  user:
+{}
+"#,
+            server, user
+        )
+    }
+
+    /// 本地 API server 替身，校验 portforward 请求后回显数据通道中的内容
+    async fn stand_in_api_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let callback = |request: &Request,
+                                    mut response: Response|
+                     -> Result<Response, ErrorResponse> {
+                        assert_eq!(
+                            request.uri().path(),
+                            "/api/v1/namespaces/kube-system/pods/etcd-0/portforward"
+                        );
+                        assert_eq!(request.uri().query(), Some("ports=2379"));
+                        assert_eq!(
+                            request.headers().get("Authorization").unwrap(),
+                            "Bearer test-token"
+                        );
+                        response.headers_mut().insert(
+                            "Sec-WebSocket-Protocol",
+                            "v4.channel.k8s.io".parse().unwrap(),
+                        );
+                        Ok(response)
+                    };
+                    let mut ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+                        .await
+                        .unwrap();
+                    //  每个通道的第一帧为端口号
+                    let port = 2379u16.to_le_bytes();
+                    ws.send(Message::Binary(vec![0, port[0], port[1]]))
+                        .await
+                        .unwrap();
+                    ws.send(Message::Binary(vec![1, port[0], port[1]]))
+                        .await
+                        .unwrap();
+                    while let Some(Ok(Message::Binary(data))) = ws.next().await {
+                        if ws.send(Message::Binary(data)).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn parse_kubeconfig() {
+        let api = parse(
+            &kubeconfig("http://127.0.0.1:6443", "    token: test-token"),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(api.namespace.as_deref(), Some("kube-system"));
+        assert_eq!(api.authorization.as_deref(), Some("Bearer test-token"));
+        assert!(api.tls.is_none());
+
+        let content = kubeconfig("http://127.0.0.1:6443", "    exec:\n      command: aws");
+        assert!(parse(&content, None, None).is_err());
+        assert!(parse(&content, Some("missing"), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn port_forward() {
+        let api_port = stand_in_api_server().await;
+        let server = format!("http://127.0.0.1:{}", api_port);
+        let api = parse(&kubeconfig(&server, "    token: test-token"), None, None).unwrap();
+        let tunnel = K8sPortForward::connect(
+            api,
+            String::from("kube-system"),
+            String::from("etcd-0"),
+            2379,
+            None,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", tunnel.get_proxy_port()))
+            .await
+            .unwrap();
+        stream.write_all(b"hello etcd").await.unwrap();
+        let mut buf = [0u8; 10];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello etcd");
+    }
+}
@@ -16,9 +16,11 @@ use crate::utils::file_util;
 mod api;
 mod error;
 mod etcd;
+mod k8s;
 #[allow(unused)]
 #[allow(dead_code)]
 mod proto;
+mod proxy;
 mod ssh;
 mod transport;
 mod utils;
@@ -101,6 +103,13 @@ async fn main() {
                 }
             }
 
+            let app_handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = api::maintenance::restore_snapshot_schedules(app_handle).await {
+                    log::error!("Failed to restore snapshot schedules: {:?}", e);
+                }
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -113,6 +122,12 @@ async fn main() {
             api::connection::connect_test,
             api::connection::connect,
             api::connection::disconnect,
+            api::connection::inspect_tls_certificates,
+            api::connection::ssh_host_key_answer,
+            api::connection::ssh_auth_answer,
+            api::connection::ssh_known_hosts_remove,
+            api::connection::offline_session_open,
+            api::connection::offline_session_close,
             api::connection::save_connection,
             api::connection::remove_connection,
             api::connection::get_connection_list,
@@ -142,14 +157,31 @@ async fn main() {
             api::kv::kv_rename_dir,
             api::kv::kv_batch_export,
             api::kv::kv_batch_import,
+            api::kv::kv_v2_get,
+            api::kv::kv_v2_export,
+            api::kv::kv_v2_migrate,
             api::maintenance::get_cluster,
             api::maintenance::maintenance_defragment,
+            api::maintenance::maintenance_defragment_members,
             api::maintenance::maintenance_compact,
+            api::maintenance::alarm_list,
+            api::maintenance::alarm_disarm,
+            api::maintenance::maintenance_recover_nospace,
+            api::maintenance::set_compaction_policy,
+            api::maintenance::maintenance_log_list,
+            api::maintenance::snapshot_verify,
+            api::maintenance::maintenance_restore_snapshot,
+            api::maintenance::set_snapshot_schedule,
+            api::maintenance::snapshot_schedule_state,
             api::maintenance::maintenance_create_snapshot_task,
             api::maintenance::maintenance_stop_snapshot_task,
             api::maintenance::maintenance_remove_snapshot_task,
             api::maintenance::maintenance_list_snapshot_task,
+            api::maintenance::cluster_health,
             api::maintenance::metrics,
+            api::maintenance::metrics_members,
+            api::maintenance::tunnel_stats,
+            api::maintenance::metrics_history,
             api::lease::leases,
             api::lease::lease_get,
             api::lease::lease_grant,
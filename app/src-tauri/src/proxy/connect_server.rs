@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::select;
+use tokio::sync::watch;
+
+use crate::error::LogicError;
+use crate::transport::connection::ConnectionProxy;
+
+use super::proxy_tunnel::{self, pipe, read_http_head};
+
+const CONNECT_TIMEOUT_SECONDS: u64 = 10;
+
+/// 本地的 HTTP CONNECT 代理，将每个 CONNECT 请求经过配置的代理转发
+///
+/// 用于无法直接指定代理的 http 客户端（例如 tauri 的更新器），只支持 CONNECT 方法
+pub struct LocalConnectProxy {
+    port: u16,
+    send_abort: watch::Sender<()>,
+}
+
+impl LocalConnectProxy {
+    pub async fn start(proxy: ConnectionProxy) -> Result<Self, LogicError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let (send_abort, mut rcv_abort) = watch::channel(());
+        let proxy = Arc::new(proxy);
+
+        info!(
+            "Local connect proxy started on 127.0.0.1:{}, upstream is {}",
+            port,
+            proxy_tunnel::proxy_simple_info(&proxy)
+        );
+
+        tokio::spawn(async move {
+            let accept_task = async move {
+                loop {
+                    let stream = match listener.accept().await {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            warn!("local connect proxy listener error: {}", e);
+                            break;
+                        }
+                    };
+                    let proxy = Arc::clone(&proxy);
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connect(stream, &proxy).await {
+                            debug!("local connect proxy request failed: {:?}", e);
+                        }
+                    });
+                }
+            };
+
+            select! {
+                _ = accept_task => {}
+                _ = rcv_abort.changed() => {}
+            }
+            debug!("local connect proxy on port {} finished", port);
+        });
+
+        Ok(LocalConnectProxy { port, send_abort })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for LocalConnectProxy {
+    fn drop(&mut self) {
+        let _ = self.send_abort.send(());
+    }
+}
+
+async fn handle_connect(mut stream: TcpStream, proxy: &ConnectionProxy) -> Result<(), LogicError> {
+    let head = read_http_head(&mut stream).await?;
+    let request_line = head.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let authority = parts.next().unwrap_or_default();
+
+    let target = authority
+        .rsplit_once(':')
+        .and_then(|(host, port)| port.parse::<u16>().ok().map(|port| (host, port)));
+    let (host, port) = match (method, target) {
+        ("CONNECT", Some(target)) => target,
+        _ => {
+            stream
+                .write_all(b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\n\r\n")
+                .await?;
+            return Err(LogicError::MsgError(format!(
+                "Unsupported proxy request: {}",
+                request_line
+            )));
+        }
+    };
+
+    let upstream = match proxy_tunnel::connect(
+        proxy,
+        host,
+        port,
+        Duration::from_secs(CONNECT_TIMEOUT_SECONDS),
+    )
+    .await
+    {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            stream
+                .write_all(b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n")
+                .await?;
+            return Err(e);
+        }
+    };
+
+    stream
+        .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+        .await?;
+    pipe(stream, upstream).await;
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+
+use crate::error::LogicError;
+
+#[cfg(not(target_os = "linux"))]
+pub mod connect_server;
+pub mod proxy_tunnel;
+mod test;
+
+/// 将远程地址转发到本地端口的隧道，ssh 隧道与代理隧道都实现了该接口
+#[async_trait]
+pub trait TcpForwarder: Send {
+    /// 转发远程地址，返回本地端口，同一地址只会创建一次转发
+    async fn forward(&mut self, host: String, port: u16) -> Result<u16, LogicError>;
+}
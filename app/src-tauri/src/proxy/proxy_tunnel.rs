@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::Engine;
+use log::{debug, info, warn};
+use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::select;
+use tokio::sync::watch;
+use tokio::time::timeout;
+use tokio_socks::tcp::Socks5Stream;
+
+use crate::api::settings::get_settings;
+use crate::error::LogicError;
+use crate::transport::connection::{ConnectionProxy, ProxyProtocol};
+
+use super::TcpForwarder;
+
+/// 读取 http 请求头或响应头的最大长度
+const MAX_HTTP_HEAD_LEN: usize = 8192;
+
+/// 经过 HTTP CONNECT 或 SOCKS5 代理的本地转发，与 ssh 隧道一样在 `127.0.0.1:0` 上监听，
+/// 每个本地连接都通过代理打开一个到远程地址的连接
+pub struct ProxyTunnel {
+    proxy_port: u16,
+    proxy: Arc<ConnectionProxy>,
+    proxy_simple_info: Arc<String>,
+    connect_timeout: Duration,
+    send_abort: watch::Sender<()>,
+    /// 转发目标：(远程host, 远程port) -> 本地端口
+    forwards: HashMap<(String, u16), u16>,
+}
+
+impl ProxyTunnel {
+    pub async fn new(
+        proxy: ConnectionProxy,
+        forward_host: String,
+        forward_port: u16,
+    ) -> Result<Self, LogicError> {
+        let settings = get_settings().await?;
+        let connect_timeout = Duration::from_secs(settings.connect_timeout_seconds);
+
+        //  先经过代理建立一次连接，代理不可用或认证失败时尽早给出明确的错误
+        let stream = connect(&proxy, &forward_host, forward_port, connect_timeout).await?;
+        drop(stream);
+
+        let (send_abort, _) = watch::channel(());
+        let mut tunnel = ProxyTunnel {
+            proxy_port: 0,
+            proxy_simple_info: Arc::new(proxy_simple_info(&proxy)),
+            proxy: Arc::new(proxy),
+            connect_timeout,
+            send_abort,
+            forwards: HashMap::new(),
+        };
+        tunnel.proxy_port = tunnel.forward(forward_host, forward_port).await?;
+        Ok(tunnel)
+    }
+
+    pub fn get_proxy_port(&self) -> u16 {
+        self.proxy_port
+    }
+
+    fn handle_tcp_proxy(
+        &self,
+        listener: TcpListener,
+        forward_host: String,
+        forward_port: u16,
+    ) -> Result<(), LogicError> {
+        let local_port = listener.local_addr()?.port();
+        let proxy = Arc::clone(&self.proxy);
+        let proxy_simple_info = Arc::clone(&self.proxy_simple_info);
+        let connect_timeout = self.connect_timeout;
+        let mut rcv_abort = self.send_abort.subscribe();
+        let stream_abort = rcv_abort.clone();
+        let accept_info = Arc::clone(&proxy_simple_info);
+
+        tokio::spawn(async move {
+            let accept_task = async move {
+                let proxy_simple_info = accept_info;
+                loop {
+                    let (stream, addr) = match listener.accept().await {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            warn!("proxy listener error: {e} | {}", proxy_simple_info);
+                            break;
+                        }
+                    };
+                    debug!(
+                        "proxy stream task started, chain: local({}) -> local(127.0.0.1:{}) -> proxy({}) -> remote({}:{})",
+                        addr, local_port, proxy_simple_info, forward_host, forward_port
+                    );
+
+                    let proxy = Arc::clone(&proxy);
+                    let proxy_simple_info = Arc::clone(&proxy_simple_info);
+                    let forward_host = forward_host.clone();
+                    let mut rcv_abort = stream_abort.clone();
+                    tokio::spawn(async move {
+                        let upstream =
+                            connect(&proxy, &forward_host, forward_port, connect_timeout).await;
+                        match upstream {
+                            Ok(upstream) => {
+                                select! {
+                                    _ = pipe(stream, upstream) => {
+                                        debug!("proxy stream finished | {}", proxy_simple_info)
+                                    }
+                                    _ = rcv_abort.changed() => {
+                                        debug!("proxy stream task received abort event | {}", proxy_simple_info)
+                                    }
+                                }
+                            }
+                            Err(e) => warn!(
+                                "Unable to forward messages via proxy {}: {:?}",
+                                proxy_simple_info, e
+                            ),
+                        }
+                    });
+                }
+            };
+
+            select! {
+                _ = accept_task => {
+                    debug!("proxy accept task finished | {}", proxy_simple_info)
+                }
+                _ = rcv_abort.changed() => {
+                    debug!("proxy accept task received abort event | {}", proxy_simple_info)
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TcpForwarder for ProxyTunnel {
+    async fn forward(&mut self, host: String, port: u16) -> Result<u16, LogicError> {
+        if let Some(local_port) = self.forwards.get(&(host.clone(), port)) {
+            return Ok(*local_port);
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let local_port = listener.local_addr()?.port();
+
+        info!(
+            "{} create proxy forward to {}:{}, local port is {}",
+            self.proxy_simple_info, host, port, local_port
+        );
+
+        self.handle_tcp_proxy(listener, host.clone(), port)?;
+        self.forwards.insert((host, port), local_port);
+        Ok(local_port)
+    }
+}
+
+impl Drop for ProxyTunnel {
+    fn drop(&mut self) {
+        let _ = self.send_abort.send(());
+        debug!("drop proxy tunnel | {}", self.proxy_simple_info);
+    }
+}
+
+pub fn proxy_simple_info(proxy: &ConnectionProxy) -> String {
+    let scheme = match proxy.protocol {
+        ProxyProtocol::Http => "http",
+        ProxyProtocol::Socks5 => "socks5",
+    };
+    format!("{}://{}:{}", scheme, proxy.host, proxy.port)
+}
+
+/// 通过代理打开到 `host:port` 的连接
+pub async fn connect(
+    proxy: &ConnectionProxy,
+    host: &str,
+    port: u16,
+    connect_timeout: Duration,
+) -> Result<TcpStream, LogicError> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let connect_future = async {
+        match proxy.protocol {
+            ProxyProtocol::Http => http_connect(proxy, host, port).await,
+            ProxyProtocol::Socks5 => socks5_connect(proxy, host, port).await,
+        }
+    };
+    timeout(connect_timeout, connect_future)
+        .await
+        .map_err(|_| io::Error::new(ErrorKind::TimedOut, "proxy connection timeout"))?
+}
+
+async fn http_connect(
+    proxy: &ConnectionProxy,
+    host: &str,
+    port: u16,
+) -> Result<TcpStream, LogicError> {
+    let mut stream = TcpStream::connect((proxy.host.as_str(), proxy.port)).await?;
+
+    let authority = if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    let mut request = format!(
+        "CONNECT {} HTTP/1.1\r\nHost: {}\r\nProxy-Connection: Keep-Alive\r\n",
+        authority, authority
+    );
+    if let Some(user) = &proxy.user {
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", user.username, user.password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let head = read_http_head(&mut stream).await?;
+    let status_line = head.lines().next().unwrap_or_default();
+    let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+    if status.starts_with('2') {
+        Ok(stream)
+    } else if status == "407" {
+        Err(LogicError::MsgError(String::from(
+            "Proxy authentication required or the credentials were rejected",
+        )))
+    } else {
+        Err(LogicError::MsgError(format!(
+            "Proxy refused the connection to {}: {}",
+            authority, status_line
+        )))
+    }
+}
+
+async fn socks5_connect(
+    proxy: &ConnectionProxy,
+    host: &str,
+    port: u16,
+) -> Result<TcpStream, LogicError> {
+    let proxy_addr = (proxy.host.as_str(), proxy.port);
+    let stream = match &proxy.user {
+        Some(user) => {
+            Socks5Stream::connect_with_password(
+                proxy_addr,
+                (host, port),
+                &user.username,
+                &user.password,
+            )
+            .await
+        }
+        None => Socks5Stream::connect(proxy_addr, (host, port)).await,
+    }
+    .map_err(|e| LogicError::MsgError(format!("Socks5 proxy error: {}", e)))?;
+    Ok(stream.into_inner())
+}
+
+/// 逐字节读取 http 头直到空行，避免读走隧道中的数据
+pub async fn read_http_head(stream: &mut TcpStream) -> Result<String, LogicError> {
+    let mut head = Vec::with_capacity(256);
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HTTP_HEAD_LEN {
+            return Err(LogicError::MsgError(String::from("Http head too large")));
+        }
+        if stream.read(&mut byte).await? == 0 {
+            return Err(LogicError::IoError(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Connection closed before the http head was read",
+            )));
+        }
+        head.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// 双向复制两个连接的数据，任意一端关闭后结束
+pub async fn pipe(mut local: TcpStream, mut upstream: TcpStream) {
+    if let Err(e) = io::copy_bidirectional(&mut local, &mut upstream).await {
+        debug!("proxy stream closed: {}", e);
+    }
+}
@@ -0,0 +1,230 @@
+#![cfg(test)]
+mod test_proxy_tunnel {
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::task::JoinHandle;
+
+    use crate::error::LogicError;
+    use crate::proxy::proxy_tunnel::{connect, read_http_head};
+    use crate::transport::connection::{ConnectionProxy, ConnectionUser, ProxyProtocol};
+
+    /// 只接受一个连接的 http 代理替身，返回收到的 CONNECT 请求头，
+    /// 响应 2xx 时之后原样回写隧道中的数据
+    pub(super) async fn stand_in_proxy(response: &'static str) -> (u16, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let task = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let head = read_http_head(&mut stream).await.unwrap();
+            stream.write_all(response.as_bytes()).await.unwrap();
+            if response.starts_with("HTTP/1.1 2") {
+                let (mut reader, mut writer) = stream.split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            }
+            head
+        });
+        (port, task)
+    }
+
+    pub(super) fn http_proxy(port: u16, user: Option<ConnectionUser>) -> ConnectionProxy {
+        ConnectionProxy {
+            protocol: ProxyProtocol::Http,
+            host: String::from("127.0.0.1"),
+            port,
+            user,
+        }
+    }
+
+    /// 连接到只发送 `content` 后关闭连接的服务端
+    async fn stream_with(content: Vec<u8>) -> TcpStream {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.write_all(&content).await;
+        });
+        TcpStream::connect(("127.0.0.1", port)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn read_head_keeps_tunnel_data() {
+        let mut stream = stream_with(Vec::from(
+            "HTTP/1.1 200 Connection established\r\n\r\nhello",
+        ))
+        .await;
+        let head = read_http_head(&mut stream).await.unwrap();
+        assert_eq!(head, "HTTP/1.1 200 Connection established\r\n\r\n");
+
+        let mut rest = String::new();
+        stream.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "hello");
+    }
+
+    #[tokio::test]
+    async fn read_head_too_large() {
+        let mut stream = stream_with(vec![b'a'; 9000]).await;
+        let result = read_http_head(&mut stream).await;
+        assert!(matches!(result, Err(LogicError::MsgError(msg)) if msg == "Http head too large"));
+    }
+
+    #[tokio::test]
+    async fn read_head_eof() {
+        let mut stream = stream_with(Vec::from("HTTP/1.1 200 OK\r\n")).await;
+        let result = read_http_head(&mut stream).await;
+        assert!(
+            matches!(result, Err(LogicError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof)
+        );
+    }
+
+    #[tokio::test]
+    async fn http_connect_established() {
+        let (port, task) = stand_in_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+        let user = ConnectionUser {
+            username: String::from("user"),
+            password: String::from("pass"),
+        };
+        let mut stream = connect(
+            &http_proxy(port, Some(user)),
+            "etcd",
+            2379,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        stream.write_all(b"ping").await.unwrap();
+        let mut echo = [0u8; 4];
+        stream.read_exact(&mut echo).await.unwrap();
+        assert_eq!(&echo, b"ping");
+        drop(stream);
+
+        let head = task.await.unwrap();
+        assert!(head.starts_with("CONNECT etcd:2379 HTTP/1.1\r\n"));
+        //  base64("user:pass")
+        assert!(head.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+    }
+
+    #[tokio::test]
+    async fn http_connect_ipv6_authority() {
+        let (port, task) = stand_in_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+        let stream = connect(
+            &http_proxy(port, None),
+            "[::1]",
+            2379,
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+        drop(stream);
+
+        let head = task.await.unwrap();
+        assert!(head.starts_with("CONNECT [::1]:2379 HTTP/1.1\r\n"));
+        assert!(!head.contains("Proxy-Authorization"));
+    }
+
+    #[tokio::test]
+    async fn http_connect_auth_required() {
+        let (port, _task) =
+            stand_in_proxy("HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").await;
+        let result = connect(
+            &http_proxy(port, None),
+            "etcd",
+            2379,
+            Duration::from_secs(5),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(LogicError::MsgError(msg))
+                if msg == "Proxy authentication required or the credentials were rejected"
+        ));
+    }
+
+    #[tokio::test]
+    async fn http_connect_refused() {
+        let (port, _task) = stand_in_proxy("HTTP/1.1 403 Forbidden\r\n\r\n").await;
+        let result = connect(
+            &http_proxy(port, None),
+            "etcd",
+            2379,
+            Duration::from_secs(5),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(LogicError::MsgError(msg))
+                if msg == "Proxy refused the connection to etcd:2379: HTTP/1.1 403 Forbidden"
+        ));
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod test_connect_server {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    use crate::proxy::connect_server::LocalConnectProxy;
+    use crate::proxy::proxy_tunnel::read_http_head;
+
+    use super::test_proxy_tunnel::{http_proxy, stand_in_proxy};
+
+    async fn request(server: &LocalConnectProxy, request: &str) -> (TcpStream, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+            .await
+            .unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let head = read_http_head(&mut stream).await.unwrap();
+        (stream, head)
+    }
+
+    #[tokio::test]
+    async fn connect_established() {
+        let (port, task) = stand_in_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+        let server = LocalConnectProxy::start(http_proxy(port, None))
+            .await
+            .unwrap();
+
+        let (mut stream, head) = request(
+            &server,
+            "CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n",
+        )
+        .await;
+        assert_eq!(head, "HTTP/1.1 200 Connection established\r\n\r\n");
+
+        stream.write_all(b"ping").await.unwrap();
+        let mut echo = [0u8; 4];
+        stream.read_exact(&mut echo).await.unwrap();
+        assert_eq!(&echo, b"ping");
+        drop(stream);
+
+        assert!(task
+            .await
+            .unwrap()
+            .starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+    }
+
+    #[tokio::test]
+    async fn method_not_allowed() {
+        let (port, _task) = stand_in_proxy("HTTP/1.1 200 Connection established\r\n\r\n").await;
+        let server = LocalConnectProxy::start(http_proxy(port, None))
+            .await
+            .unwrap();
+
+        let (_, head) = request(&server, "GET http://example.com/ HTTP/1.1\r\n\r\n").await;
+        assert!(head.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[tokio::test]
+    async fn upstream_refused() {
+        let (port, _task) = stand_in_proxy("HTTP/1.1 403 Forbidden\r\n\r\n").await;
+        let server = LocalConnectProxy::start(http_proxy(port, None))
+            .await
+            .unwrap();
+
+        let (_, head) = request(&server, "CONNECT example.com:443 HTTP/1.1\r\n\r\n").await;
+        assert!(head.starts_with("HTTP/1.1 502 Bad Gateway\r\n"));
+    }
+}
@@ -0,0 +1,78 @@
+use log::{debug, info};
+use russh::client::{AuthResult, Handle};
+use russh::keys::agent::client::{AgentClient, AgentStream};
+
+use crate::error::LogicError;
+use crate::ssh::ssh_client::SshClientHandler;
+
+type DynAgentClient = AgentClient<Box<dyn AgentStream + Send + Unpin + 'static>>;
+
+/// windows 上 OpenSSH 的 ssh-agent 默认使用的命名管道
+#[cfg(windows)]
+const WINDOWS_AGENT_PIPE: &str = r"\\.\pipe\openssh-ssh-agent";
+
+/// 连接本地运行的 ssh-agent，unix 上使用 `SSH_AUTH_SOCK`，windows 上未设置时使用 OpenSSH 的默认管道
+#[cfg(unix)]
+async fn connect() -> Result<DynAgentClient, LogicError> {
+    let agent = AgentClient::connect_env()
+        .await
+        .map_err(|e| LogicError::MsgError(format!("Failed to connect to ssh agent: {}", e)))?;
+    Ok(agent.dynamic())
+}
+
+#[cfg(windows)]
+async fn connect() -> Result<DynAgentClient, LogicError> {
+    let pipe = std::env::var("SSH_AUTH_SOCK").unwrap_or_else(|_| String::from(WINDOWS_AGENT_PIPE));
+    let agent = AgentClient::connect_named_pipe(pipe)
+        .await
+        .map_err(|e| LogicError::MsgError(format!("Failed to connect to ssh agent: {}", e)))?;
+    Ok(agent.dynamic())
+}
+
+/// 依次尝试 ssh-agent 中的所有公钥，返回第一个被接受（包括部分成功）的结果，都被拒绝时返回最后一次的结果
+pub async fn authenticate(
+    session: &mut Handle<SshClientHandler>,
+    user: &str,
+) -> Result<AuthResult, LogicError> {
+    let mut agent = connect().await?;
+    let identities = agent.request_identities().await?;
+    if identities.is_empty() {
+        return Err(LogicError::MsgError(String::from(
+            "The ssh agent has no identities",
+        )));
+    }
+
+    let rsa_hash = session.best_supported_rsa_hash().await?.flatten();
+    let mut result = None;
+    for key in identities {
+        let hash_alg = if key.algorithm().is_rsa() {
+            rsa_hash
+        } else {
+            None
+        };
+        let comment = String::from(key.comment());
+        let res = session
+            .authenticate_publickey_with(user, key, hash_alg, &mut agent)
+            .await
+            .map_err(|e| LogicError::MsgError(format!("Ssh agent error: {}", e)))?;
+        if is_accepted(&res) {
+            info!("Ssh authenticated with agent identity {}", comment);
+            return Ok(res);
+        }
+        debug!("Ssh agent identity {} was rejected", comment);
+        result = Some(res);
+    }
+    Ok(result.unwrap())
+}
+
+/// 认证成功或部分成功（还需要继续其他方式的认证）
+pub fn is_accepted(res: &AuthResult) -> bool {
+    matches!(
+        res,
+        AuthResult::Success
+            | AuthResult::Failure {
+                partial_success: true,
+                ..
+            }
+    )
+}
@@ -0,0 +1,151 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use russh::client::{AuthResult, Handle, KeyboardInteractiveAuthResponse};
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+
+use crate::error::LogicError;
+use crate::ssh::ssh_client::SshClientHandler;
+use crate::transport::event::{SshAuthPrompt, SshAuthPromptEvent};
+
+const SSH_AUTH_PROMPT_EVENT: &str = "sshAuthPrompt";
+
+/// 等待用户输入的最长时间，需要留出查看验证码的时间
+const PROMPT_TIMEOUT_SECONDS: u64 = 180;
+
+lazy_static! {
+    static ref PENDING_PROMPTS: DashMap<u32, oneshot::Sender<Option<Vec<String>>>> = DashMap::new();
+}
+
+static PROMPT_ID_COUNTER: AtomicU32 = AtomicU32::new(1);
+
+/// keyboard-interactive 认证，服务器的每一轮提问都转发给界面，由用户输入后回复
+///
+/// 返回值与其他认证方式一致，部分成功时由调用方继续剩余的认证方式
+pub async fn authenticate(
+    session: &mut Handle<SshClientHandler>,
+    app_handle: Option<&AppHandle>,
+    host: &str,
+    port: u16,
+    user: &str,
+) -> Result<AuthResult, LogicError> {
+    let mut response = session
+        .authenticate_keyboard_interactive_start(user, None::<String>)
+        .await?;
+    loop {
+        match response {
+            KeyboardInteractiveAuthResponse::Success => return Ok(AuthResult::Success),
+            KeyboardInteractiveAuthResponse::Failure {
+                remaining_methods,
+                partial_success,
+            } => {
+                return Ok(AuthResult::Failure {
+                    remaining_methods,
+                    partial_success,
+                })
+            }
+            KeyboardInteractiveAuthResponse::InfoRequest {
+                name,
+                instructions,
+                prompts,
+            } => {
+                //  没有提问的请求直接回复，部分服务器会以此结束认证
+                let answers =
+                    if prompts.is_empty() {
+                        vec![]
+                    } else {
+                        let app_handle = app_handle.ok_or(LogicError::MsgError(String::from(
+                            "Keyboard-interactive authentication requires user input",
+                        )))?;
+                        let event = SshAuthPromptEvent {
+                            request_id: 0,
+                            host: String::from(host),
+                            port,
+                            user: String::from(user),
+                            name,
+                            instructions,
+                            prompts: prompts
+                                .into_iter()
+                                .map(|p| SshAuthPrompt {
+                                    prompt: p.prompt,
+                                    echo: p.echo,
+                                })
+                                .collect(),
+                        };
+                        prompt(app_handle, event).await.ok_or(LogicError::MsgError(
+                            String::from("Keyboard-interactive authentication was canceled"),
+                        ))?
+                    };
+                response = session
+                    .authenticate_keyboard_interactive_respond(answers)
+                    .await?;
+            }
+        }
+    }
+}
+
+/// 向界面发送输入请求并等待用户的回复，取消、超时或界面关闭时返回 None
+async fn prompt(app_handle: &AppHandle, mut event: SshAuthPromptEvent) -> Option<Vec<String>> {
+    let (request_id, receiver) = register_prompt();
+
+    event.request_id = request_id;
+    debug!(
+        "Ssh keyboard-interactive prompt {} for {}@{}:{}",
+        request_id, event.user, event.host, event.port
+    );
+    match app_handle.emit_all(SSH_AUTH_PROMPT_EVENT, event) {
+        Ok(_) => {
+            wait_answer(
+                request_id,
+                receiver,
+                Duration::from_secs(PROMPT_TIMEOUT_SECONDS),
+            )
+            .await
+        }
+        Err(e) => {
+            warn!("Failed to send ssh auth prompt: {}", e);
+            PENDING_PROMPTS.remove(&request_id);
+            None
+        }
+    }
+}
+
+/// 登记一个等待用户回复的输入请求，返回请求 id
+pub fn register_prompt() -> (u32, oneshot::Receiver<Option<Vec<String>>>) {
+    let request_id = PROMPT_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = oneshot::channel();
+    PENDING_PROMPTS.insert(request_id, sender);
+    (request_id, receiver)
+}
+
+/// 等待 [`answer`] 对请求的回复，超时后请求失效，之后的回复会返回错误
+pub async fn wait_answer(
+    request_id: u32,
+    receiver: oneshot::Receiver<Option<Vec<String>>>,
+    wait: Duration,
+) -> Option<Vec<String>> {
+    let answers = match timeout(wait, receiver).await {
+        Ok(Ok(answers)) => answers,
+        _ => None,
+    };
+    PENDING_PROMPTS.remove(&request_id);
+    answers
+}
+
+/// 用户对输入请求的回复，`answers` 为 None 表示取消认证
+pub fn answer(request_id: u32, answers: Option<Vec<String>>) -> Result<(), LogicError> {
+    match PENDING_PROMPTS.remove(&request_id) {
+        Some((_, sender)) => {
+            let _ = sender.send(answers);
+            Ok(())
+        }
+        None => Err(LogicError::ResourceNotExist(
+            "The authentication request has expired",
+        )),
+    }
+}
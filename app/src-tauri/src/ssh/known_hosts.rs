@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use russh::keys::known_hosts::{check_known_hosts_path, learn_known_hosts_path};
+use russh::keys::ssh_key::{HashAlg, PublicKey};
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+
+use crate::error::LogicError;
+use crate::transport::connection::SshHostKeyInfo;
+use crate::transport::event::SshHostKeyPromptEvent;
+use crate::utils::file_util;
+
+const SSH_HOST_KEY_PROMPT_EVENT: &str = "sshHostKeyPrompt";
+
+/// 等待用户确认公钥的最长时间，超时视为不信任
+const PROMPT_TIMEOUT_SECONDS: u64 = 120;
+
+lazy_static! {
+    static ref PENDING_PROMPTS: DashMap<u32, oneshot::Sender<bool>> = DashMap::new();
+}
+
+static PROMPT_ID_COUNTER: AtomicU32 = AtomicU32::new(1);
+
+/// 公钥校验结果
+#[derive(Debug, PartialEq)]
+pub enum HostKeyCheck {
+    /// 公钥已被信任
+    Trusted,
+    /// 没有该主机的记录，需要用户确认
+    Unknown,
+    /// 记录中同类型的公钥与服务器的不一致
+    Changed { path: PathBuf, line: usize },
+}
+
+pub fn host_key_info(host: &str, port: u16, key: &PublicKey) -> SshHostKeyInfo {
+    SshHostKeyInfo {
+        host: String::from(host),
+        port,
+        algorithm: key.algorithm().to_string(),
+        fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+    }
+}
+
+/// 校验服务器公钥，workbench 的 known_hosts 中有记录时以其为准，
+/// 否则在 `read_system` 为 true 时读取 `~/.ssh/known_hosts`
+pub fn check(host: &str, port: u16, key: &PublicKey, read_system: bool) -> HostKeyCheck {
+    let result = check_path(host, port, key, &file_util::get_known_hosts_file_path());
+    if result != HostKeyCheck::Unknown || !read_system {
+        return result;
+    }
+    match system_known_hosts_path() {
+        Some(path) => check_path(host, port, key, &path),
+        None => HostKeyCheck::Unknown,
+    }
+}
+
+/// 校验单个 known_hosts 文件，文件中无法解析的内容视为没有记录
+pub fn check_path(host: &str, port: u16, key: &PublicKey, path: &Path) -> HostKeyCheck {
+    match check_known_hosts_path(host, port, key, path) {
+        Ok(true) => HostKeyCheck::Trusted,
+        Ok(false) => HostKeyCheck::Unknown,
+        Err(russh::keys::Error::KeyChanged { line }) => HostKeyCheck::Changed {
+            path: path.to_path_buf(),
+            line,
+        },
+        Err(e) => {
+            warn!("Failed to read known hosts file {:?}: {}", path, e);
+            HostKeyCheck::Unknown
+        }
+    }
+}
+
+/// 将公钥写入 workbench 的 known_hosts
+pub fn trust(host: &str, port: u16, key: &PublicKey) -> Result<(), LogicError> {
+    let path = file_util::get_known_hosts_file_path();
+    learn_known_hosts_path(host, port, key, &path)?;
+    info!("Trusted ssh host key of {}:{}", host, port);
+    Ok(())
+}
+
+/// 从 workbench 的 known_hosts 中删除主机的所有公钥，返回删除的条数
+///
+/// 只处理 workbench 自己写入的记录，`~/.ssh/known_hosts` 中的记录需要用户自行维护
+pub fn forget(host: &str, port: u16) -> Result<usize, LogicError> {
+    let path = file_util::get_known_hosts_file_path();
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let host_port = known_hosts_host(host, port);
+    let content = fs::read_to_string(&path)?;
+    let mut removed = 0;
+    let mut lines = vec![];
+    for line in content.lines() {
+        let hosts = line.split(' ').next().unwrap_or_default();
+        if !line.starts_with('#') && hosts.split(',').any(|h| h == host_port) {
+            removed += 1;
+        } else {
+            lines.push(line);
+        }
+    }
+
+    if removed > 0 {
+        let mut content = lines.join("\n");
+        content.push('\n');
+        fs::write(&path, content)?;
+        info!("Removed {} ssh host keys of {}", removed, host_port);
+    }
+    Ok(removed)
+}
+
+/// known_hosts 中的主机名，非 22 端口时为 `[host]:port`
+fn known_hosts_host(host: &str, port: u16) -> String {
+    if port == 22 {
+        String::from(host)
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+fn system_known_hosts_path() -> Option<PathBuf> {
+    let home = tauri::api::path::home_dir()?;
+    Some(home.join(".ssh").join("known_hosts"))
+}
+
+/// 向界面发送公钥确认事件并等待用户的选择，超时或界面关闭视为不信任
+pub async fn prompt(app_handle: &AppHandle, host_key: SshHostKeyInfo) -> bool {
+    let request_id = PROMPT_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = oneshot::channel();
+    PENDING_PROMPTS.insert(request_id, sender);
+
+    let event = SshHostKeyPromptEvent {
+        request_id,
+        host_key,
+    };
+    let trusted = match app_handle.emit_all(SSH_HOST_KEY_PROMPT_EVENT, event) {
+        Ok(_) => matches!(
+            timeout(Duration::from_secs(PROMPT_TIMEOUT_SECONDS), receiver).await,
+            Ok(Ok(true))
+        ),
+        Err(e) => {
+            warn!("Failed to send ssh host key prompt: {}", e);
+            false
+        }
+    };
+    PENDING_PROMPTS.remove(&request_id);
+    trusted
+}
+
+/// 用户对公钥确认事件的回复
+pub fn answer(request_id: u32, trust: bool) -> Result<(), LogicError> {
+    match PENDING_PROMPTS.remove(&request_id) {
+        Some((_, sender)) => {
+            let _ = sender.send(trust);
+            Ok(())
+        }
+        None => Err(LogicError::ResourceNotExist(
+            "The host key confirmation has expired",
+        )),
+    }
+}
@@ -1,2 +1,8 @@
+pub mod agent;
+pub mod interactive;
+pub mod known_hosts;
+pub mod session_pool;
 pub mod ssh_tunnel;
-pub mod ssh_client;
\ No newline at end of file
+pub mod ssh_client;
+pub mod ssh_config;
+mod test;
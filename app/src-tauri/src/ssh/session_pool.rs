@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::sync::{Arc, Weak};
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use tokio::sync::Mutex;
+
+use crate::error::LogicError;
+use crate::ssh::ssh_tunnel::SharedSshSession;
+use crate::transport::connection::{ConnectionProxy, SshJumpHost};
+use crate::utils::md5;
+
+lazy_static! {
+    /// 可复用的 ssh 会话
+    static ref SSH_SESSION_POOL: SessionPool<SharedSshSession> = SessionPool::default();
+}
+
+/// 按复用键保存会话的池，会话由使用它的隧道持有，池中只保存弱引用，最后一个使用者释放后自动失效
+pub struct SessionPool<T> {
+    sessions: DashMap<String, Weak<T>>,
+    /// 每个复用键的创建锁，同时创建相同会话的连接依次进行，后进行的连接复用先建立的会话
+    connecting: DashMap<String, Arc<Mutex<()>>>,
+}
+
+impl<T> Default for SessionPool<T> {
+    fn default() -> Self {
+        SessionPool {
+            sessions: DashMap::new(),
+            connecting: DashMap::new(),
+        }
+    }
+}
+
+impl<T> SessionPool<T> {
+    /// 获取仍在使用且 `available` 为 true 的会话
+    pub fn get(&self, key: &str, available: impl Fn(&T) -> bool) -> Option<Arc<T>> {
+        self.sessions
+            .get(key)
+            .and_then(|session| session.upgrade())
+            .filter(|session| available(session.as_ref()))
+    }
+
+    /// 获取可复用的会话，没有时通过 `connect` 建立并放入池中
+    ///
+    /// 相同复用键的创建过程互斥，获得创建锁后会再次检查池中是否已有其他连接建立的会话
+    pub async fn get_or_connect<F, Fut>(
+        &self,
+        key: &str,
+        available: impl Fn(&T) -> bool,
+        connect: F,
+    ) -> Result<Arc<T>, LogicError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Arc<T>, LogicError>>,
+    {
+        if let Some(session) = self.get(key, &available) {
+            return Ok(session);
+        }
+
+        let lock = Arc::clone(self.connecting.entry(key.to_string()).or_default().value());
+        let _guard = lock.lock().await;
+        if let Some(session) = self.get(key, &available) {
+            return Ok(session);
+        }
+
+        let session = connect().await?;
+        self.insert(key.to_string(), &session);
+        Ok(session)
+    }
+
+    pub fn insert(&self, key: String, session: &Arc<T>) {
+        self.sessions.insert(key, Arc::downgrade(session));
+    }
+
+    /// 会话释放时移除，只移除同一个会话，避免误删同时创建的另一个会话
+    pub fn remove(&self, key: &str, session: *const T) {
+        self.sessions
+            .remove_if(key, |_, s| std::ptr::eq(s.as_ptr(), session));
+    }
+}
+
+/// 会话的复用键，由经过的每一跳的 (host, port, user, 认证信息摘要) 与代理组成
+pub fn session_key(hops: &[SshJumpHost], proxy: &Option<ConnectionProxy>) -> String {
+    let mut parts = hops
+        .iter()
+        .map(|hop| {
+            let identity = serde_json::to_vec(&hop.identity).unwrap_or_default();
+            format!("{}@{}:{}#{}", hop.user, hop.host, hop.port, md5(identity))
+        })
+        .collect::<Vec<String>>();
+    if let Some(proxy) = proxy {
+        parts.push(md5(serde_json::to_vec(proxy).unwrap_or_default()));
+    }
+    parts.join(" -> ")
+}
+
+/// 获取可复用的会话，没有时建立新的会话，相同复用键的会话不会被同时建立
+pub async fn get_or_connect<F, Fut>(
+    key: &str,
+    connect: F,
+) -> Result<Arc<SharedSshSession>, LogicError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Arc<SharedSshSession>, LogicError>>,
+{
+    SSH_SESSION_POOL
+        .get_or_connect(key, SharedSshSession::is_available, connect)
+        .await
+}
+
+/// 会话释放时移除，只移除同一个会话
+pub fn remove(key: &str, session: *const SharedSshSession) {
+    SSH_SESSION_POOL.remove(key, session);
+}
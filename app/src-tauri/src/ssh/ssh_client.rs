@@ -1,19 +1,38 @@
 use std::future::Future;
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
-use log::info;
+use log::{debug, info, warn};
 use russh::client::{DisconnectReason, Session};
 use russh::keys::ssh_key;
 use russh::{client, ChannelId};
+use tauri::AppHandle;
+use tokio::sync::mpsc;
 
-use crate::etcd::etcd_connector_handler::EtcdConnectorHandler;
+use crate::api::settings::get_settings;
+use crate::ssh::known_hosts::{self, HostKeyCheck};
+use crate::transport::connection::SshHostKeyInfo;
 use crate::transport::event::DisconnectCase;
 
+/// 服务器公钥未通过校验的原因
+#[derive(Debug, Clone)]
+pub enum HostKeyRejection {
+    /// 与已信任的公钥不一致
+    Changed(SshHostKeyInfo),
+    /// 首次连接时用户没有信任该公钥
+    NotTrusted(SshHostKeyInfo),
+}
+
 pub struct SshClientHandler {
     ssh_user: String,
     ssh_host: String,
     ssh_port: u16,
-    connector_handler: EtcdConnectorHandler,
+    /// 用于向界面请求确认服务器公钥
+    app_handle: Option<AppHandle>,
+    /// 公钥校验失败的原因，握手失败后由调用方读取
+    host_key_rejection: Arc<Mutex<Option<HostKeyRejection>>>,
+    /// 会话断开时通知隧道重连
+    lost_sender: mpsc::UnboundedSender<DisconnectCase>,
 }
 
 impl SshClientHandler {
@@ -21,38 +40,86 @@ impl SshClientHandler {
         ssh_user: String,
         ssh_host: String,
         ssh_port: u16,
-        connector_handler: EtcdConnectorHandler,
+        app_handle: Option<AppHandle>,
+        host_key_rejection: Arc<Mutex<Option<HostKeyRejection>>>,
+        lost_sender: mpsc::UnboundedSender<DisconnectCase>,
     ) -> Self {
         SshClientHandler {
             ssh_user,
             ssh_host,
             ssh_port,
-            connector_handler,
+            app_handle,
+            host_key_rejection,
+            lost_sender,
         }
     }
+
+    /// 首次连接的主机需要用户确认公钥，确认后写入 workbench 的 known_hosts
+    async fn verify_host_key(&self, key: ssh_key::PublicKey) -> Result<bool, russh::Error> {
+        let read_system = get_settings()
+            .await
+            .map(|settings| settings.ssh_read_system_known_hosts)
+            .unwrap_or(true);
+        let info = known_hosts::host_key_info(&self.ssh_host, self.ssh_port, &key);
+
+        let rejection = match known_hosts::check(&self.ssh_host, self.ssh_port, &key, read_system) {
+            HostKeyCheck::Trusted => return Ok(true),
+            HostKeyCheck::Changed { path, line } => {
+                warn!(
+                    "The ssh host key of {}:{} does not match {:?} line {}, fingerprint: {}",
+                    self.ssh_host, self.ssh_port, path, line, info.fingerprint
+                );
+                //  首次连接时由调用方返回错误，重连时由重连任务通知使用该会话的所有连接断开
+                HostKeyRejection::Changed(info)
+            }
+            HostKeyCheck::Unknown => {
+                let trusted = match &self.app_handle {
+                    Some(app_handle) => known_hosts::prompt(app_handle, info.clone()).await,
+                    None => false,
+                };
+                if trusted {
+                    if let Err(e) = known_hosts::trust(&self.ssh_host, self.ssh_port, &key) {
+                        warn!("Failed to save ssh host key: {:?}", e);
+                    }
+                    return Ok(true);
+                }
+                HostKeyRejection::NotTrusted(info)
+            }
+        };
+
+        if let Ok(mut lock) = self.host_key_rejection.lock() {
+            *lock = Some(rejection);
+        }
+        Ok(false)
+    }
 }
 
 #[async_trait]
 impl client::Handler for SshClientHandler {
     type Error = russh::Error;
 
-    #[allow(unused_variables)]
     fn check_server_key(
         &mut self,
         server_public_key: &ssh_key::PublicKey,
     ) -> impl Future<Output = Result<bool, Self::Error>> + Send {
-        async { Ok(true) }
+        let key = server_public_key.clone();
+        async move { self.verify_host_key(key).await }
     }
 
     /// Called when the server signals failure.
+    ///
+    /// 失败只影响对应的通道，由持有该通道的隧道记录，会话共享时不能因此断开其他连接，
+    /// 会话真正断开时由 [`Self::disconnected`] 通知隧道重连
     #[allow(unused_variables)]
     fn channel_failure(
         &mut self,
         channel: ChannelId,
         session: &mut Session,
     ) -> impl Future<Output = Result<(), Self::Error>> + Send {
-        self.connector_handler
-            .disconnected(DisconnectCase::SshChannelFailure);
+        debug!(
+            "{}@{}:{} ssh channel {} failure",
+            self.ssh_user, self.ssh_host, self.ssh_port, channel
+        );
         async { Ok(()) }
     }
 
@@ -78,13 +145,18 @@ impl client::Handler for SshClientHandler {
                 "{}@{}:{} ssh disconnected: {:?}",
                 self.ssh_user, self.ssh_host, self.ssh_port, reason
             );
+            //  由隧道决定重连还是断开会话，隧道已经释放时忽略
             match reason {
-                DisconnectReason::ReceivedDisconnect(_) => {
+                DisconnectReason::ReceivedDisconnect(info) => {
+                    let _ = self
+                        .lost_sender
+                        .send(DisconnectCase::SshDisconnected(info.message));
                     Ok(())
                 }
                 DisconnectReason::Error(e) => {
-                    self.connector_handler
-                        .disconnected(DisconnectCase::SshDisconnected(e.to_string()));
+                    let _ = self
+                        .lost_sender
+                        .send(DisconnectCase::SshDisconnected(e.to_string()));
                     Err(e)
                 }
             }
@@ -0,0 +1,262 @@
+use std::fs;
+use std::path::PathBuf;
+
+use log::debug;
+use russh::keys::decode_secret_key;
+
+use crate::error::LogicError;
+use crate::transport::connection::{ConnectionSsh, SshIdentity, SshJumpHost, SshPrivateKey};
+
+/// `~/.ssh/config` 中与主机别名匹配的配置，同一配置项以第一次出现的值为准
+#[derive(Debug, Default, PartialEq)]
+pub struct SshHostConfig {
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_files: Vec<String>,
+    pub proxy_jump: Option<String>,
+}
+
+/// 解析 ssh config 内容，返回与 `alias` 匹配的所有 `Host` 块合并后的配置
+///
+/// 只支持 `Host` 块，`Match` 块与 `Include` 会被忽略
+pub fn parse(content: &str, alias: &str) -> SshHostConfig {
+    let mut config = SshHostConfig::default();
+    //  第一个 Host 之前的配置对所有主机生效
+    let mut matched = true;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((keyword, value)) => (
+                keyword.to_lowercase(),
+                value
+                    .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+                    .trim(),
+            ),
+            None => continue,
+        };
+        let value = value.trim_matches('"');
+
+        match keyword.as_str() {
+            "host" => matched = host_matches(value, alias),
+            "match" => matched = false,
+            "include" => debug!("Include in ssh config is not supported: {}", value),
+            _ if !matched => {}
+            "hostname" => set_once(&mut config.host_name, value),
+            "user" => set_once(&mut config.user, value),
+            "port" => {
+                if config.port.is_none() {
+                    config.port = value.parse().ok();
+                }
+            }
+            "identityfile" => config.identity_files.push(String::from(value)),
+            "proxyjump" => set_once(&mut config.proxy_jump, value),
+            _ => {}
+        }
+    }
+    config
+}
+
+fn set_once(field: &mut Option<String>, value: &str) {
+    if field.is_none() {
+        *field = Some(String::from(value));
+    }
+}
+
+/// 匹配 `Host` 的模式列表，支持 `*`、`?` 通配符与 `!` 取反
+fn host_matches(patterns: &str, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        if let Some(pattern) = pattern.strip_prefix('!') {
+            if wildcard_match(pattern, alias) {
+                return false;
+            }
+        } else if wildcard_match(pattern, alias) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let text = text.as_bytes();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p].eq_ignore_ascii_case(&text[t])) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    Some(tauri::api::path::home_dir()?.join(".ssh"))
+}
+
+fn read_config(alias: &str) -> Result<SshHostConfig, LogicError> {
+    let path = ssh_dir()
+        .map(|dir| dir.join("config"))
+        .ok_or(LogicError::MsgError(String::from(
+            "Unable to locate ~/.ssh/config",
+        )))?;
+    let content = fs::read_to_string(&path).map_err(|e| {
+        LogicError::MsgError(format!("Failed to read ssh config {:?}: {}", path, e))
+    })?;
+    Ok(parse(&content, alias))
+}
+
+/// 展开 `~` 与 `%d`、`%h`、`%r`、`%u` 等常用的标记
+fn expand_path(path: &str, host: &str, user: &str) -> Option<PathBuf> {
+    let home = tauri::api::path::home_dir()?;
+    let local_user = default_user();
+    let path = path
+        .replace("%d", &home.to_string_lossy())
+        .replace("%h", host)
+        .replace("%r", user)
+        .replace("%u", &local_user)
+        .replace("%%", "%");
+    match path.strip_prefix("~/") {
+        Some(relative) => Some(home.join(relative)),
+        None => Some(PathBuf::from(path)),
+    }
+}
+
+/// 使用 ssh-agent 认证，并读取第一个可用的 `IdentityFile` 作为 ssh-agent 失败时的后备
+fn config_identity(config: &SshHostConfig, host: &str, user: &str) -> SshIdentity {
+    let mut files = config.identity_files.clone();
+    if files.is_empty() {
+        files = ["~/.ssh/id_ed25519", "~/.ssh/id_ecdsa", "~/.ssh/id_rsa"]
+            .iter()
+            .map(|s| String::from(*s))
+            .collect();
+    }
+
+    let key = identity_key(
+        files
+            .iter()
+            .filter_map(|file| expand_path(file, host, user)),
+    );
+
+    SshIdentity {
+        password: None,
+        key,
+        agent: true,
+    }
+}
+
+/// 返回第一个可读且无需密码即可解析的私钥文件，加密或格式不支持的私钥会被跳过
+pub fn identity_key(paths: impl IntoIterator<Item = PathBuf>) -> Option<SshPrivateKey> {
+    paths.into_iter().find_map(|path| {
+        let key = fs::read(&path).ok()?;
+        let content = std::str::from_utf8(&key).ok()?;
+        if let Err(e) = decode_secret_key(content, None) {
+            debug!("Skip ssh identity file {:?}: {}", path, e);
+            return None;
+        }
+        debug!("Use ssh identity file {:?}", path);
+        Some(SshPrivateKey {
+            key,
+            passphrase: None,
+            hash_algorithm: None,
+        })
+    })
+}
+
+fn default_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// 解析 `[user@]host[:port]` 格式的跳板机，主机同样会在 ssh config 中查找别名
+fn resolve_jump_host(jump: &str) -> SshJumpHost {
+    let (user, host_port) = match jump.rsplit_once('@') {
+        Some((user, host_port)) => (Some(String::from(user)), host_port),
+        None => (None, jump),
+    };
+    let (alias, port) = if let Some(bracketed) = host_port.strip_prefix('[') {
+        match bracketed.split_once("]:") {
+            Some((host, port)) => (host, port.parse::<u16>().ok()),
+            None => (bracketed.trim_end_matches(']'), None),
+        }
+    } else {
+        match host_port.split_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>().ok()),
+            None => (host_port, None),
+        }
+    };
+
+    let config = read_config(alias).unwrap_or_default();
+    let host = config
+        .host_name
+        .as_ref()
+        .map(|host_name| host_name.replace("%h", alias))
+        .unwrap_or(String::from(alias));
+    let user = user.or(config.user.clone()).unwrap_or_else(default_user);
+    SshJumpHost {
+        port: port.or(config.port).unwrap_or(22),
+        identity: Some(config_identity(&config, &host, &user)),
+        host,
+        user,
+    }
+}
+
+/// 按 `config_host` 从 `~/.ssh/config` 中补全主机、端口、用户、认证文件与跳板机
+///
+/// ssh config 中的值优先，没有配置的项使用连接配置中的值。连接配置中已有认证信息或跳板机时不会被替换，
+/// 跳板机自身的 ProxyJump 不会继续展开
+pub fn resolve(mut ssh: ConnectionSsh) -> Result<ConnectionSsh, LogicError> {
+    let alias = match &ssh.config_host {
+        Some(alias) if !alias.trim().is_empty() => alias.trim().to_string(),
+        _ => return Ok(ssh),
+    };
+    let config = read_config(&alias)?;
+    debug!("Resolved ssh config of {}: {:?}", alias, config);
+
+    if let Some(host_name) = &config.host_name {
+        ssh.host = host_name.replace("%h", &alias);
+    } else if ssh.host.is_empty() {
+        ssh.host = alias.clone();
+    }
+    if let Some(port) = config.port {
+        ssh.port = port;
+    }
+    if let Some(user) = &config.user {
+        ssh.user = user.clone();
+    } else if ssh.user.is_empty() {
+        ssh.user = default_user();
+    }
+    if ssh.identity.is_none() {
+        ssh.identity = Some(config_identity(&config, &ssh.host, &ssh.user));
+    }
+
+    if ssh.jump_hosts.is_empty() {
+        if let Some(proxy_jump) = &config.proxy_jump {
+            if !proxy_jump.eq_ignore_ascii_case("none") {
+                ssh.jump_hosts = proxy_jump
+                    .split(',')
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .map(resolve_jump_host)
+                    .collect();
+            }
+        }
+    }
+    Ok(ssh)
+}
@@ -1,37 +1,213 @@
 use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::io::{Error, ErrorKind};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::api::settings::get_settings;
 use crate::error::LogicError;
 use crate::etcd::etcd_connector_handler::EtcdConnectorHandler;
-use crate::ssh::ssh_client::SshClientHandler;
-use crate::transport::connection::ConnectionSsh;
-use crate::transport::event::DisconnectCase;
+use crate::etcd::now_timestamp;
+use crate::proxy::{proxy_tunnel, TcpForwarder};
+use crate::ssh::ssh_client::{HostKeyRejection, SshClientHandler};
+use crate::ssh::{agent, interactive, session_pool};
+use crate::transport::connection::{ConnectionProxy, ConnectionSsh, SshIdentity, SshJumpHost};
+use crate::transport::event::{DisconnectCase, SshTunnelState};
+use crate::transport::metrics::{SshChannelFailure, SshTunnelStats};
+use async_trait::async_trait;
 use log::{debug, error, info, warn};
 use russh::client::{AuthResult, Handle, Msg};
 use russh::keys::key::PrivateKeyWithHashAlg;
 use russh::keys::{decode_secret_key, HashAlg};
-use russh::{client, kex, Channel, ChannelMsg, Preferred};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use russh::{client, kex, Channel, ChannelMsg, MethodKind, Preferred};
+use tauri::AppHandle;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{oneshot, watch};
-use tokio::time::timeout;
+use tokio::sync::{mpsc, oneshot, watch, RwLock};
+use tokio::time::{sleep, timeout};
 use tokio::{io, select};
 
+/// 重连的最大等待间隔
+const RECONNECT_MAX_BACKOFF_SECONDS: u64 = 30;
+
+/// 测量 keepalive 往返时间的间隔
+const KEEPALIVE_PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 保留的最近通道打开失败记录条数
+const RECENT_CHANNEL_FAILURE_LIMIT: usize = 20;
+
+/// 多步认证的进度，按服务器返回的 remaining_methods 选择下一步的认证方式
+pub struct AuthProgress {
+    /// 连接配置中有认证信息的方式，keyboard-interactive 由用户输入，总是可用
+    available: Vec<MethodKind>,
+    /// 已部分成功的认证方式
+    succeeded: Vec<MethodKind>,
+    /// 上一次部分成功之后失败的认证方式
+    failed: Vec<MethodKind>,
+}
+
+impl AuthProgress {
+    pub fn new(identity: Option<&SshIdentity>) -> Self {
+        let mut available = vec![];
+        if let Some(identity) = identity {
+            if identity.agent || identity.key.is_some() {
+                available.push(MethodKind::PublicKey);
+            }
+            if identity.password.is_some() {
+                available.push(MethodKind::Password);
+            }
+        }
+        available.push(MethodKind::KeyboardInteractive);
+        AuthProgress {
+            available,
+            succeeded: vec![],
+            failed: vec![],
+        }
+    }
+
+    /// 记录一次认证的结果，部分成功后服务器的状态已改变，之前失败的方式可以再次尝试
+    pub fn record(&mut self, method: MethodKind, partial_success: bool) {
+        if partial_success {
+            self.failed.clear();
+            self.succeeded.push(method);
+        } else {
+            self.failed.push(method);
+        }
+    }
+
+    /// 按 remaining_methods 的顺序选择第一个可用且未失败的方式，
+    /// 除 keyboard-interactive 可以进行多轮外，已部分成功的方式不会重复进行
+    pub fn next(&self, remaining_methods: &[MethodKind]) -> Option<MethodKind> {
+        remaining_methods
+            .iter()
+            .find(|method| {
+                self.available.contains(method)
+                    && !self.failed.contains(method)
+                    && (**method == MethodKind::KeyboardInteractive
+                        || !self.succeeded.contains(method))
+            })
+            .cloned()
+    }
+}
+
 pub struct SshTunnel {
     proxy_port: u16,
     send_abort: watch::Sender<()>,
+    /// 隧道使用的 ssh 会话，可能与其他连接共享
+    shared: Arc<SharedSshSession>,
+    state: Arc<TunnelState>,
+    /// 注册到共享会话中的连接，隧道释放时注销
+    session_id: i32,
+    ssh_simple_info: String,
+    /// 额外的转发目标：(远程host, 远程port) -> 本地端口
+    forwards: HashMap<(String, u16), u16>,
 }
 
-impl SshTunnel {
-    pub async fn new(
-        ssh_config: ConnectionSsh,
-        forward_host: &'static str,
-        forward_port: u16,
+/// 隧道的所有转发任务共享的状态
+struct TunnelState {
+    session: Arc<SessionState>,
+    counters: TunnelCounters,
+}
+
+/// 隧道的流量与诊断计数，包含所有转发目标
+#[derive(Default)]
+pub struct TunnelCounters {
+    pub bytes_in: AtomicU64,
+    pub bytes_out: AtomicU64,
+    pub active_channels: AtomicU64,
+    pub total_channels: AtomicU64,
+    pub channel_open_failures: AtomicU64,
+    recent_failures: Mutex<VecDeque<SshChannelFailure>>,
+}
+
+impl TunnelCounters {
+    pub fn channel_opened(&self) {
+        self.total_channels.fetch_add(1, Ordering::Relaxed);
+        self.active_channels.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn channel_closed(&self) {
+        self.active_channels.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// 记录通道打开失败，只保留最近 [`RECENT_CHANNEL_FAILURE_LIMIT`] 条原因
+    pub fn channel_open_failed(&self, host: &str, port: u16, reason: String) {
+        self.channel_open_failures.fetch_add(1, Ordering::Relaxed);
+        self.push_failure(host, port, reason);
+    }
+
+    /// 记录已打开的通道上服务器返回的 SSH_MSG_CHANNEL_FAILURE，通道与会话仍然可用
+    pub fn channel_request_failed(&self, host: &str, port: u16) {
+        self.push_failure(host, port, String::from("Channel request failed"));
+    }
+
+    fn push_failure(&self, host: &str, port: u16, reason: String) {
+        let mut failures = self.recent_failures.lock().unwrap();
+        if failures.len() >= RECENT_CHANNEL_FAILURE_LIMIT {
+            failures.pop_front();
+        }
+        failures.push_back(SshChannelFailure {
+            time: now_timestamp() as u64,
+            target: format!("{}:{}", host, port),
+            reason,
+        });
+    }
+
+    /// 最近的通道打开失败记录，按时间先后排列
+    pub fn recent_failures(&self) -> Vec<SshChannelFailure> {
+        self.recent_failures
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+/// 可被多个连接共享的 ssh 会话，由使用它的隧道持有，最后一个隧道释放后断开
+///
+/// 会话的重连与 keepalive 探测由会话统一处理，断开与重连状态会通知所有使用它的连接
+pub struct SharedSshSession {
+    key: String,
+    state: Arc<SessionState>,
+    send_abort: watch::Sender<()>,
+}
+
+struct SessionState {
+    /// 当前的 ssh 会话，重连后会被替换，转发任务每次打开通道时读取
+    chain: RwLock<SshChain>,
+    lost_sender: mpsc::UnboundedSender<DisconnectCase>,
+    /// 使用该会话的连接
+    handlers: Mutex<Vec<EtcdConnectorHandler>>,
+    /// 已放弃重连，不能再被复用
+    failed: AtomicBool,
+    reconnects: AtomicU64,
+    /// 最近一次 keepalive 的往返时间（毫秒），为 0 表示尚未测量或会话已断开
+    keepalive_rtt_millis: AtomicU64,
+}
+
+impl SessionState {
+    fn handlers(&self) -> Vec<EtcdConnectorHandler> {
+        self.handlers.lock().unwrap().clone()
+    }
+
+    fn broadcast_state(&self, state: SshTunnelState) {
+        for handler in self.handlers() {
+            handler.ssh_tunnel_state(state.clone());
+        }
+    }
+}
+
+impl SharedSshSession {
+    async fn connect(
+        key: String,
+        ssh_simple_info: String,
+        hops: Vec<SshJumpHost>,
+        proxy: Option<ConnectionProxy>,
         handler: EtcdConnectorHandler,
-    ) -> Result<Self, LogicError> {
+    ) -> Result<Arc<Self>, LogicError> {
         let config = client::Config {
             inactivity_timeout: None,
             keepalive_interval: Some(Duration::from_secs(10)),
@@ -57,68 +233,355 @@ impl SshTunnel {
             ..<_>::default()
         };
 
-        let config = Arc::new(config);
+        let settings = get_settings().await?;
+        let (lost_sender, lost_receiver) = mpsc::unbounded_channel();
+        let connector = SshConnector {
+            config: Arc::new(config),
+            hops,
+            proxy,
+            connect_timeout: Duration::from_secs(settings.ssh_connect_timeout_seconds),
+            app_handle: handler.app_handle().cloned(),
+            lost_sender: lost_sender.clone(),
+        };
+        let state = Arc::new(SessionState {
+            chain: RwLock::new(connector.connect().await?),
+            lost_sender,
+            handlers: Mutex::new(vec![]),
+            failed: AtomicBool::new(false),
+            reconnects: AtomicU64::new(0),
+            keepalive_rtt_millis: AtomicU64::new(0),
+        });
 
-        let ssh_handler = SshClientHandler::new(
-            ssh_config.user.clone(),
-            ssh_config.host.clone(),
-            ssh_config.port,
-            handler.clone(),
-        );
+        let (send_abort, _) = watch::channel(());
 
-        let ssh_simple_info = format!(
-            "{}@{}:{}",
-            ssh_config.user, ssh_config.host, ssh_config.port
+        Self::handle_reconnect(
+            ssh_simple_info.clone(),
+            connector,
+            Arc::clone(&state),
+            lost_receiver,
+            settings.ssh_reconnect_max_attempts,
+            send_abort.subscribe(),
         );
-        let addr = format!("{}:{}", ssh_config.host, ssh_config.port);
 
-        let settings = get_settings().await?;
+        Self::handle_keepalive_probe(ssh_simple_info, Arc::clone(&state), send_abort.subscribe());
 
-        let stream = timeout(
-            Duration::from_secs(settings.ssh_connect_timeout_seconds),
-            TcpStream::connect(addr),
-        )
-        .await
-        .map_err(|_| io::Error::new(ErrorKind::ConnectionAborted, "ssh connection timeout"))??;
-
-        let mut session = client::connect_stream(config, stream, ssh_handler).await?;
-
-        if let Some(identity) = ssh_config.identity {
-            if let Some(key) = identity.key {
-                let passphrase = if let Some(ref p) = key.passphrase {
-                    Some(p.as_str())
-                } else {
-                    None
-                };
-                let hash_alg = key
-                    .hash_algorithm
-                    .clone()
-                    .map(|s| HashAlg::new(s.as_str()).unwrap());
-
-                match decode_secret_key(String::from_utf8(key.key)?.as_str(), passphrase) {
-                    Ok(key_pair) => {
-                        let private_key = PrivateKeyWithHashAlg::new(Arc::new(key_pair), hash_alg);
-                        let res = session
-                            .authenticate_publickey(ssh_config.user, private_key)
-                            .await?;
-                        Self::handle_auth_result(res)?;
+        Ok(Arc::new(SharedSshSession {
+            key,
+            state,
+            send_abort,
+        }))
+    }
+
+    /// 会话仍可被新的连接复用
+    pub fn is_available(&self) -> bool {
+        !self.state.failed.load(Ordering::Relaxed)
+    }
+
+    fn register(&self, handler: EtcdConnectorHandler) {
+        self.state.handlers.lock().unwrap().push(handler);
+    }
+
+    fn unregister(&self, session_id: i32) {
+        let mut handlers = self.state.handlers.lock().unwrap();
+        if let Some(idx) = handlers.iter().position(|h| h.session_id() == session_id) {
+            handlers.remove(idx);
+        }
+    }
+
+    /// 会话断开后按指数退避重新建立 ssh 会话，本地监听端口保持不变，客户端会自行重新连接
+    ///
+    /// 达到最大重连次数或服务器公钥变化时放弃，此时才断开使用该会话的所有连接
+    fn handle_reconnect(
+        ssh_simple_info: String,
+        connector: SshConnector,
+        state: Arc<SessionState>,
+        mut lost_receiver: mpsc::UnboundedReceiver<DisconnectCase>,
+        max_attempts: u32,
+        mut rcv_abort: watch::Receiver<()>,
+    ) {
+        tokio::spawn(async move {
+            let reconnect_task = async {
+                while let Some(case) = lost_receiver.recv().await {
+                    //  同一次断开可能被多个会话重复通知，旧会话的通知也会在重连后到达
+                    if !state.chain.read().await.is_closed() {
+                        continue;
                     }
-                    Err(e) => {
-                        error!("decode ssh key failed: {}", e);
-                        return Err(LogicError::IoError(Error::new(
-                            ErrorKind::ConnectionAborted,
-                            "Failed to parse ssh private key",
-                        )));
+                    warn!(
+                        "ssh session lost, try to reconnect at most {} times | {}",
+                        max_attempts, ssh_simple_info
+                    );
+
+                    let restored = reconnect(
+                        case,
+                        max_attempts,
+                        || connector.connect(),
+                        |tunnel_state| {
+                            if matches!(tunnel_state, SshTunnelState::Failed) {
+                                state.failed.store(true, Ordering::Relaxed);
+                            }
+                            state.broadcast_state(tunnel_state);
+                        },
+                    )
+                    .await;
+
+                    match restored {
+                        Ok(new_chain) => {
+                            *state.chain.write().await = new_chain;
+                            state.reconnects.fetch_add(1, Ordering::Relaxed);
+                            info!("ssh session restored | {}", ssh_simple_info);
+                            state.broadcast_state(SshTunnelState::Restored);
+                        }
+                        Err(case) => {
+                            warn!("ssh session reconnect gave up | {}", ssh_simple_info);
+                            for handler in state.handlers() {
+                                handler.disconnected(case.clone());
+                            }
+                            return;
+                        }
                     }
                 }
-            } else if let Some(password) = identity.password {
-                let res = session
-                    .authenticate_password(ssh_config.user, password)
-                    .await?;
-                Self::handle_auth_result(res)?;
+            };
+
+            select! {
+                _ = reconnect_task => {
+                    debug!("ssh reconnect task finished | {}", ssh_simple_info)
+                }
+                _ = rcv_abort.changed() => {
+                    debug!("ssh reconnect task received abort event | {}", ssh_simple_info)
+                }
+            }
+        });
+    }
+
+    /// 定期发送 keepalive 并等待回复，记录往返时间
+    fn handle_keepalive_probe(
+        ssh_simple_info: String,
+        state: Arc<SessionState>,
+        mut rcv_abort: watch::Receiver<()>,
+    ) {
+        tokio::spawn(async move {
+            let probe_task = async {
+                loop {
+                    sleep(KEEPALIVE_PROBE_INTERVAL).await;
+                    let session = Arc::clone(&state.chain.read().await.session);
+                    let rtt_millis = if session.is_closed() {
+                        0
+                    } else {
+                        let start = Instant::now();
+                        match timeout(KEEPALIVE_PROBE_INTERVAL, session.send_ping()).await {
+                            Ok(Ok(_)) => (start.elapsed().as_millis() as u64).max(1),
+                            _ => {
+                                debug!("ssh keepalive probe failed | {}", ssh_simple_info);
+                                0
+                            }
+                        }
+                    };
+                    state
+                        .keepalive_rtt_millis
+                        .store(rtt_millis, Ordering::Relaxed);
+                }
+            };
+
+            select! {
+                _ = probe_task => {}
+                _ = rcv_abort.changed() => {
+                    debug!("ssh keepalive probe task received abort event | {}", ssh_simple_info)
+                }
+            }
+        });
+    }
+}
+
+/// 第 `attempt` 次重连前的等待时间，从 1 秒开始指数增长，最长 [`RECONNECT_MAX_BACKOFF_SECONDS`] 秒
+pub fn reconnect_backoff(attempt: u32) -> Duration {
+    let backoff = 2u64.saturating_pow(attempt.saturating_sub(1));
+    Duration::from_secs(backoff.min(RECONNECT_MAX_BACKOFF_SECONDS))
+}
+
+/// 按指数退避最多重连 `max_attempts` 次，每次重连前通过 `notify` 通知重连状态
+///
+/// 放弃时先通知 [`SshTunnelState::Failed`]，再返回需要通知连接断开的原因：
+/// 服务器公钥变化时立即放弃并返回 [`DisconnectCase::SshHostKeyChanged`]，达到最大次数时返回原本的断开原因
+pub async fn reconnect<T, F, Fut>(
+    case: DisconnectCase,
+    max_attempts: u32,
+    mut connect: F,
+    mut notify: impl FnMut(SshTunnelState),
+) -> Result<T, DisconnectCase>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, LogicError>>,
+{
+    for attempt in 1..=max_attempts {
+        notify(SshTunnelState::Reconnecting {
+            attempt,
+            max: max_attempts,
+        });
+        sleep(reconnect_backoff(attempt)).await;
+
+        match connect().await {
+            Ok(restored) => return Ok(restored),
+            //  公钥变化时通知所有连接断开，不再重试
+            Err(LogicError::SshHostKeyChanged(info)) => {
+                warn!("ssh host key changed while reconnecting");
+                notify(SshTunnelState::Failed);
+                return Err(DisconnectCase::SshHostKeyChanged(info));
+            }
+            Err(e) => warn!("ssh reconnect attempt {} failed: {:?}", attempt, e),
+        }
+    }
+    notify(SshTunnelState::Failed);
+    Err(case)
+}
+
+impl Drop for SharedSshSession {
+    fn drop(&mut self) {
+        let _ = self.send_abort.send(());
+        session_pool::remove(&self.key, self);
+        debug!("drop shared ssh session");
+    }
+}
+
+/// 到目标主机的 ssh 会话以及途经的跳板机会话，跳板机的会话需要与其一同保持
+struct SshChain {
+    session: Arc<Handle<SshClientHandler>>,
+    jump_sessions: Vec<Handle<SshClientHandler>>,
+}
+
+impl SshChain {
+    fn is_closed(&self) -> bool {
+        self.session.is_closed() || self.jump_sessions.iter().any(|s| s.is_closed())
+    }
+}
+
+/// 建立 ssh 会话所需的信息，首次连接与重连共用
+struct SshConnector {
+    config: Arc<client::Config>,
+    /// 依次经过的主机，最后一跳为 ssh 配置本身
+    hops: Vec<SshJumpHost>,
+    proxy: Option<ConnectionProxy>,
+    connect_timeout: Duration,
+    /// 用于向界面请求输入，例如确认服务器公钥与 keyboard-interactive 认证
+    app_handle: Option<AppHandle>,
+    lost_sender: mpsc::UnboundedSender<DisconnectCase>,
+}
+
+impl SshConnector {
+    async fn connect(&self) -> Result<SshChain, LogicError> {
+        let mut hops = self.hops.clone().into_iter();
+        let first_hop = hops.next().unwrap();
+        let stream = match &self.proxy {
+            Some(proxy) => {
+                proxy_tunnel::connect(proxy, &first_hop.host, first_hop.port, self.connect_timeout)
+                    .await?
             }
+            None => timeout(
+                self.connect_timeout,
+                TcpStream::connect((first_hop.host.as_str(), first_hop.port)),
+            )
+            .await
+            .map_err(|_| {
+                io::Error::new(ErrorKind::ConnectionAborted, "ssh connection timeout")
+            })??,
+        };
+        let mut session = SshTunnel::open_session(
+            Arc::clone(&self.config),
+            stream,
+            first_hop,
+            self.app_handle.clone(),
+            self.lost_sender.clone(),
+        )
+        .await?;
+
+        //  在上一跳的会话中打开到下一跳的 direct-tcpip 通道，并在通道上建立下一跳的会话
+        let mut jump_sessions = vec![];
+        for hop in hops {
+            let channel = timeout(
+                self.connect_timeout,
+                session.channel_open_direct_tcpip(
+                    hop.host.clone(),
+                    hop.port as u32,
+                    "127.0.0.1",
+                    0,
+                ),
+            )
+            .await
+            .map_err(|_| io::Error::new(ErrorKind::ConnectionAborted, "ssh connection timeout"))?
+            .map_err(|e| {
+                LogicError::MsgError(format!(
+                    "Failed to open channel to jump host {}:{}: {}",
+                    hop.host, hop.port, e
+                ))
+            })?;
+
+            let next_session = SshTunnel::open_session(
+                Arc::clone(&self.config),
+                channel.into_stream(),
+                hop,
+                self.app_handle.clone(),
+                self.lost_sender.clone(),
+            )
+            .await?;
+            jump_sessions.push(session);
+            session = next_session;
         }
 
+        Ok(SshChain {
+            session: Arc::new(session),
+            jump_sessions,
+        })
+    }
+}
+
+impl SshTunnel {
+    pub async fn new(
+        ssh_config: ConnectionSsh,
+        forward_host: String,
+        forward_port: u16,
+        proxy: Option<ConnectionProxy>,
+        handler: EtcdConnectorHandler,
+    ) -> Result<Self, LogicError> {
+        let ssh_config = crate::ssh::ssh_config::resolve(ssh_config)?;
+
+        let mut hops = ssh_config.jump_hosts;
+        hops.push(SshJumpHost {
+            host: ssh_config.host,
+            port: ssh_config.port,
+            user: ssh_config.user,
+            identity: ssh_config.identity,
+        });
+
+        let ssh_simple_info = hops
+            .iter()
+            .map(|hop| format!("{}@{}:{}", hop.user, hop.host, hop.port))
+            .collect::<Vec<String>>()
+            .join(" -> ");
+
+        //  相同主机、用户与认证信息的连接复用同一个 ssh 会话
+        let key = session_pool::session_key(&hops, &proxy);
+        let mut connected = false;
+        let shared = session_pool::get_or_connect(&key, || {
+            connected = true;
+            SharedSshSession::connect(
+                key.clone(),
+                ssh_simple_info.clone(),
+                hops,
+                proxy,
+                handler.clone(),
+            )
+        })
+        .await?;
+        if !connected {
+            info!("reuse shared ssh session | {}", ssh_simple_info);
+        }
+        shared.register(handler.clone());
+        let session_id = handler.session_id();
+
+        let state = Arc::new(TunnelState {
+            session: Arc::clone(&shared.state),
+            counters: TunnelCounters::default(),
+        });
+
         let listener = TcpListener::bind("127.0.0.1:0").await?;
         let proxy_port = listener.local_addr()?.port();
 
@@ -130,22 +593,233 @@ impl SshTunnel {
         );
 
         Self::handle_tcp_proxy(
-            ssh_simple_info,
+            ssh_simple_info.clone(),
             listener,
-            Arc::new(session),
-            forward_host,
+            Arc::clone(&state),
+            forward_host.clone(),
             forward_port,
             rcv_abort,
-            handler,
+            Some(handler),
         )
         .await?;
 
+        let mut forwards = HashMap::new();
+        forwards.insert((forward_host, forward_port), proxy_port);
+
         Ok(SshTunnel {
             proxy_port,
             send_abort,
+            shared,
+            state,
+            session_id,
+            ssh_simple_info,
+            forwards,
         })
     }
 
+    /// 在 `stream` 上建立 ssh 会话并完成认证，`stream` 可以是 tcp 连接或上一跳的通道
+    async fn open_session<S>(
+        config: Arc<client::Config>,
+        stream: S,
+        hop: SshJumpHost,
+        app_handle: Option<AppHandle>,
+        lost_sender: mpsc::UnboundedSender<DisconnectCase>,
+    ) -> Result<Handle<SshClientHandler>, LogicError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let host_key_rejection = Arc::new(Mutex::new(None));
+        let ssh_handler = SshClientHandler::new(
+            hop.user.clone(),
+            hop.host.clone(),
+            hop.port,
+            app_handle.clone(),
+            Arc::clone(&host_key_rejection),
+            lost_sender,
+        );
+
+        let mut session = match client::connect_stream(config, stream, ssh_handler).await {
+            Ok(session) => session,
+            Err(e) => {
+                let rejection = host_key_rejection.lock().ok().and_then(|mut r| r.take());
+                return Err(match rejection {
+                    Some(HostKeyRejection::Changed(info)) => LogicError::SshHostKeyChanged(info),
+                    Some(HostKeyRejection::NotTrusted(info)) => LogicError::MsgError(format!(
+                        "The ssh host key of {}:{} was not trusted",
+                        info.host, info.port
+                    )),
+                    None => LogicError::from(e),
+                });
+            }
+        };
+
+        let mut progress = AuthProgress::new(hop.identity.as_ref());
+        let (mut method, mut result) = Self::authenticate_identity(
+            &mut session,
+            &hop.user,
+            hop.identity.as_ref(),
+            &mut progress,
+        )
+        .await?;
+        //  服务器要求多步认证时按 remaining_methods 继续，例如公钥认证后再进行密码或 keyboard-interactive 认证
+        loop {
+            let next = match &result {
+                AuthResult::Failure {
+                    remaining_methods,
+                    partial_success,
+                } => {
+                    progress.record(method, *partial_success);
+                    progress.next(remaining_methods)
+                }
+                _ => return Ok(session),
+            };
+            method = match next {
+                Some(method) => method,
+                None => {
+                    Self::handle_auth_result(result)?;
+                    return Ok(session);
+                }
+            };
+            debug!(
+                "Ssh {}@{}:{} continue with {:?}",
+                hop.user, hop.host, hop.port, method
+            );
+
+            result = match (&method, &hop.identity) {
+                (MethodKind::KeyboardInteractive, _) => {
+                    interactive::authenticate(
+                        &mut session,
+                        app_handle.as_ref(),
+                        &hop.host,
+                        hop.port,
+                        &hop.user,
+                    )
+                    .await?
+                }
+                (MethodKind::Password, Some(identity)) => {
+                    let password = identity.password.clone().unwrap_or_default();
+                    session.authenticate_password(&hop.user, password).await?
+                }
+                (_, Some(identity)) => {
+                    Self::authenticate_publickey(&mut session, &hop.user, identity).await?
+                }
+                (_, None) => session.authenticate_none(&hop.user).await?,
+            };
+        }
+    }
+
+    /// 使用连接配置的认证信息进行第一步认证，没有认证信息时以 none 方式获取服务器支持的认证方式
+    ///
+    /// 公钥认证出错且配置了密码时改用密码认证，返回最后使用的认证方式与结果
+    async fn authenticate_identity(
+        session: &mut Handle<SshClientHandler>,
+        user: &str,
+        identity: Option<&SshIdentity>,
+        progress: &mut AuthProgress,
+    ) -> Result<(MethodKind, AuthResult), LogicError> {
+        let identity = match identity {
+            Some(identity) => identity,
+            None => return Ok((MethodKind::None, session.authenticate_none(user).await?)),
+        };
+
+        if identity.agent || identity.key.is_some() {
+            match Self::authenticate_publickey(session, user, identity).await {
+                Err(e) if identity.password.is_some() => {
+                    warn!(
+                        "Ssh public key authentication failed, fallback to password: {:?}",
+                        e
+                    );
+                    progress.record(MethodKind::PublicKey, false);
+                }
+                result => return Ok((MethodKind::PublicKey, result?)),
+            }
+        }
+
+        match &identity.password {
+            Some(password) => Ok((
+                MethodKind::Password,
+                session.authenticate_password(user, password).await?,
+            )),
+            None => Ok((MethodKind::None, session.authenticate_none(user).await?)),
+        }
+    }
+
+    /// 公钥认证，优先使用 ssh-agent，失败时再使用配置的私钥
+    async fn authenticate_publickey(
+        session: &mut Handle<SshClientHandler>,
+        user: &str,
+        identity: &SshIdentity,
+    ) -> Result<AuthResult, LogicError> {
+        if identity.agent {
+            let has_fallback = identity.key.is_some();
+            match agent::authenticate(session, user).await {
+                Ok(res) if !has_fallback || agent::is_accepted(&res) => return Ok(res),
+                Err(e) if !has_fallback => return Err(e),
+                result => warn!(
+                    "Ssh agent authentication failed, fallback to private key: {:?}",
+                    result
+                ),
+            }
+        }
+
+        let key = match &identity.key {
+            Some(key) => key,
+            None => return Ok(session.authenticate_none(user).await?),
+        };
+        let hash_alg = key
+            .hash_algorithm
+            .clone()
+            .map(|s| HashAlg::new(s.as_str()).unwrap());
+
+        match decode_secret_key(
+            String::from_utf8(key.key.clone())?.as_str(),
+            key.passphrase.as_deref(),
+        ) {
+            Ok(key_pair) => {
+                let private_key = PrivateKeyWithHashAlg::new(Arc::new(key_pair), hash_alg);
+                Ok(session.authenticate_publickey(user, private_key).await?)
+            }
+            Err(e) => {
+                error!("decode ssh key failed: {}", e);
+                Err(LogicError::IoError(Error::new(
+                    ErrorKind::ConnectionAborted,
+                    "Failed to parse ssh private key",
+                )))
+            }
+        }
+    }
+
+    /// 复用当前的 ssh 会话转发另一个远程地址，返回本地端口，同一地址只会创建一次转发
+    ///
+    /// 额外转发的失败不会影响会话状态，只记录日志
+    pub async fn forward(&mut self, host: String, port: u16) -> Result<u16, LogicError> {
+        if let Some(local_port) = self.forwards.get(&(host.clone(), port)) {
+            return Ok(*local_port);
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let local_port = listener.local_addr()?.port();
+
+        info!(
+            "{} create ssh forward to {}:{}, local port is {}",
+            self.ssh_simple_info, host, port, local_port
+        );
+
+        Self::handle_tcp_proxy(
+            self.ssh_simple_info.clone(),
+            listener,
+            Arc::clone(&self.state),
+            host.clone(),
+            port,
+            self.send_abort.subscribe(),
+            None,
+        )
+        .await?;
+
+        self.forwards.insert((host, port), local_port);
+        Ok(local_port)
+    }
+
     fn handle_auth_result(res: AuthResult) -> Result<(), LogicError> {
         match res {
             client::AuthResult::Failure {
@@ -169,14 +843,35 @@ impl SshTunnel {
         self.proxy_port
     }
 
+    /// 隧道的流量统计与诊断信息
+    pub fn stats(&self) -> SshTunnelStats {
+        let counters = &self.state.counters;
+        let session = &self.shared.state;
+        let rtt = session.keepalive_rtt_millis.load(Ordering::Relaxed);
+        SshTunnelStats {
+            ssh_info: self.ssh_simple_info.clone(),
+            local_port: self.proxy_port,
+            forwards: self.forwards.len(),
+            bytes_in: counters.bytes_in.load(Ordering::Relaxed),
+            bytes_out: counters.bytes_out.load(Ordering::Relaxed),
+            active_channels: counters.active_channels.load(Ordering::Relaxed),
+            total_channels: counters.total_channels.load(Ordering::Relaxed),
+            channel_open_failures: counters.channel_open_failures.load(Ordering::Relaxed),
+            recent_failures: counters.recent_failures(),
+            keepalive_rtt_millis: if rtt > 0 { Some(rtt) } else { None },
+            reconnects: session.reconnects.load(Ordering::Relaxed),
+            shared_connections: session.handlers.lock().unwrap().len(),
+        }
+    }
+
     async fn handle_tcp_proxy(
         ssh_simple_info: String,
         listener: TcpListener,
-        ssh_session: Arc<Handle<SshClientHandler>>,
-        forward_host: &'static str,
+        state: Arc<TunnelState>,
+        forward_host: String,
         forward_port: u16,
         rcv_abort: watch::Receiver<()>,
-        handler: EtcdConnectorHandler,
+        handler: Option<EtcdConnectorHandler>,
     ) -> Result<(), LogicError> {
         let (sender, receiver) = oneshot::channel();
         tokio::spawn(async move {
@@ -197,7 +892,8 @@ impl SshTunnel {
                     match accept_result {
                         Ok((stream, addr)) => {
                             let rcv_abort3 = rcv_abort2.clone();
-                            let ssh_session = Arc::clone(&ssh_session);
+                            let ssh_session = Arc::clone(&state.session.chain.read().await.session);
+                            let state = Arc::clone(&state);
                             let ssh_simple_info3 = Arc::clone(&ssh_simple_info2);
 
                             debug!("ssh proxy stream task started, chain: local({}) -> local(127.0.0.1:{}) -> ssh({}) -> remote({}:{})",
@@ -205,7 +901,7 @@ impl SshTunnel {
 
                             let direct_channel_result = ssh_session
                                 .channel_open_direct_tcpip(
-                                    forward_host,
+                                    forward_host.clone(),
                                     forward_port as u32,
                                     "127.0.0.1",
                                     57128,
@@ -214,28 +910,57 @@ impl SshTunnel {
 
                             match direct_channel_result {
                                 Ok(channel) => {
+                                    let forward_host = forward_host.clone();
                                     tokio::spawn(async move {
+                                        let counters = &state.counters;
+                                        counters.channel_opened();
                                         let _ = Self::start_ssh_tunnel(
                                             channel,
                                             stream,
                                             rcv_abort3,
                                             ssh_simple_info3,
+                                            counters,
+                                            (&forward_host, forward_port),
                                         )
                                         .await;
+                                        counters.channel_closed();
                                     });
                                 }
+                                //  会话已断开时交给重连任务处理，丢弃本次连接，客户端会自行重试
+                                Err(e) if ssh_session.is_closed() => {
+                                    warn!("ssh session is closed, waiting for reconnect: {e}");
+                                    state.counters.channel_open_failed(
+                                        &forward_host,
+                                        forward_port,
+                                        e.to_string(),
+                                    );
+                                    let _ = state
+                                        .session
+                                        .lost_sender
+                                        .send(DisconnectCase::SshDisconnected(e.to_string()));
+                                    continue;
+                                }
                                 Err(e) => {
                                     error!("Unable to forward messages via ssh: {e}");
-                                    handler.disconnected(DisconnectCase::SshTunnelError(
+                                    state.counters.channel_open_failed(
+                                        &forward_host,
+                                        forward_port,
                                         e.to_string(),
-                                    ));
+                                    );
+                                    if let Some(handler) = &handler {
+                                        handler.disconnected(DisconnectCase::SshTunnelError(
+                                            e.to_string(),
+                                        ));
+                                    }
                                     continue;
                                 }
                             }
                         }
                         Err(e) => {
                             warn!("ssh proxy listener error: {e}");
-                            handler.disconnected(DisconnectCase::SshTunnelError(e.to_string()));
+                            if let Some(handler) = &handler {
+                                handler.disconnected(DisconnectCase::SshTunnelError(e.to_string()));
+                            }
                             break;
                         }
                     }
@@ -263,6 +988,8 @@ impl SshTunnel {
         mut stream: TcpStream,     // TCP 客户端
         mut rcv_abort: watch::Receiver<()>,
         ssh_simple_info: Arc<String>,
+        counters: &TunnelCounters,
+        target: (&str, u16),
     ) -> Result<(), LogicError> {
         let (mut socket_reader, mut socket_writer) = stream.split();
 
@@ -296,6 +1023,7 @@ impl SshTunnel {
                                 );
                                 break;
                             }
+                            counters.bytes_out.fetch_add(n as u64, Ordering::Relaxed);
                         }
                     }
                     Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
@@ -324,6 +1052,14 @@ impl SshTunnel {
                             log::error!("tcp write error: {:?} | {}", e, ssh_simple_info);
                             break;
                         }
+                        counters
+                            .bytes_in
+                            .fetch_add(data.len() as u64, Ordering::Relaxed);
+                    }
+                    //  只记录在该通道所属的隧道中，不影响共享会话上的其他连接
+                    Some(ChannelMsg::Failure) => {
+                        warn!("ssh channel request failed | {}", ssh_simple_info);
+                        counters.channel_request_failed(target.0, target.1);
                     }
                     _ => {}
                 }
@@ -331,16 +1067,25 @@ impl SshTunnel {
         };
 
         // 并发执行两端
-        tokio::select! {
+        let aborted = tokio::select! {
             _ = to_ssh => {
-                debug!("TCP → SSH stream proxy stopped | {}", ssh_simple_info)
+                debug!("TCP → SSH stream proxy stopped | {}", ssh_simple_info);
+                false
             },
             _ = to_tcp => {
-                debug!("SSH → TCP stream proxy stopped | {}", ssh_simple_info)
+                debug!("SSH → TCP stream proxy stopped | {}", ssh_simple_info);
+                false
             },
             _ = rcv_abort.changed() => {
-                debug!("ssh proxy stream task received abort event | {}", ssh_simple_info)
+                debug!("ssh proxy stream task received abort event | {}", ssh_simple_info);
+                true
             }
+        };
+
+        //  隧道关闭时主动关闭通道与 tcp 连接，避免共享的 ssh 会话上残留通道
+        if aborted {
+            let _ = channel_writer.close().await;
+            let _ = socket_writer.shutdown().await;
         }
 
         log::info!("ssh tunnel finished. | {}", ssh_simple_info);
@@ -349,6 +1094,13 @@ impl SshTunnel {
     }
 }
 
+#[async_trait]
+impl TcpForwarder for SshTunnel {
+    async fn forward(&mut self, host: String, port: u16) -> Result<u16, LogicError> {
+        SshTunnel::forward(self, host, port).await
+    }
+}
+
 impl Drop for SshTunnel {
     fn drop(&mut self) {
         match self.send_abort.send(()) {
@@ -359,6 +1111,7 @@ impl Drop for SshTunnel {
                 warn!("ssh send abort error: {e}")
             }
         }
+        self.shared.unregister(self.session_id);
         debug!("drop ssh tunnel");
     }
 }
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ConnectionUser {
     pub username: String,
     pub password: String,
@@ -21,6 +21,35 @@ pub struct ConnectionTls {
     pub identity: Option<TlsIdentity>,
 }
 
+/// 从 PEM 中解析出的证书信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct TlsCertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    /// 例如 `DNS:etcd.local`、`IP:127.0.0.1`
+    pub subject_alt_names: Vec<String>,
+    pub serial_number: String,
+    /// 毫秒时间戳
+    pub not_before: i64,
+    /// 毫秒时间戳
+    pub not_after: i64,
+    pub is_ca: bool,
+    /// DER 的 SHA-256 指纹
+    pub fingerprint: String,
+}
+
+/// 连接 tls 配置中证书的检查结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct TlsInspection {
+    pub ca: Vec<TlsCertificateInfo>,
+    /// 客户端证书链，第一个为客户端证书
+    pub identity: Vec<TlsCertificateInfo>,
+    /// 客户端私钥与证书是否匹配，未配置客户端证书或无法判断时为空
+    pub key_matches: Option<bool>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SshPrivateKey {
@@ -35,6 +64,9 @@ pub struct SshPrivateKey {
 pub struct SshIdentity {
     pub password: Option<String>,
     pub key: Option<SshPrivateKey>,
+    /// 使用本地运行的 ssh-agent 认证，失败时再尝试 key 与 password
+    #[serde(default)]
+    pub agent: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -43,6 +75,74 @@ pub struct ConnectionSsh {
     pub port: u16,
     pub user: String,
     pub identity: Option<SshIdentity>,
+    /// 跳板机，按顺序依次连接，最后经过当前配置的主机转发到 etcd
+    #[serde(default, rename = "jumpHosts")]
+    pub jump_hosts: Vec<SshJumpHost>,
+    /// `~/.ssh/config` 中的主机别名，设置后从中读取主机、端口、用户、认证文件与跳板机
+    #[serde(default, rename = "configHost")]
+    pub config_host: Option<String>,
+}
+
+/// ssh 跳板机，每一跳使用各自的认证信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SshJumpHost {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub identity: Option<SshIdentity>,
+}
+
+/// ssh 服务器的公钥
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SshHostKeyInfo {
+    pub host: String,
+    pub port: u16,
+    pub algorithm: String,
+    /// SHA256 指纹，与 `ssh-keygen -l` 的输出一致
+    pub fingerprint: String,
+}
+
+/// 代理协议
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all="camelCase")]
+pub enum ProxyProtocol {
+    /// HTTP CONNECT
+    Http,
+    Socks5,
+}
+
+/// HTTP CONNECT 或 SOCKS5 代理，user 为代理的认证信息
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all="camelCase")]
+pub struct ConnectionProxy {
+    pub protocol: ProxyProtocol,
+    pub host: String,
+    pub port: u16,
+    pub user: Option<ConnectionUser>,
+}
+
+/// 通过 Kubernetes API server 的 portforward 子资源连接 pod 中的 etcd，远程端口为连接配置的 port
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all="camelCase")]
+pub struct ConnectionK8s {
+    /// kubeconfig 文件路径，为空时使用 `KUBECONFIG` 环境变量或 `~/.kube/config`
+    pub kubeconfig: Option<String>,
+    /// 使用的 context，为空时使用 kubeconfig 的 current-context
+    pub context: Option<String>,
+    /// pod 所在的命名空间，为空时使用 context 中的命名空间
+    pub namespace: Option<String>,
+    pub pod: String,
+}
+
+/// 客户端与 etcd 之间的传输方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all="camelCase")]
+pub enum ConnectionTransport {
+    #[default]
+    Grpc,
+    /// 通过 grpc-gateway 的 http json 接口访问，用于只开放了 gateway 的环境，只支持 kv、lease、watch 与认证
+    Gateway,
 }
 
 /// 连接必要数据
@@ -51,16 +151,43 @@ pub struct ConnectionSsh {
 pub struct Connection {
     pub host: String,
     pub port: u16,
+    /// 其他节点地址（`host:port` 或 url），与 host、port 一起作为客户端的连接地址
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// 连接后是否从成员列表中发现其他成员的客户端地址
+    #[serde(default)]
+    pub discover_endpoints: bool,
+    /// DNS SRV 发现域名，设置后通过 `_etcd-client-ssl._tcp` 与 `_etcd-client._tcp` 记录获取节点
+    #[serde(default)]
+    pub discovery_srv: Option<String>,
+    /// SRV 服务名后缀，对应 `--discovery-srv-name`
+    #[serde(default)]
+    pub discovery_srv_name: Option<String>,
+    /// 传输方式，默认直连 gRPC
+    #[serde(default)]
+    pub transport: ConnectionTransport,
+    /// grpc-gateway 的路径前缀，为空时使用 `/v3`
+    #[serde(default)]
+    pub gateway_prefix: Option<String>,
     pub namespace: Option<String>,
     pub user: Option<ConnectionUser>,
     pub tls: Option<ConnectionTls>,
     pub ssh: Option<ConnectionSsh>,
+    /// 通过代理连接，同时设置 ssh 时只有 ssh 连接经过代理
+    #[serde(default)]
+    pub proxy: Option<ConnectionProxy>,
+    /// 通过 Kubernetes 端口转发连接，不能与 ssh 同时使用，设置代理时只有 API server 的连接经过代理
+    #[serde(default)]
+    pub k8s: Option<ConnectionK8s>,
     /// KV分页读取
     #[serde(default = "default_query_pagination")]
     pub query_pagination: bool,
     /// KV分页获取每页大小
     #[serde(default = "default_query_pagination_size")]
     pub query_pagination_size: u32,
+    /// 指标地址，对应 `--listen-metrics-urls`，为空时使用客户端地址
+    #[serde(default)]
+    pub metrics_url: Option<String>,
 }
 
 /// 连接信息
@@ -76,6 +203,12 @@ pub struct ConnectionInfo {
     //  key监控列表
     #[serde(default = "default_key_monitor_list")]
     pub key_monitor_list: Vec<KeyMonitorConfig>,
+    //  自动压缩策略
+    #[serde(default)]
+    pub compaction_policy: Option<CompactionPolicy>,
+    //  定时快照备份
+    #[serde(default)]
+    pub snapshot_schedule: Option<SnapshotSchedule>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -91,6 +224,9 @@ pub struct SessionData {
     pub read_all_keys: bool,
     pub query_pagination: bool,
     pub query_pagination_size: u32,
+    pub compaction_policy: Option<CompactionPolicy>,
+    /// 实际连接的节点地址，包含 SRV 与成员列表发现的节点
+    pub endpoints: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -108,6 +244,30 @@ pub struct KeyMonitorConfig {
     pub paused: bool,
 }
 
+/// 自动压缩的版本保留方式
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub enum CompactionRetention {
+    /// 保留最近的N个版本
+    Revision(i64),
+    /// 保留最近N分钟内的版本
+    Period(u64),
+}
+
+/// 自动压缩策略，会话打开期间按周期执行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct CompactionPolicy {
+    pub enabled: bool,
+    pub retention: CompactionRetention,
+    /// 执行压缩的间隔分钟数
+    #[serde(default = "default_compaction_interval_minutes")]
+    pub interval_minutes: u64,
+    /// 是否等待压缩在物理存储上生效
+    #[serde(default)]
+    pub physical: bool,
+}
+
 unsafe impl Send for KeyMonitorConfig {
 }
 
@@ -141,10 +301,37 @@ fn default_key_monitor_paused() -> bool {
     false
 }
 
+fn default_compaction_interval_minutes() -> u64 {
+    60
+}
+
 fn default_query_pagination() -> bool {
     true
 }
 
 fn default_query_pagination_size() -> u32 {
     2000
-}
\ No newline at end of file
+}
+
+/// 定时快照备份配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotSchedule {
+    pub enabled: bool,
+    /// 快照保存目录
+    pub folder: String,
+    /// 备份间隔小时数
+    #[serde(default = "default_snapshot_interval_hours")]
+    pub interval_hours: u64,
+    /// 最多保留的快照文件数
+    #[serde(default = "default_snapshot_keep")]
+    pub keep: usize,
+}
+
+fn default_snapshot_interval_hours() -> u64 {
+    24
+}
+
+fn default_snapshot_keep() -> usize {
+    7
+}
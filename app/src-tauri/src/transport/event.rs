@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::transport::connection::{SshHostKeyInfo, TlsCertificateInfo};
 use crate::transport::kv::RenameAction;
+use crate::transport::maintenance::SerializableMemberDefragment;
 
 use super::{connection::KeyMonitorConfig, kv::SerializableKeyValue};
 
@@ -11,6 +13,66 @@ pub enum DisconnectCase {
     SshChannelEof,
     SshDisconnected(String),
     SshTunnelError(String),
+    /// 服务器公钥与 known_hosts 中记录的不一致，可能存在中间人攻击
+    SshHostKeyChanged(SshHostKeyInfo),
+}
+
+/// ssh 隧道的重连状态
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all="camelCase")]
+pub enum SshTunnelState {
+    /// 正在进行第 attempt 次重连，最多 max 次
+    Reconnecting { attempt: u32, max: u32 },
+    /// 重连成功，本地端口不变
+    Restored,
+    /// 达到最大重连次数，会话随后会被断开
+    Failed,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct SshTunnelStateEvent {
+    pub session_id: i32,
+    pub state: SshTunnelState,
+}
+
+/// 客户端证书即将过期或已过期
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct TlsCertificateExpiringEvent {
+    pub session_id: i32,
+    pub certificate: TlsCertificateInfo,
+    /// 剩余天数，已过期时为负数
+    pub days_left: i64,
+}
+
+/// 首次连接 ssh 服务器时请求用户确认公钥
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct SshHostKeyPromptEvent {
+    pub request_id: u32,
+    pub host_key: SshHostKeyInfo,
+}
+
+/// ssh keyboard-interactive 认证中服务器要求用户输入的一项，echo 为 false 时输入内容应隐藏
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct SshAuthPrompt {
+    pub prompt: String,
+    pub echo: bool,
+}
+
+/// ssh keyboard-interactive 认证请求用户输入，例如一次性验证码
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all="camelCase")]
+pub struct SshAuthPromptEvent {
+    pub request_id: u32,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<SshAuthPrompt>,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -81,4 +143,58 @@ pub struct KVBatchImportAndExportEvent {
     pub success: bool,
     pub key: Option<Vec<u8>>,
     pub failed_msg: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum NospaceRecoverStep {
+    Compact,
+    Defragment,
+    Disarm,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct NospaceRecoverEvent {
+    pub step: NospaceRecoverStep,
+    pub member_id: Option<String>,
+    pub success: bool,
+    pub failed_msg: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MemberDefragmentEvent {
+    pub member_id: String,
+    pub leader: bool,
+    pub result: Option<SerializableMemberDefragment>,
+    pub success: bool,
+    pub failed_msg: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum SnapshotRestoreStep {
+    VerifyHash,
+    RestoreDb,
+    WriteWal,
+    WriteSnap,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotRestoreEvent {
+    pub step: SnapshotRestoreStep,
+    /// 正在恢复的 bucket，仅在 RestoreDb 步骤中有值
+    pub bucket: Option<String>,
+    /// 当前 bucket 已写入的 key 数量
+    pub keys: u64,
+}
+
+impl SnapshotRestoreEvent {
+    pub fn new(step: SnapshotRestoreStep) -> Self {
+        SnapshotRestoreEvent {
+            step,
+            bucket: None,
+            keys: 0,
+        }
+    }
 }
\ No newline at end of file
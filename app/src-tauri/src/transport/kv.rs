@@ -161,6 +161,26 @@ impl PutStrategy {
     }
 }
 
+/// etcd v2 存储的节点，字段与 v2 接口返回的 json 一致
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct V2Node {
+    pub key: String,
+    #[serde(default)]
+    pub dir: bool,
+    pub value: Option<String>,
+    //  剩余存活秒数，未设置过期时间时为空
+    pub ttl: Option<i64>,
+    pub expiration: Option<String>,
+    #[serde(default)]
+    pub created_index: u64,
+    #[serde(default)]
+    pub modified_index: u64,
+    //  目录的子节点，按 key 排序
+    #[serde(default)]
+    pub nodes: Vec<V2Node>,
+}
+
 /// 传入 A B 两个字节数组，找出哪个是另一个的前缀，如果没有前缀则返回 [`None`]
 pub fn get_prefix_one<V: AsRef<Vec<u8>>>(one: V, two: V) -> Option<V> {
     let one_vec = one.as_ref();
@@ -20,6 +20,14 @@ pub struct SerializableClusterMember {
     pub alarm_type: i32
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SerializableAlarm {
+    pub member_id: String,
+    /// etcd_client::AlarmType
+    pub alarm_type: i32
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all="camelCase")]
 pub struct SerializableClusterStatus {
@@ -33,6 +41,16 @@ pub struct SerializableClusterStatus {
     pub errors: Vec<String>
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SerializableMemberDefragment {
+    pub member_id: String,
+    pub db_size_before: i64,
+    pub db_size_used_before: i64,
+    pub db_size_after: i64,
+    pub db_size_used_after: i64
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all="camelCase")]
 pub struct SnapshotState {
@@ -65,4 +83,155 @@ pub struct SnapshotInfo {
 pub struct SnapshotStateEvent {
     pub id: i32,
     pub state: SnapshotState
+}
+
+/// 快照元数据，保存在快照文件旁边
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotMetadata {
+    pub cluster_id: String,
+    pub member_id: String,
+    pub revision: i64,
+    /// 快照文件大小，包含末尾的 sha256
+    pub size: u64,
+    /// 快照内容的 sha256
+    pub sha256: String,
+    /// 快照时间戳，单位毫秒
+    pub time: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotVerifyResult {
+    pub size: u64,
+    pub hash_valid: bool,
+    pub failed_msg: Option<String>,
+    pub metadata: Option<SnapshotMetadata>,
+    /// 元数据中记录的大小和 hash 是否与文件一致，没有元数据时为 None
+    pub metadata_matched: Option<bool>,
+}
+
+/// 离线快照会话信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct OfflineSessionData {
+    pub id: i32,
+    pub filepath: String,
+    /// 快照中的最新版本号
+    pub revision: i64,
+    /// 快照中已压缩到的版本号
+    pub compact_revision: i64,
+    pub key_count: usize,
+}
+
+/// 一次定时快照备份的记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotBackupRecord {
+    pub filepath: String,
+    /// 开始时间戳，单位毫秒
+    pub time: u64,
+    /// 执行耗时，单位毫秒
+    pub duration: u64,
+    pub success: bool,
+    pub failed_msg: Option<String>,
+}
+
+/// 定时快照备份的状态，保存在数据目录中，重启后据此恢复调度
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotScheduleState {
+    /// 下一次执行的时间戳，单位毫秒
+    pub next_run: u64,
+    #[serde(default)]
+    pub history: Vec<SnapshotBackupRecord>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub enum MaintenanceAction {
+    AutoCompact,
+}
+
+/// 本地维护日志
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MaintenanceLog {
+    pub action: MaintenanceAction,
+    pub revision: i64,
+    /// 执行耗时，单位毫秒
+    pub duration: u64,
+    /// 执行时间戳，单位毫秒
+    pub time: u64,
+    pub success: bool,
+    pub failed_msg: Option<String>,
+}
+
+/// 从快照恢复数据目录的参数，与 `etcdutl snapshot restore` 的参数对应
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotRestoreOptions {
+    pub snapshot_path: String,
+    /// 恢复后的数据目录，目录下不能已存在 member 目录
+    pub data_dir: String,
+    /// 新集群的 token，对应 `--initial-cluster-token`
+    pub cluster_name: String,
+    /// 当前成员名，对应 `--name`
+    pub member_name: String,
+    /// 当前成员的 peer urls，对应 `--initial-advertise-peer-urls`
+    pub peer_urls: Vec<String>,
+    /// 格式为 `name=url,name=url`，为空时集群只包含当前成员
+    pub initial_cluster: Option<String>,
+    #[serde(default)]
+    pub skip_hash_check: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SnapshotRestoreResult {
+    pub data_dir: String,
+    pub cluster_id: String,
+    pub member_id: String,
+}
+
+/// 延迟统计，单位毫秒
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+/// 单个成员的健康检查结果，健康的标准参考 `etcdctl endpoint health`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MemberHealth {
+    pub member_id: String,
+    pub name: String,
+    pub client_urls: Vec<String>,
+    pub healthy: bool,
+    /// 线性一致读的延迟
+    pub read_latency: Option<LatencyStats>,
+    /// status 请求的延迟
+    pub status_latency: Option<LatencyStats>,
+    pub failed_probes: usize,
+    pub leader: Option<String>,
+    pub raft_index: Option<u64>,
+    pub db_size: Option<i64>,
+    /// 去重后的错误信息
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct ClusterHealth {
+    /// 是否经过 ssh 隧道，经过隧道时延迟包含隧道的开销
+    pub ssh_tunnel: bool,
+    pub probes: u32,
+    pub members: Vec<MemberHealth>,
 }
\ No newline at end of file
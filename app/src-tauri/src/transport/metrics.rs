@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+    Untyped,
+}
+
+/// 同名指标的集合，对应 Prometheus 文本格式中 `# TYPE` 声明的一组指标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MetricFamily {
+    pub name: String,
+    pub help: Option<String>,
+    pub metric_type: MetricType,
+    pub metrics: Vec<Metric>,
+}
+
+/// 一组标签对应的指标值，histogram 与 summary 会将 `_bucket`、`_sum`、`_count` 合并到一起
+///
+/// 注意 json 无法表示 `NaN` 与 `±Inf`，序列化后为 null
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct Metric {
+    pub labels: BTreeMap<String, String>,
+    /// counter、gauge、untyped 的值
+    pub value: Option<f64>,
+    pub histogram: Option<HistogramValue>,
+    pub summary: Option<SummaryValue>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct HistogramValue {
+    pub buckets: Vec<HistogramBucket>,
+    pub sum: f64,
+    pub count: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct HistogramBucket {
+    /// 桶的上界，`+Inf` 桶序列化后为 null
+    pub upper_bound: f64,
+    /// 累计数量
+    pub count: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all="camelCase")]
+pub struct SummaryValue {
+    pub quantiles: Vec<SummaryQuantile>,
+    pub sum: f64,
+    pub count: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SummaryQuantile {
+    pub quantile: f64,
+    pub value: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MetricPoint {
+    /// 采样时间戳，单位毫秒
+    pub time: u64,
+    pub value: f64,
+}
+
+/// 一个指标在一段时间内的采样值
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MetricSeries {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub points: VecDeque<MetricPoint>,
+}
+
+/// 单个成员的指标数据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct MemberMetrics {
+    pub member_id: String,
+    pub name: String,
+    pub url: String,
+    pub success: bool,
+    pub families: Vec<MetricFamily>,
+    pub failed_msg: Option<String>,
+}
+
+/// ssh 隧道的流量与诊断统计，包含所有转发目标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SshTunnelStats {
+    pub ssh_info: String,
+    pub local_port: u16,
+    /// 转发目标数量，包含主转发
+    pub forwards: usize,
+    /// 从 ssh 通道返回给本地客户端的字节数
+    pub bytes_in: u64,
+    /// 从本地客户端发往 ssh 通道的字节数
+    pub bytes_out: u64,
+    pub active_channels: u64,
+    pub total_channels: u64,
+    pub channel_open_failures: u64,
+    /// 最近的通道打开失败记录，按时间先后排列
+    pub recent_failures: Vec<SshChannelFailure>,
+    /// 最近一次 keepalive 的往返时间，单位毫秒
+    pub keepalive_rtt_millis: Option<u64>,
+    pub reconnects: u64,
+    /// 共享该 ssh 会话的连接数
+    pub shared_connections: usize,
+}
+
+/// 打开 direct-tcpip 通道失败的记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all="camelCase")]
+pub struct SshChannelFailure {
+    /// 时间戳，单位毫秒
+    pub time: u64,
+    pub target: String,
+    pub reason: String,
+}
@@ -2,5 +2,6 @@ pub mod connection;
 pub mod kv;
 pub mod user;
 pub mod maintenance;
+pub mod metrics;
 pub mod settings;
 pub mod event;
\ No newline at end of file
@@ -2,6 +2,8 @@ use std::collections::VecDeque;
 
 use serde::{Deserialize, Serialize};
 
+use crate::transport::connection::ConnectionProxy;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all="camelCase")]
 pub struct SettingConfig {
@@ -43,6 +45,9 @@ pub struct SettingConfig {
     /// 更新源
     #[serde(default = "default_update_source")]
     pub update_source: UpdateSource,
+    /// 检查与下载更新时使用的代理
+    #[serde(default)]
+    pub network_proxy: Option<ConnectionProxy>,
 
     /// 使用 ctrl + w 关闭连接tab
     #[serde(default)]
@@ -56,6 +61,15 @@ pub struct SettingConfig {
     /// SSH连接超时秒数
     #[serde(default = "default_ssh_connect_timeout_seconds")]
     pub ssh_connect_timeout_seconds: u64,
+    /// 校验 ssh 服务器公钥时是否同时读取 `~/.ssh/known_hosts`
+    #[serde(default = "default_ssh_read_system_known_hosts")]
+    pub ssh_read_system_known_hosts: bool,
+    /// ssh 会话断开后的最大重连次数，为 0 时不重连
+    #[serde(default = "default_ssh_reconnect_max_attempts")]
+    pub ssh_reconnect_max_attempts: u32,
+    /// 客户端证书在该天数内过期时连接后发出提醒，为 0 时不提醒
+    #[serde(default = "default_tls_cert_expiry_warning_days")]
+    pub tls_cert_expiry_warning_days: u32,
     /// 连接存储加密密钥，bytes字符长度必须为16位
     #[serde(default = "default_connection_conf_encrypt_key")]
     pub connection_conf_encrypt_key: String,
@@ -113,6 +127,18 @@ fn default_ssh_connect_timeout_seconds() -> u64 {
     10
 }
 
+fn default_ssh_read_system_known_hosts() -> bool {
+    true
+}
+
+fn default_ssh_reconnect_max_attempts() -> u32 {
+    5
+}
+
+fn default_tls_cert_expiry_warning_days() -> u32 {
+    30
+}
+
 fn default_auto_update() -> bool {
     true
 }
@@ -140,10 +166,14 @@ impl Default for SettingConfig {
             kv_dir_rename_keys_limit: default_kv_dir_rename_keys_limit(),
             auto_update: default_auto_update(),
             update_source: default_update_source(),
+            network_proxy: None,
             close_tab_use_ctrl_w: true,
             connect_timeout_seconds: default_connect_timeout_seconds(),
             request_timeout_seconds: default_request_timeout_seconds(),
             ssh_connect_timeout_seconds: default_ssh_connect_timeout_seconds(),
+            ssh_read_system_known_hosts: default_ssh_read_system_known_hosts(),
+            ssh_reconnect_max_attempts: default_ssh_reconnect_max_attempts(),
+            tls_cert_expiry_warning_days: default_tls_cert_expiry_warning_days(),
             connection_conf_encrypt_key: default_connection_conf_encrypt_key(),
         }
     }
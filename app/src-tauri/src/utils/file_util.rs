@@ -14,6 +14,9 @@ pub static DATA_DIR: &'static str = "data";
 pub static SETTINGS_FILE: &'static str = "settings";
 pub static GLOBAL_STORE_FILE: &'static str = "store";
 pub static META_FILE: &'static str = "meta";
+pub static MAINTENANCE_LOG_DIR: &'static str = "maintenance";
+pub static SNAPSHOT_SCHEDULE_DIR: &'static str = "snapshot_schedule";
+pub static KNOWN_HOSTS_FILE: &'static str = "known_hosts";
 
 /// 创建一个临时文件，并返回该文件的全路径
 pub fn create_temp_file(data: &[u8]) -> io::Result<String> {
@@ -38,6 +41,16 @@ pub fn init() -> io::Result<()> {
         fs::create_dir_all(&config_path)?;
     }
 
+    let maintenance_log_path = get_maintenance_log_dir_path();
+    if !maintenance_log_path.exists() {
+        fs::create_dir_all(&maintenance_log_path)?;
+    }
+
+    let snapshot_schedule_path = get_snapshot_schedule_dir_path();
+    if !snapshot_schedule_path.exists() {
+        fs::create_dir_all(&snapshot_schedule_path)?;
+    }
+
     Ok(())
 }
 
@@ -48,6 +61,20 @@ pub fn get_conn_config_dir_path() -> PathBuf {
     path
 }
 
+/// 获取维护日志目录路径
+pub fn get_maintenance_log_dir_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(MAINTENANCE_LOG_DIR);
+    path
+}
+
+/// 获取定时快照状态目录路径
+pub fn get_snapshot_schedule_dir_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(SNAPSHOT_SCHEDULE_DIR);
+    path
+}
+
 /// 获取设置文件路径
 pub fn get_setting_file_path() -> PathBuf {
     let mut path = get_data_path();
@@ -62,6 +89,13 @@ pub fn get_global_store_file_path() -> PathBuf {
     path
 }
 
+/// 获取信任的 ssh 服务器公钥文件路径，格式与 OpenSSH 的 known_hosts 一致
+pub fn get_known_hosts_file_path() -> PathBuf {
+    let mut path = get_data_path();
+    path.push(KNOWN_HOSTS_FILE);
+    path
+}
+
 /// 存储数据的目录，存放配置、元数据、设置等
 pub fn get_data_path() -> PathBuf {
     let mut path = get_storage_root_path();
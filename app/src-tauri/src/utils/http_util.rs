@@ -0,0 +1,89 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use reqwest::Url;
+use tokio::net::lookup_host;
+
+use crate::error::LogicError;
+use crate::transport::connection::ConnectionTls;
+
+/// 构造访问 `url` 的 http 客户端，实际连接 `connect_host:connect_port`（经过 ssh 或代理时为本地转发端口）
+///
+/// url 中的域名（或 tls 配置的域名）作为 SNI 与证书校验的主机名，并将该域名解析到实际连接的地址。
+/// 连接地址已经确定，不使用环境变量中的代理。返回客户端与实际请求使用的 url
+pub async fn build_client(
+    url: &Url,
+    connect_host: &str,
+    connect_port: u16,
+    tls: &Option<ConnectionTls>,
+    timeout: Option<Duration>,
+) -> Result<(reqwest::Client, Url), LogicError> {
+    let mut url = url.clone();
+    let mut client_builder = reqwest::Client::builder().no_proxy();
+    if let Some(timeout) = timeout {
+        client_builder = client_builder.timeout(timeout);
+    }
+
+    let mut server_name = url
+        .host_str()
+        .map(|host| {
+            host.trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string()
+        })
+        .filter(|host| !is_ip(host));
+    if let Some(tls) = tls {
+        client_builder = client_builder.use_rustls_tls().tls_sni(true);
+        for cert in &tls.cert {
+            let certificate = reqwest::Certificate::from_pem(cert.as_slice())?;
+            client_builder = client_builder.add_root_certificate(certificate);
+        }
+
+        if let Some(identity) = &tls.identity {
+            let buf = [&identity.key[..], &identity.cert[..]].concat();
+            let id = reqwest::Identity::from_pem(buf.as_slice())?;
+            client_builder = client_builder.identity(id);
+        }
+
+        //  证书签发的域名与连接地址不一致时，以配置的域名校验证书
+        if let Some(domain) = &tls.domain {
+            if !is_ip(domain) {
+                server_name = Some(domain.clone());
+            }
+        }
+    }
+
+    url.set_port(Some(connect_port))
+        .map_err(|_| LogicError::MsgError(format!("Invalid url: {}", url)))?;
+    match server_name {
+        Some(server_name) => {
+            //  reqwest 会忽略覆盖地址中的端口，端口以 url 为准
+            let addr = lookup_host((connect_host, connect_port))
+                .await?
+                .next()
+                .ok_or(LogicError::MsgError(format!(
+                    "Failed to resolve host: {}",
+                    connect_host
+                )))?;
+            url.set_host(Some(&server_name))
+                .map_err(|e| LogicError::MsgError(format!("Invalid url: {}", e)))?;
+            client_builder = client_builder.resolve(&server_name, addr);
+        }
+        None => {
+            let host = if connect_host.contains(':') {
+                format!("[{}]", connect_host)
+            } else {
+                connect_host.to_string()
+            };
+            url.set_host(Some(&host))
+                .map_err(|e| LogicError::MsgError(format!("Invalid url: {}", e)))?;
+        }
+    }
+
+    Ok((client_builder.build()?, url))
+}
+
+fn is_ip(host: &str) -> bool {
+    IpAddr::from_str(host).is_ok()
+}
@@ -1,6 +1,9 @@
 pub mod aes_util;
 pub mod file_util;
+pub mod http_util;
 pub mod k8s_formatter;
+pub mod prometheus;
+pub mod tls_util;
 mod test;
 
 pub fn md5(content: impl AsRef<[u8]>) -> String {
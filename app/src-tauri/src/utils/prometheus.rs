@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, HashMap};
+
+use log::debug;
+
+use crate::transport::metrics::{
+    HistogramBucket, HistogramValue, Metric, MetricFamily, MetricType, SummaryQuantile,
+    SummaryValue,
+};
+
+/// 解析 Prometheus 文本格式（text/plain; version=0.0.4）的指标数据
+///
+/// 无法解析的行会被忽略，没有 `# TYPE` 声明的指标按 untyped 处理
+pub fn parse(text: &str) -> Vec<MetricFamily> {
+    let mut families: Vec<MetricFamily> = vec![];
+    let mut family_index: HashMap<String, usize> = HashMap::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut parts = comment.trim_start().splitn(3, char::is_whitespace);
+            let keyword = parts.next().unwrap_or_default();
+            let name = parts.next().unwrap_or_default();
+            let content = parts.next().unwrap_or_default().trim();
+            if name.is_empty() {
+                continue;
+            }
+            match keyword {
+                "HELP" => {
+                    let idx = get_family(&mut families, &mut family_index, name);
+                    families[idx].help = Some(unescape(content, false));
+                }
+                "TYPE" => {
+                    let idx = get_family(&mut families, &mut family_index, name);
+                    families[idx].metric_type = parse_type(content);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let sample = match parse_sample(line) {
+            Some(sample) => sample,
+            None => {
+                debug!("Ignored invalid metric line: {}", line);
+                continue;
+            }
+        };
+
+        let (family_name, suffix) = resolve_family(&families, &family_index, &sample.name);
+        let idx = get_family(&mut families, &mut family_index, family_name.as_str());
+        add_sample(&mut families[idx], suffix, sample);
+    }
+
+    families
+}
+
+struct Sample {
+    name: String,
+    labels: BTreeMap<String, String>,
+    value: f64,
+    timestamp: Option<i64>,
+}
+
+fn get_family(
+    families: &mut Vec<MetricFamily>,
+    family_index: &mut HashMap<String, usize>,
+    name: &str,
+) -> usize {
+    if let Some(idx) = family_index.get(name) {
+        return *idx;
+    }
+    families.push(MetricFamily {
+        name: name.to_string(),
+        help: None,
+        metric_type: MetricType::Untyped,
+        metrics: vec![],
+    });
+    family_index.insert(name.to_string(), families.len() - 1);
+    families.len() - 1
+}
+
+/// 找到样本所属的指标族，histogram 与 summary 的样本带有 `_bucket`、`_sum`、`_count` 后缀
+fn resolve_family(
+    families: &Vec<MetricFamily>,
+    family_index: &HashMap<String, usize>,
+    sample_name: &str,
+) -> (String, &'static str) {
+    for suffix in ["_bucket", "_sum", "_count"] {
+        if let Some(base) = sample_name.strip_suffix(suffix) {
+            if let Some(idx) = family_index.get(base) {
+                let metric_type = families[*idx].metric_type;
+                let matched = match metric_type {
+                    MetricType::Histogram => true,
+                    MetricType::Summary => suffix != "_bucket",
+                    _ => false,
+                };
+                if matched {
+                    return (base.to_string(), suffix);
+                }
+            }
+        }
+    }
+    (sample_name.to_string(), "")
+}
+
+fn add_sample(family: &mut MetricFamily, suffix: &str, mut sample: Sample) {
+    match family.metric_type {
+        MetricType::Histogram | MetricType::Summary => {
+            let bound_label = if family.metric_type == MetricType::Histogram {
+                "le"
+            } else {
+                "quantile"
+            };
+            let bound = if suffix.is_empty() || suffix == "_bucket" {
+                sample.labels.remove(bound_label)
+            } else {
+                None
+            };
+
+            let metric = match family
+                .metrics
+                .iter()
+                .position(|m| m.labels == sample.labels)
+            {
+                Some(idx) => &mut family.metrics[idx],
+                None => {
+                    family.metrics.push(Metric {
+                        labels: sample.labels,
+                        timestamp: sample.timestamp,
+                        ..Default::default()
+                    });
+                    family.metrics.last_mut().unwrap()
+                }
+            };
+
+            if family.metric_type == MetricType::Histogram {
+                let histogram = metric.histogram.get_or_insert_with(HistogramValue::default);
+                match suffix {
+                    "_sum" => histogram.sum = sample.value,
+                    "_count" => histogram.count = sample.value,
+                    _ => {
+                        if let Some(upper_bound) = bound.and_then(|b| parse_value(&b)) {
+                            histogram.buckets.push(HistogramBucket {
+                                upper_bound,
+                                count: sample.value,
+                            });
+                        }
+                    }
+                }
+            } else {
+                let summary = metric.summary.get_or_insert_with(SummaryValue::default);
+                match suffix {
+                    "_sum" => summary.sum = sample.value,
+                    "_count" => summary.count = sample.value,
+                    _ => {
+                        if let Some(quantile) = bound.and_then(|b| parse_value(&b)) {
+                            summary.quantiles.push(SummaryQuantile {
+                                quantile,
+                                value: sample.value,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        _ => family.metrics.push(Metric {
+            labels: sample.labels,
+            value: Some(sample.value),
+            timestamp: sample.timestamp,
+            ..Default::default()
+        }),
+    }
+}
+
+fn parse_type(s: &str) -> MetricType {
+    match s {
+        "counter" => MetricType::Counter,
+        "gauge" => MetricType::Gauge,
+        "histogram" => MetricType::Histogram,
+        "summary" => MetricType::Summary,
+        _ => MetricType::Untyped,
+    }
+}
+
+/// 解析样本行：`name{label="value",...} value [timestamp]`
+fn parse_sample(line: &str) -> Option<Sample> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .unwrap_or(line.len());
+    let name = &line[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut rest = &line[name_end..];
+    let mut labels = BTreeMap::new();
+    if rest.starts_with('{') {
+        rest = parse_labels(&rest[1..], &mut labels)?;
+    }
+
+    let mut parts = rest.split_whitespace();
+    let value = parse_value(parts.next()?)?;
+    let timestamp = match parts.next() {
+        Some(t) => Some(t.parse::<i64>().ok()?),
+        None => None,
+    };
+
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+        timestamp,
+    })
+}
+
+/// 解析 `{` 之后的标签，返回 `}` 之后剩余的内容
+fn parse_labels<'a>(mut s: &'a str, labels: &mut BTreeMap<String, String>) -> Option<&'a str> {
+    loop {
+        s = s.trim_start();
+        if let Some(rest) = s.strip_prefix('}') {
+            return Some(rest);
+        }
+
+        let eq = s.find('=')?;
+        let label_name = s[..eq].trim();
+        s = s[eq + 1..].trim_start().strip_prefix('"')?;
+
+        let mut end = None;
+        let mut escaped = false;
+        for (i, c) in s.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                end = Some(i);
+                break;
+            }
+        }
+        let end = end?;
+        labels.insert(label_name.to_string(), unescape(&s[..end], true));
+
+        s = s[end + 1..].trim_start();
+        if let Some(rest) = s.strip_prefix(',') {
+            s = rest;
+        }
+    }
+}
+
+fn parse_value(s: &str) -> Option<f64> {
+    match s {
+        "+Inf" => Some(f64::INFINITY),
+        "-Inf" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        _ => s.parse::<f64>().ok(),
+    }
+}
+
+/// HELP 中只转义 `\\` 与 `\n`，标签值还会转义 `\"`
+fn unescape(s: &str, quote: bool) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('\\') => result.push('\\'),
+            Some('"') if quote => result.push('"'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
@@ -0,0 +1,336 @@
+use std::io::{self, ErrorKind};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{
+    Certificate, ClientConfig, OwnedTrustAnchor, PrivateKey, RootCertStore, ServerName,
+    SignatureScheme,
+};
+use rustls_pemfile::Item;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::{FromDer, X509Certificate};
+use x509_parser::public_key::PublicKey;
+
+use crate::error::LogicError;
+use crate::transport::connection::{ConnectionTls, TlsCertificateInfo, TlsInspection};
+
+/// 校验私钥与证书是否匹配时签名的内容
+const KEY_CHECK_MESSAGE: &[u8] = b"etcd-workbench key check";
+
+const KEY_CHECK_SCHEMES: [SignatureScheme; 4] = [
+    SignatureScheme::RSA_PKCS1_SHA256,
+    SignatureScheme::ECDSA_NISTP256_SHA256,
+    SignatureScheme::ECDSA_NISTP384_SHA384,
+    SignatureScheme::ED25519,
+];
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+pub fn pem_certs(pem: &[u8]) -> Result<Vec<Certificate>, LogicError> {
+    let certs = rustls_pemfile::certs(&mut &*pem)?;
+    if certs.is_empty() {
+        return Err(LogicError::MsgError(String::from(
+            "No certificate found in PEM data",
+        )));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+pub fn pem_private_key(pem: &[u8]) -> Result<PrivateKey, LogicError> {
+    for item in rustls_pemfile::read_all(&mut &*pem)? {
+        match item {
+            Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) => {
+                return Ok(PrivateKey(key))
+            }
+            _ => {}
+        }
+    }
+    Err(LogicError::MsgError(String::from(
+        "No private key found in PEM data",
+    )))
+}
+
+pub fn certificate_info(cert: &Certificate) -> Result<TlsCertificateInfo, LogicError> {
+    let (_, x509) = X509Certificate::from_der(&cert.0)
+        .map_err(|e| LogicError::MsgError(format!("Invalid certificate: {}", e)))?;
+
+    let mut subject_alt_names = vec![];
+    if let Ok(Some(san)) = x509.subject_alternative_name() {
+        for name in &san.value.general_names {
+            match name {
+                GeneralName::DNSName(dns) => subject_alt_names.push(format!("DNS:{}", dns)),
+                GeneralName::IPAddress(ip) => {
+                    if let Some(ip) = ip_address(ip) {
+                        subject_alt_names.push(format!("IP:{}", ip))
+                    }
+                }
+                GeneralName::URI(uri) => subject_alt_names.push(format!("URI:{}", uri)),
+                GeneralName::RFC822Name(email) => {
+                    subject_alt_names.push(format!("email:{}", email))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let validity = x509.validity();
+    Ok(TlsCertificateInfo {
+        subject: x509.subject().to_string(),
+        issuer: x509.issuer().to_string(),
+        subject_alt_names,
+        serial_number: x509.raw_serial_as_string(),
+        not_before: validity.not_before.timestamp() * 1000,
+        not_after: validity.not_after.timestamp() * 1000,
+        is_ca: x509.is_ca(),
+        fingerprint: fingerprint(&cert.0),
+    })
+}
+
+fn ip_address(bytes: &[u8]) -> Option<IpAddr> {
+    if let Ok(v4) = <[u8; 4]>::try_from(bytes) {
+        return Some(IpAddr::from(v4));
+    }
+    <[u8; 16]>::try_from(bytes).ok().map(IpAddr::from)
+}
+
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+/// 距离证书过期的天数，已过期时为负数
+pub fn days_until_expiry(cert: &TlsCertificateInfo) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    (cert.not_after - now).div_euclid(MILLIS_PER_DAY)
+}
+
+/// 用私钥签名后再用证书中的公钥验签，判断两者是否匹配
+///
+/// 无法判断时返回 None，例如私钥类型不受支持，或证书中的 RSA 公钥不在 webpki 验签支持的 2048 到 8192 位之间
+pub fn key_matches(cert: &Certificate, key: &PrivateKey) -> Option<bool> {
+    let signing_key = match rustls::sign::any_supported_type(key) {
+        Ok(signing_key) => signing_key,
+        Err(e) => {
+            warn!("Unable to check the client private key: {}", e);
+            return None;
+        }
+    };
+    let signer = signing_key.choose_scheme(&KEY_CHECK_SCHEMES)?;
+    let signature = match signer.sign(KEY_CHECK_MESSAGE) {
+        Ok(signature) => signature,
+        Err(e) => {
+            warn!("Failed to sign with client private key: {}", e);
+            return None;
+        }
+    };
+    let algorithm = match signer.scheme() {
+        SignatureScheme::RSA_PKCS1_SHA256 => {
+            if rsa_key_size(cert).is_some_and(|bits| !(2048..=8192).contains(&bits)) {
+                return None;
+            }
+            &webpki::RSA_PKCS1_2048_8192_SHA256
+        }
+        SignatureScheme::ECDSA_NISTP256_SHA256 => &webpki::ECDSA_P256_SHA256,
+        SignatureScheme::ECDSA_NISTP384_SHA384 => &webpki::ECDSA_P384_SHA384,
+        SignatureScheme::ED25519 => &webpki::ED25519,
+        _ => return None,
+    };
+    let cert = webpki::EndEntityCert::try_from(cert.0.as_slice()).ok()?;
+    match cert.verify_signature(algorithm, KEY_CHECK_MESSAGE, &signature) {
+        Ok(_) => Some(true),
+        //  签名不正确，或私钥与证书中公钥的类型不同
+        Err(webpki::Error::InvalidSignatureForPublicKey)
+        | Err(webpki::Error::UnsupportedSignatureAlgorithmForPublicKey) => Some(false),
+        Err(e) => {
+            warn!("Unable to verify the client private key: {:?}", e);
+            None
+        }
+    }
+}
+
+/// 证书中 RSA 公钥的位数，不是 RSA 公钥时为空
+fn rsa_key_size(cert: &Certificate) -> Option<usize> {
+    let (_, x509) = X509Certificate::from_der(&cert.0).ok()?;
+    match x509.public_key().parsed() {
+        Ok(PublicKey::RSA(rsa)) => Some(rsa.key_size()),
+        _ => None,
+    }
+}
+
+/// 配置中非空的 CA 证书，未选择 CA 文件时界面会传入空内容
+fn ca_pems(tls: &ConnectionTls) -> impl Iterator<Item = &Vec<u8>> {
+    tls.cert
+        .iter()
+        .filter(|pem| !pem.iter().all(u8::is_ascii_whitespace))
+}
+
+/// 解析连接配置中的 CA、客户端证书与私钥
+pub fn inspect(tls: &ConnectionTls) -> Result<TlsInspection, LogicError> {
+    let mut ca = vec![];
+    for pem in ca_pems(tls) {
+        for cert in pem_certs(pem)? {
+            ca.push(certificate_info(&cert)?);
+        }
+    }
+
+    let (identity, key_matches) = match &tls.identity {
+        Some(identity) => {
+            let certs = pem_certs(&identity.cert)?;
+            let key = pem_private_key(&identity.key)?;
+            let matches = key_matches(&certs[0], &key);
+            let infos = certs
+                .iter()
+                .map(certificate_info)
+                .collect::<Result<Vec<_>, _>>()?;
+            (infos, matches)
+        }
+        None => (vec![], None),
+    };
+
+    Ok(TlsInspection {
+        ca,
+        identity,
+        key_matches,
+    })
+}
+
+/// 保存与连接前的检查，只有确定私钥与客户端证书不匹配时返回错误
+///
+/// 证书或私钥无法解析、无法判断是否匹配时只记录警告，由 tls 握手给出最终的结果，
+/// 例如只有 OpenSSL 支持的私钥格式。检查失败时返回的检查结果为空
+pub fn validate(tls: &ConnectionTls) -> Result<Option<TlsInspection>, LogicError> {
+    let inspection = match inspect(tls) {
+        Ok(inspection) => inspection,
+        Err(e) => {
+            warn!("Unable to inspect tls certificates: {:?}", e);
+            return Ok(None);
+        }
+    };
+    if inspection.key_matches == Some(false) {
+        return Err(LogicError::MsgError(String::from(
+            "The client private key does not match the client certificate",
+        )));
+    }
+    if inspection.key_matches.is_none() && !inspection.identity.is_empty() {
+        warn!("Unable to check whether the client private key matches the client certificate");
+    }
+    Ok(Some(inspection))
+}
+
+/// tls 握手中服务器提供的证书链
+pub struct PresentedChain {
+    pub chain: Vec<TlsCertificateInfo>,
+    /// 使用连接配置中的 CA 校验失败的原因，校验通过时为空
+    pub error: Option<String>,
+}
+
+/// 记录服务器提供的证书链与校验结果
+struct RecordingVerifier {
+    inner: WebPkiVerifier,
+    chain: Mutex<Vec<Certificate>>,
+    error: Mutex<Option<rustls::Error>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let mut chain = vec![end_entity.clone()];
+        chain.extend_from_slice(intermediates);
+        *self.chain.lock().unwrap() = chain;
+
+        let result = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        );
+        if let Err(e) = &result {
+            *self.error.lock().unwrap() = Some(e.clone());
+        }
+        result
+    }
+}
+
+/// 单独进行一次 tls 握手，获取服务器提供的证书链并使用连接配置中的 CA 校验，不发送客户端证书
+pub async fn presented_chain(
+    host: &str,
+    port: u16,
+    server_name: &str,
+    tls: &ConnectionTls,
+    connect_timeout: Duration,
+) -> Result<PresentedChain, LogicError> {
+    let mut roots = RootCertStore::empty();
+    if ca_pems(tls).next().is_none() {
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+    } else {
+        for pem in ca_pems(tls) {
+            for cert in pem_certs(pem)? {
+                roots.add(&cert).map_err(|e| {
+                    LogicError::MsgError(format!("Invalid certificate authority: {}", e))
+                })?;
+            }
+        }
+    }
+
+    let verifier = Arc::new(RecordingVerifier {
+        inner: WebPkiVerifier::new(roots, None),
+        chain: Mutex::new(vec![]),
+        error: Mutex::new(None),
+    });
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(verifier.clone())
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(server_name).map_err(|e| {
+        LogicError::MsgError(format!("Invalid server name '{}': {}", server_name, e))
+    })?;
+
+    let stream = timeout(connect_timeout, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| io::Error::new(ErrorKind::TimedOut, "TLS probe connection timeout"))??;
+    let connector = TlsConnector::from(Arc::new(config));
+    let _ = timeout(connect_timeout, connector.connect(server_name, stream)).await;
+
+    let chain = verifier
+        .chain
+        .lock()
+        .unwrap()
+        .iter()
+        .map(certificate_info)
+        .collect::<Result<Vec<_>, _>>()?;
+    let error = verifier
+        .error
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|e| e.to_string());
+    Ok(PresentedChain { chain, error })
+}
@@ -5,8 +5,9 @@ import mitt, {Emitter, EventType, Handler} from "mitt";
 import {DialogItem, TipsItem} from "~/common/types.ts";
 import {_relativeTimeFormat} from "~/common/utils.ts";
 import {KeyValue} from "./transport/kv";
-import {KeyMonitorConfig} from "~/common/transport/connection.ts";
+import {KeyMonitorConfig, SshHostKeyInfo, TlsCertificateInfo} from "~/common/transport/connection.ts";
 import {CustomUpdateManifest} from "~/common/updater.ts";
+import {MemberDefragment} from "~/common/transport/maintenance.ts";
 import i18n from "~/language";
 
 const localEvents = mitt();
@@ -28,6 +29,10 @@ export enum EventName {
     KEY_MONITOR_MODIFIED_BY_SERVER = "key_monitor_modified_by_server",
     SET_SETTING_ANCHOR = 'setSettingAnchor',
     SESSION_DISCONNECTED = 'sessionDisconnected',
+    SSH_HOST_KEY_PROMPT = 'sshHostKeyPrompt',
+    SSH_AUTH_PROMPT = 'sshAuthPrompt',
+    SSH_TUNNEL_STATE = 'sshTunnelState',
+    TLS_CERTIFICATE_EXPIRING = 'tlsCertificateExpiring',
     UPDATE_AVAILABLE = 'updateAvailable',
     UPDATE_PENDING = 'updatePending',
     UPDATE_DOWNLOADING_PROGRESS = 'updateDownloadingProgress',
@@ -46,6 +51,22 @@ export enum EventName {
     BATCH_IMPORT_START_EVENT = 'batchImportStartEvent',
     BATCH_IMPORT_END_EVENT = 'batchImportEndEvent',
     BATCH_IMPORT_ERR_EVENT = 'batchImportErrEvent',
+    V2_MIGRATE_EVENT = 'v2MigrateEvent',
+    V2_MIGRATE_START_EVENT = 'v2MigrateStartEvent',
+    V2_MIGRATE_END_EVENT = 'v2MigrateEndEvent',
+    V2_MIGRATE_ERR_EVENT = 'v2MigrateErrEvent',
+    DEFRAGMENT_MEMBER_EVENT = 'defragmentMemberEvent',
+    DEFRAGMENT_MEMBER_START_EVENT = 'defragmentMemberStartEvent',
+    DEFRAGMENT_MEMBER_END_EVENT = 'defragmentMemberEndEvent',
+    DEFRAGMENT_MEMBER_ERR_EVENT = 'defragmentMemberErrEvent',
+    NOSPACE_RECOVER_EVENT = 'nospaceRecoverEvent',
+    NOSPACE_RECOVER_START_EVENT = 'nospaceRecoverStartEvent',
+    NOSPACE_RECOVER_END_EVENT = 'nospaceRecoverEndEvent',
+    NOSPACE_RECOVER_ERR_EVENT = 'nospaceRecoverErrEvent',
+    SNAPSHOT_RESTORE_EVENT = 'snapshotRestoreEvent',
+    SNAPSHOT_RESTORE_START_EVENT = 'snapshotRestoreStartEvent',
+    SNAPSHOT_RESTORE_END_EVENT = 'snapshotRestoreEndEvent',
+    SNAPSHOT_RESTORE_ERR_EVENT = 'snapshotRestoreErrEvent',
 }
 
 export type KeyWatchEventType = "Remove" | "Create" | "Modify"
@@ -72,6 +93,40 @@ export interface SessionDisconnectedEvent {
     case: string | Record<string, string>,
 }
 
+export interface SshHostKeyPromptEvent {
+    requestId: number,
+    hostKey: SshHostKeyInfo,
+}
+
+export interface SshAuthPrompt {
+    prompt: string,
+    echo: boolean,
+}
+
+export type SshTunnelState = 'restored' | 'failed' | { reconnecting: { attempt: number, max: number } }
+
+export interface SshTunnelStateEvent {
+    sessionId: number,
+    state: SshTunnelState,
+}
+
+export interface TlsCertificateExpiringEvent {
+    sessionId: number,
+    certificate: TlsCertificateInfo,
+    //  已过期时为负数
+    daysLeft: number,
+}
+
+export interface SshAuthPromptEvent {
+    requestId: number,
+    host: string,
+    port: number,
+    user: string,
+    name: string,
+    instructions: string,
+    prompts: SshAuthPrompt[],
+}
+
 export interface UpdateDownloadingProgressEvent {
     chunkLength: number,
     contentLength?: number,
@@ -92,6 +147,31 @@ export interface KVBatchExportAndImportEvent {
     failedMsg?: string
 }
 
+export interface MemberDefragmentEvent {
+    memberId: string
+    leader: boolean
+    result?: MemberDefragment
+    success: boolean
+    failedMsg?: string
+}
+
+export type NospaceRecoverStep = 'Compact' | 'Defragment' | 'Disarm'
+
+export interface NospaceRecoverEvent {
+    step: NospaceRecoverStep
+    memberId?: string
+    success: boolean
+    failedMsg?: string
+}
+
+export type SnapshotRestoreStep = 'VerifyHash' | 'RestoreDb' | 'WriteWal' | 'WriteSnap'
+
+export interface SnapshotRestoreEvent {
+    step: SnapshotRestoreStep
+    bucket?: string
+    keys: number
+}
+
 export function _useLocalEvents(): Emitter<Record<EventType, any>> {
     return localEvents
 }
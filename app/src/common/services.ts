@@ -1,10 +1,13 @@
 import {invoke} from "@tauri-apps/api";
-import {Connection, ConnectionInfo, KeyMonitorConfig, SessionData} from "~/common/transport/connection.ts";
-import {Cluster, SnapshotInfo} from "~/common/transport/maintenance.ts";
-import {KeyValue, KVPutResult, LeaseInfo, PutStrategy, SearchResult} from "~/common/transport/kv.ts";
-import {_emitLocal, _tipError, EventName} from "~/common/events.ts";
+import {CompactionPolicy, Connection, ConnectionInfo, ConnectionTls, KeyMonitorConfig, SessionData, SnapshotSchedule, SshHostKeyInfo, TlsCertificateInfo, TlsInspection} from "~/common/transport/connection.ts";
+import {Alarm, AlarmMember, Cluster, ClusterHealth, MaintenanceLog, OfflineSessionData, SnapshotInfo, SnapshotRestoreOptions, SnapshotScheduleState, SnapshotVerifyResult} from "~/common/transport/maintenance.ts";
+import {MemberMetrics, MetricFamily, MetricSeries, SshTunnelStats} from "~/common/transport/metrics.ts";
+import {KeyValue, KVPutResult, LeaseInfo, PutStrategy, SearchResult, V2Node} from "~/common/transport/kv.ts";
+import {_alert, _confirm, _emitLocal, _tipError, _tipSuccess, EventName} from "~/common/events.ts";
 import {LogicErrorInfo} from "~/common/types.ts";
+import {_tlsCertificatesToHtml} from "~/common/utils.ts";
 import {RolePermission, User} from "~/common/transport/user.ts";
+import i18n from "~/language";
 
 export function _handleError(info: LogicErrorInfo) {
     let error = info.e
@@ -12,6 +15,10 @@ export function _handleError(info: LogicErrorInfo) {
 
     if (typeof error === 'string') {
         _tipError((info.prefix ? info.prefix : "") + info.e)
+    } else if (error.errType == "SshHostKeyChanged" && error.data) {
+        handleSshHostKeyChanged(error.data as unknown as SshHostKeyInfo)
+    } else if (error.errType == "TlsHandshakeError" && error.data) {
+        handleTlsHandshakeError(error.errMsg, error.data.chain as TlsCertificateInfo[])
     } else {
         _tipError((info.prefix ? info.prefix : "") + error.errMsg)
         if (error.errType == "Unauthenticated" && info.session) {
@@ -20,6 +27,34 @@ export function _handleError(info: LogicErrorInfo) {
     }
 }
 
+/**
+ * 服务器公钥与已信任的不一致，确认后删除 workbench 中保存的公钥，下次连接时重新确认
+ */
+function handleSshHostKeyChanged(hostKey: SshHostKeyInfo) {
+    const t = i18n.global.t
+    _confirm(t('main.home.sshHostKeyChangedTitle'), t('main.home.sshHostKeyChanged', {
+        host: hostKey.host,
+        port: hostKey.port,
+        algorithm: hostKey.algorithm,
+        fingerprint: hostKey.fingerprint,
+    })).then(() => {
+        _sshKnownHostsRemove(hostKey.host, hostKey.port).then(() => {
+            _tipSuccess(t('main.home.sshHostKeyRemoved'))
+        }).catch(e => {
+            _handleError({e})
+        })
+    }).catch(() => {
+    })
+}
+
+/**
+ * 服务器证书校验失败，展示服务器提供的证书链，便于排查 CA 与域名配置
+ */
+function handleTlsHandshakeError(reason: string, chain: TlsCertificateInfo[]) {
+    const t = i18n.global.t
+    _alert(t('main.home.tlsHandshakeFailed', {reason}) + _tlsCertificatesToHtml(chain), t('main.home.tlsHandshakeFailedTitle'))
+}
+
 export function _getAppVersion(): Promise<string> {
     return invoke('get_app_version')
 }
@@ -43,6 +78,30 @@ export function _disconnect(sessionId: number): Promise<undefined> {
     return invoke('disconnect', {session: sessionId})
 }
 
+export function _inspectTlsCertificates(tls: ConnectionTls): Promise<TlsInspection> {
+    return invoke('inspect_tls_certificates', {tls})
+}
+
+export function _sshHostKeyAnswer(requestId: number, trust: boolean): Promise<undefined> {
+    return invoke('ssh_host_key_answer', {requestId, trust})
+}
+
+export function _sshAuthAnswer(requestId: number, answers?: string[]): Promise<undefined> {
+    return invoke('ssh_auth_answer', {requestId, answers})
+}
+
+export function _sshKnownHostsRemove(host: string, port: number): Promise<number> {
+    return invoke('ssh_known_hosts_remove', {host, port})
+}
+
+export function _openOfflineSession(filepath: string): Promise<OfflineSessionData> {
+    return invoke('offline_session_open', {filepath})
+}
+
+export function _closeOfflineSession(sessionId: number): Promise<undefined> {
+    return invoke('offline_session_close', {session: sessionId})
+}
+
 export function _getConnectionList(): Promise<ConnectionInfo[]> {
     return invoke('get_connection_list')
 }
@@ -74,12 +133,68 @@ export function _defragment(sessionId: number): Promise<undefined> {
     return invoke('maintenance_defragment', {session: sessionId})
 }
 
+export function _defragmentMembers(sessionId: number, memberIds: string[], rolling: boolean): Promise<undefined> {
+    return invoke('maintenance_defragment_members', {session: sessionId, memberIds, rolling})
+}
+
 export function _compact(sessionId: number, revision: number, physical: boolean): Promise<undefined> {
     return invoke('maintenance_compact', {session: sessionId, revision, physical})
 }
 
-export function _metrics(sessionId: number): Promise<Array<string[]>> {
-    return invoke<Array<string[]>>('metrics', {session: sessionId})
+export function _alarmList(sessionId: number): Promise<AlarmMember[]> {
+    return invoke('alarm_list', {session: sessionId})
+}
+
+export function _alarmDisarm(sessionId: number, memberId: string, alarmType: Alarm): Promise<undefined> {
+    return invoke('alarm_disarm', {session: sessionId, memberId, alarmType})
+}
+
+export function _recoverNospace(sessionId: number): Promise<undefined> {
+    return invoke('maintenance_recover_nospace', {session: sessionId})
+}
+
+export function _setCompactionPolicy(sessionId: number, policy?: CompactionPolicy): Promise<undefined> {
+    return invoke('set_compaction_policy', {session: sessionId, policy})
+}
+
+export function _maintenanceLogList(name: string): Promise<MaintenanceLog[]> {
+    return invoke('maintenance_log_list', {name})
+}
+
+export function _snapshotVerify(filepath: string): Promise<SnapshotVerifyResult> {
+    return invoke('snapshot_verify', {filepath})
+}
+
+export function _restoreSnapshot(options: SnapshotRestoreOptions): Promise<undefined> {
+    return invoke('maintenance_restore_snapshot', {options})
+}
+
+export function _setSnapshotSchedule(name: string, schedule?: SnapshotSchedule): Promise<undefined> {
+    return invoke('set_snapshot_schedule', {name, schedule})
+}
+
+export function _snapshotScheduleState(name: string): Promise<SnapshotScheduleState> {
+    return invoke('snapshot_schedule_state', {name})
+}
+
+export function _clusterHealth(sessionId: number, probes?: number): Promise<ClusterHealth> {
+    return invoke<ClusterHealth>('cluster_health', {session: sessionId, probes})
+}
+
+export function _metrics(sessionId: number): Promise<MetricFamily[]> {
+    return invoke<MetricFamily[]>('metrics', {session: sessionId})
+}
+
+export function _metricsMembers(sessionId: number): Promise<MemberMetrics[]> {
+    return invoke<MemberMetrics[]>('metrics_members', {session: sessionId})
+}
+
+export function _tunnelStats(sessionId: number): Promise<SshTunnelStats | null> {
+    return invoke<SshTunnelStats | null>('tunnel_stats', {session: sessionId})
+}
+
+export function _metricsHistory(sessionId: number, names: string[]): Promise<MetricSeries[]> {
+    return invoke<MetricSeries[]>('metrics_history', {session: sessionId, names})
 }
 
 export function _getAllKeys(sessionId: number): Promise<KeyValue[]> {
@@ -382,4 +497,27 @@ export function _kvBatchImport(session: number, targetPath: string, putStrategy:
         putStrategy,
         prefix
     })
+}
+
+export function _kvV2Get(session: number, key: string): Promise<V2Node> {
+    return invoke<V2Node>('kv_v2_get', {
+        session,
+        key
+    })
+}
+
+export function _kvV2Export(session: number, key: string, targetPath: string): Promise<number> {
+    return invoke<number>('kv_v2_export', {
+        session,
+        key,
+        targetPath
+    })
+}
+
+export function _kvV2Migrate(session: number, key: string, prefix: string): Promise<void> {
+    return invoke('kv_v2_migrate', {
+        session,
+        key,
+        prefix
+    })
 }
\ No newline at end of file
@@ -14,6 +14,28 @@ export interface ConnectionTls {
     identity?: TlsIdentity
 }
 
+export interface TlsCertificateInfo {
+    subject: string,
+    issuer: string,
+    //  例如 DNS:etcd.local、IP:127.0.0.1
+    subjectAltNames: string[],
+    serialNumber: string,
+    //  毫秒时间戳
+    notBefore: number,
+    notAfter: number,
+    isCa: boolean,
+    //  SHA-256 指纹
+    fingerprint: string,
+}
+
+export interface TlsInspection {
+    ca: TlsCertificateInfo[],
+    //  客户端证书链，第一个为客户端证书
+    identity: TlsCertificateInfo[],
+    //  未配置客户端证书时为空
+    keyMatches?: boolean,
+}
+
 export type HashAlgorithm = "sha256" | "sha512"
 
 export interface SshPrivateKey {
@@ -24,25 +46,73 @@ export interface SshPrivateKey {
 
 export interface SshIdentity {
     password?: string,
-    key?: SshPrivateKey
+    key?: SshPrivateKey,
+    //  使用本地运行的 ssh-agent 认证
+    agent?: boolean
 }
 
-export interface ConnectionSsh {
+export interface SshJumpHost {
     host: string,
     port: number,
     user: string,
     identity?: SshIdentity
 }
 
+export interface ConnectionSsh {
+    host: string,
+    port: number,
+    user: string,
+    identity?: SshIdentity,
+    //  跳板机，按顺序依次连接
+    jumpHosts?: SshJumpHost[],
+    //  ~/.ssh/config 中的主机别名
+    configHost?: string
+}
+
+export type ProxyProtocol = 'http' | 'socks5'
+
+export interface ConnectionProxy {
+    protocol: ProxyProtocol,
+    host: string,
+    port: number,
+    user?: ConnectionUser
+}
+
+export interface ConnectionK8s {
+    //  为空时使用 $KUBECONFIG 或 ~/.kube/config
+    kubeconfig?: string,
+    context?: string,
+    namespace?: string,
+    pod: string,
+}
+
+export interface SshHostKeyInfo {
+    host: string,
+    port: number,
+    algorithm: string,
+    fingerprint: string,
+}
+
+export type ConnectionTransport = 'grpc' | 'gateway'
+
 export interface Connection {
     host: string,
     port: number,
+    endpoints?: string[],
+    discoverEndpoints?: boolean,
+    discoverySrv?: string,
+    discoverySrvName?: string,
+    transport?: ConnectionTransport,
+    gatewayPrefix?: string,
     namespace?: string,
     user?: ConnectionUser,
     tls?: ConnectionTls,
     ssh?: ConnectionSsh,
+    proxy?: ConnectionProxy,
+    k8s?: ConnectionK8s,
     queryPagination: boolean,
     queryPaginationSize: number,
+    metricsUrl?: string,
 }
 
 export interface ConnectionInfo {
@@ -50,6 +120,8 @@ export interface ConnectionInfo {
     connection: Connection,
     keyCollection: string[],
     keyMonitorList: KeyMonitorConfig[],
+    compactionPolicy?: CompactionPolicy,
+    snapshotSchedule?: SnapshotSchedule,
     default?: boolean
 }
 
@@ -81,6 +153,8 @@ export interface SessionData {
     readAllKeys: boolean,
     queryPagination: boolean,
     queryPaginationSize: number,
+    compactionPolicy?: CompactionPolicy,
+    endpoints: string[],
 }
 
 export interface ErrorPayload {
@@ -96,4 +170,23 @@ export interface KeyMonitorConfig {
     monitorCreate: boolean,
     monitorRemove: boolean,
     paused: boolean,
-}
\ No newline at end of file
+}
+
+/**
+ * 保留策略，revision 为保留的版本数，period 为保留的分钟数
+ */
+export type CompactionRetention = { revision: number } | { period: number }
+
+export interface CompactionPolicy {
+    enabled: boolean,
+    retention: CompactionRetention,
+    intervalMinutes: number,
+    physical: boolean,
+}
+
+export interface SnapshotSchedule {
+    enabled: boolean,
+    folder: string,
+    intervalHours: number,
+    keep: number,
+}
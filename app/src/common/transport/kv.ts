@@ -52,4 +52,15 @@ export interface KVPutResult {
     existVersion?: number,
 }
 
-export type PutStrategy = "Cover" | "Rename" | "AskMerge"
\ No newline at end of file
+export type PutStrategy = "Cover" | "Rename" | "AskMerge"
+
+export interface V2Node {
+    key: string,
+    dir: boolean,
+    value?: string,
+    ttl?: number,
+    expiration?: string,
+    createdIndex: number,
+    modifiedIndex: number,
+    nodes: V2Node[]
+}
\ No newline at end of file
@@ -20,6 +20,11 @@ export interface ClusterMember {
     alarmType: number
 }
 
+export interface AlarmMember {
+    memberId: string,
+    alarmType: Alarm
+}
+
 export interface ClusterStatus {
     version: string,
     dbSizeAllocated: number,
@@ -31,6 +36,14 @@ export interface ClusterStatus {
     errors: string[]
 }
 
+export interface MemberDefragment {
+    memberId: string,
+    dbSizeBefore: number,
+    dbSizeUsedBefore: number,
+    dbSizeAfter: number,
+    dbSizeUsedAfter: number
+}
+
 export interface SnapshotState {
     received: number,
     remain: number,
@@ -48,4 +61,99 @@ export interface SnapshotInfo {
 export interface SnapshotStateEvent {
     id: number,
     state: SnapshotState
-}
\ No newline at end of file
+}
+
+export interface SnapshotMetadata {
+    clusterId: string,
+    memberId: string,
+    revision: number,
+    size: number,
+    sha256: string,
+    time: number
+}
+
+export interface SnapshotVerifyResult {
+    size: number,
+    hashValid: boolean,
+    failedMsg?: string,
+    metadata?: SnapshotMetadata,
+    metadataMatched?: boolean
+}
+
+export interface OfflineSessionData {
+    id: number,
+    filepath: string,
+    revision: number,
+    compactRevision: number,
+    keyCount: number
+}
+
+export interface SnapshotBackupRecord {
+    filepath: string,
+    time: number,
+    duration: number,
+    success: boolean,
+    failedMsg?: string
+}
+
+export interface SnapshotScheduleState {
+    nextRun: number,
+    history: SnapshotBackupRecord[]
+}
+
+export type MaintenanceAction = 'autoCompact'
+
+export interface MaintenanceLog {
+    action: MaintenanceAction,
+    revision: number,
+    duration: number,
+    time: number,
+    success: boolean,
+    failedMsg?: string
+}
+
+export interface SnapshotRestoreOptions {
+    snapshotPath: string,
+    dataDir: string,
+    clusterName: string,
+    memberName: string,
+    peerUrls: string[],
+    initialCluster?: string,
+    skipHashCheck: boolean
+}
+
+export interface SnapshotRestoreResult {
+    dataDir: string,
+    clusterId: string,
+    memberId: string
+}
+
+export interface LatencyStats {
+    samples: number,
+    min: number,
+    max: number,
+    avg: number,
+    p50: number,
+    p90: number,
+    p99: number
+}
+
+export interface MemberHealth {
+    memberId: string,
+    name: string,
+    clientUrls: string[],
+    healthy: boolean,
+    readLatency?: LatencyStats,
+    statusLatency?: LatencyStats,
+    failedProbes: number,
+    leader?: string,
+    raftIndex?: number,
+    dbSize?: number,
+    errors: string[]
+}
+
+export interface ClusterHealth {
+    sshTunnel: boolean,
+    probes: number,
+    members: MemberHealth[]
+}
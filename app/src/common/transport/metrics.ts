@@ -0,0 +1,84 @@
+export type MetricType = 'Counter' | 'Gauge' | 'Histogram' | 'Summary' | 'Untyped'
+
+export interface MetricFamily {
+    name: string,
+    help?: string,
+    metricType: MetricType,
+    metrics: Metric[]
+}
+
+/**
+ * json 无法表示 NaN 与 ±Inf，这些值为 null
+ */
+export interface Metric {
+    labels: Record<string, string>,
+    value?: number | null,
+    histogram?: HistogramValue,
+    summary?: SummaryValue,
+    timestamp?: number
+}
+
+export interface HistogramValue {
+    buckets: HistogramBucket[],
+    sum: number | null,
+    count: number | null
+}
+
+export interface HistogramBucket {
+    // +Inf 桶为 null
+    upperBound: number | null,
+    count: number | null
+}
+
+export interface SummaryValue {
+    quantiles: SummaryQuantile[],
+    sum: number | null,
+    count: number | null
+}
+
+export interface SummaryQuantile {
+    quantile: number | null,
+    value: number | null
+}
+
+export interface MetricPoint {
+    time: number,
+    value: number
+}
+
+export interface MetricSeries {
+    name: string,
+    labels: Record<string, string>,
+    points: MetricPoint[]
+}
+
+export interface MemberMetrics {
+    memberId: string,
+    name: string,
+    url: string,
+    success: boolean,
+    families: MetricFamily[],
+    failedMsg?: string
+}
+
+export interface SshChannelFailure {
+    time: number,
+    target: string,
+    reason: string
+}
+
+export interface SshTunnelStats {
+    sshInfo: string,
+    localPort: number,
+    forwards: number,
+    bytesIn: number,
+    bytesOut: number,
+    activeChannels: number,
+    totalChannels: number,
+    channelOpenFailures: number,
+    recentFailures: SshChannelFailure[],
+    keepaliveRttMillis?: number,
+    reconnects: number,
+    //  共享该SSH会话的连接数
+    sharedConnections: number
+}
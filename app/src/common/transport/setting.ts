@@ -1,5 +1,6 @@
 import {EditorHighlightLanguage} from "~/common/types.ts";
 import {AppLanguage} from "~/language";
+import {ConnectionProxy} from "~/common/transport/connection.ts";
 
 export const MAIN_WINDOW_MIN_HEIGHT = 500;
 export const MAIN_WINDOW_MIN_WIDTH = 900;
@@ -32,6 +33,8 @@ export interface SettingConfig {
     autoUpdate: boolean,
     //  更新源
     updateSource: UpdateSource,
+    //  检查与下载更新时使用的代理
+    networkProxy?: ConnectionProxy,
 
     //  使用 ctrl + w 关闭连接tab
     closeTabUseCtrlW: boolean,
@@ -41,6 +44,12 @@ export interface SettingConfig {
     requestTimeoutSeconds: number | string,
     //  SSH连接超时秒数
     sshConnectTimeoutSeconds: number | string,
+    //  校验SSH公钥时是否读取 ~/.ssh/known_hosts
+    sshReadSystemKnownHosts: boolean,
+    //  SSH会话断开后的最大重连次数，为0时不重连
+    sshReconnectMaxAttempts: number | string,
+    //  客户端证书在该天数内过期时提醒，为0时不提醒
+    tlsCertExpiryWarningDays: number | string,
     //  连接存储加密密钥，bytes字符长度必须为16位
     connectionConfEncryptKey: string,
 }
@@ -69,6 +78,9 @@ export const DEFAULT_SETTING_CONFIG: SettingConfig = {
     connectTimeoutSeconds: 5,
     requestTimeoutSeconds: 15,
     sshConnectTimeoutSeconds: 10,
+    sshReadSystemKnownHosts: true,
+    sshReconnectMaxAttempts: 5,
+    tlsCertExpiryWarningDays: 30,
     connectionConfEncryptKey: 'workbench*#)&%.$'
 }
 
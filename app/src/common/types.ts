@@ -1,4 +1,4 @@
-import {ErrorPayload, HashAlgorithm, SessionData} from "~/common/transport/connection.ts";
+import {ConnectionTransport, ErrorPayload, HashAlgorithm, ProxyProtocol, SessionData} from "~/common/transport/connection.ts";
 
 export type EditorHighlightLanguage = EditorSupportedHighlightLanguage | EditorNotSupportedHighlightLanguage
 
@@ -63,10 +63,19 @@ export type ConnectionForm = {
     name: string,
     host: string,
     port: string,
+    endpoints: string,
+    discoverEndpoints: boolean,
+    discoverySrv: string,
+    discoverySrvName: string,
+    transport: ConnectionTransport,
+    gatewayPrefix: string,
     namespace: string,
+    metricsUrl: string,
     user: ConnectionUserForm,
     tls: ConnectionTlsForm,
     ssh: ConnectionSshForm,
+    proxy: ConnectionProxyForm,
+    k8s: ConnectionK8sForm,
     queryPagination: boolean,
     queryPaginationSize: string,
 }
@@ -97,24 +106,56 @@ export type ConnectionSshKey = {
 }
 
 export type ConnectionSshIdentity = {
-    model: 'password' | 'key' | 'none',
+    model: 'password' | 'key' | 'agent' | 'none',
     password: string,
     key: ConnectionSshKey
 }
 
+export type ConnectionSshJumpForm = {
+    host: string,
+    port: string,
+    user: string,
+    identity: ConnectionSshIdentity,
+}
+
 export type ConnectionSshForm = {
     enable: boolean,
+    configHost: string,
     host: string,
     port: string,
     user: string,
     identity: ConnectionSshIdentity,
+    jumpHosts: ConnectionSshJumpForm[],
+}
+
+export type ConnectionProxyForm = {
+    enable: boolean,
+    protocol: ProxyProtocol,
+    host: string,
+    port: string,
+    user: ConnectionUserForm,
+}
+
+export type ConnectionK8sForm = {
+    enable: boolean,
+    kubeconfig: string,
+    context: string,
+    namespace: string,
+    pod: string,
 }
 
 export const DefaultConnection: ConnectionForm = {
     name: '',
     host: '',
     port: '2379',
+    endpoints: '',
+    discoverEndpoints: false,
+    discoverySrv: '',
+    discoverySrvName: '',
+    transport: 'grpc',
+    gatewayPrefix: '',
     namespace: '',
+    metricsUrl: '',
     user: {
         enable: false,
         username: '',
@@ -141,6 +182,7 @@ export const DefaultConnection: ConnectionForm = {
     },
     ssh: {
         enable: false,
+        configHost: '',
         host: '',
         port: '22',
         user: '',
@@ -155,8 +197,27 @@ export const DefaultConnection: ConnectionForm = {
                 passphrase: '',
                 hashAlgorithm: ''
             }
+        },
+        jumpHosts: []
+    },
+    proxy: {
+        enable: false,
+        protocol: 'http',
+        host: '',
+        port: '1080',
+        user: {
+            enable: false,
+            username: '',
+            password: ''
         }
     },
+    k8s: {
+        enable: false,
+        kubeconfig: '',
+        context: '',
+        namespace: '',
+        pod: '',
+    },
     queryPagination: true,
     queryPaginationSize: '2000',
 }
@@ -3,6 +3,8 @@ import {_alertError} from "~/common/events.ts";
 import {EditorHighlightLanguage} from "~/common/types.ts";
 import {_useGlobalStore} from "~/common/store.ts";
 import {FormattedValue} from "~/common/transport/kv.ts";
+import {MetricFamily} from "~/common/transport/metrics.ts";
+import {TlsCertificateInfo} from "~/common/transport/connection.ts";
 import i18n from "~/language";
 
 const TEXT_DECODER = new TextDecoder();
@@ -344,4 +346,80 @@ export function arraysEqual(a: any[], b: any[]): boolean {
         if (a[i] !== b[i]) return false;
     }
     return true;
-}
\ No newline at end of file
+}
+
+function _formatSampleName(name: string, labels: Record<string, string>, extra?: [string, string]): string {
+    const pairs = Object.entries(labels)
+    if (extra) {
+        pairs.push(extra)
+    }
+    if (pairs.length == 0) {
+        return name
+    }
+    return `${name}{${pairs.map(([k, v]) => `${k}="${v}"`).join(',')}}`
+}
+
+function formatValue(value?: number | null): string {
+    return value === null || value === undefined ? 'NaN' : String(value)
+}
+
+/**
+ * 将指标族展开为文本格式中的样本行，用于列表展示
+ */
+export function _flattenMetricFamilies(families: MetricFamily[]): Array<string[]> {
+    const result: Array<string[]> = []
+    for (const family of families) {
+        for (const metric of family.metrics) {
+            if (metric.histogram) {
+                for (const bucket of metric.histogram.buckets) {
+                    const le = bucket.upperBound === null ? '+Inf' : String(bucket.upperBound)
+                    result.push([formatSampleName(`${family.name}_bucket`, metric.labels, ['le', le]), formatValue(bucket.count)])
+                }
+                result.push([formatSampleName(`${family.name}_sum`, metric.labels), formatValue(metric.histogram.sum)])
+                result.push([formatSampleName(`${family.name}_count`, metric.labels), formatValue(metric.histogram.count)])
+            } else if (metric.summary) {
+                for (const q of metric.summary.quantiles) {
+                    result.push([formatSampleName(family.name, metric.labels, ['quantile', formatValue(q.quantile)]), formatValue(q.value)])
+                }
+                result.push([formatSampleName(`${family.name}_sum`, metric.labels), formatValue(metric.summary.sum)])
+                result.push([formatSampleName(`${family.name}_count`, metric.labels), formatValue(metric.summary.count)])
+            } else {
+                result.push([formatSampleName(family.name, metric.labels), formatValue(metric.value)])
+            }
+        }
+    }
+    return result
+}
+
+function escapeHtml(str: string): string {
+    return str.replace(/&/g, '&amp;')
+        .replace(/</g, '&lt;')
+        .replace(/>/g, '&gt;')
+        .replace(/"/g, '&quot;')
+}
+
+/**
+ * 证书信息转为 html，用于在对话框中展示
+ */
+export function _tlsCertificatesToHtml(certs: TlsCertificateInfo[]): string {
+    const t = i18n.global.t
+    return certs.map(cert => {
+        let rows = [
+            [t('main.home.tlsCertSubject'), cert.subject],
+            [t('main.home.tlsCertIssuer'), cert.issuer],
+        ]
+        if (cert.subjectAltNames.length > 0) {
+            rows.push([t('main.home.tlsCertSubjectAltNames'), cert.subjectAltNames.join(', ')])
+        }
+        rows.push(
+            [t('main.home.tlsCertValidity'), `${_timeFormat(cert.notBefore)} ~ ${_timeFormat(cert.notAfter)}`],
+            [t('main.home.tlsCertSerialNumber'), cert.serialNumber],
+            [t('main.home.tlsCertFingerprint'), cert.fingerprint],
+        )
+        let html = rows.map(([label, value]) => `<strong>${label}</strong>: ${escapeHtml(value)}`).join('<br/>')
+        if (cert.isCa) {
+            html += `<br/><em>${t('main.home.tlsCertIsCa')}</em>`
+        }
+        return `<p class="mt-2">${html}</p>`
+    }).join('')
+}